@@ -2,12 +2,14 @@ use crate::{
     settings::Settings,
     storage,
     ui::{
-        password_manager_ui::PasswordManager, settings_ui::SettingsWindow,
-        theme_creator_ui::ThemeCreator, vault_creator_ui::VaultCreator,
+        generator_ui::PasswordGenerator, password_manager_ui::PasswordManager,
+        settings_ui::SettingsWindow, theme_creator_ui::ThemeCreator,
+        vault_creator_ui::VaultCreator,
     },
 };
 use eframe::egui::{self, Widget};
 use std::path::PathBuf;
+use zeroize::Zeroize;
 
 pub struct PasswordApp {
     // Состояние приложения
@@ -112,6 +114,19 @@ impl eframe::App for PasswordApp {
                 if !self.password_manager.ui(ui) {
                     self.logout();
                 }
+
+                // Параметры и профили генератора сохраняем в настройках
+                if let Some((options, profiles)) =
+                    self.password_manager.generator.take_settings_changes()
+                    && let Err(e) = self.settings_window.update_generator(options, profiles)
+                {
+                    self.error_message = e;
+                }
+                // Требования к мастер-паролю могут поменяться в настройках
+                self.password_manager.change_master.input.policy = self
+                    .settings_window
+                    .get_current_settings()
+                    .master_password_policy;
             }
         });
 
@@ -188,6 +203,11 @@ impl eframe::App for PasswordApp {
             }
         }
     }
+
+    // При закрытии окна дописываем отложенные изменения открытого хранилища
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.password_manager.flush_pending();
+    }
 }
 
 impl PasswordApp {
@@ -236,8 +256,11 @@ impl PasswordApp {
             if ui.button("➕ Создать новое").clicked() {
                 self.vault_creator.show = true;
                 self.vault_creator.vault_name.clear();
-                self.vault_creator.master_password.clear();
-                self.vault_creator.confirm_password.clear();
+                self.vault_creator.master.zeroize();
+                self.vault_creator.master.policy = self
+                    .settings_window
+                    .get_current_settings()
+                    .master_password_policy;
                 self.vault_creator.clear_messages();
             }
 
@@ -271,16 +294,27 @@ impl PasswordApp {
         if let Some(vault_path) = &self.selected_vault_path {
             match storage::load_vault_from_path(vault_path) {
                 Ok(vault) => {
-                    match vault.decrypt_entries(&self.master_password) {
-                        Ok(entries) => {
+                    match vault.decrypt(&self.master_password) {
+                        Ok(data) => {
                             self.password_manager = PasswordManager::new();
+                            let settings = self.settings_window.get_current_settings();
+                            self.password_manager.generator = PasswordGenerator::new(
+                                settings.generator_options.clone(),
+                                settings.generator_profiles.clone(),
+                            );
                             // Инициализируем менеджер паролей
                             self.password_manager.set_vault(
                                 vault,
-                                entries,
+                                data,
                                 self.master_password.clone(),
                                 vault_path.clone(),
                             );
+                            // Удаляем из корзины устаревшие записи
+                            self.password_manager.purge_trash(
+                                self.settings_window
+                                    .get_current_settings()
+                                    .trash_retention_days,
+                            );
                             self.state = AppState::Unlocked;
                             self.master_password.clear();
                         }
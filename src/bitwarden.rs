@@ -0,0 +1,693 @@
+// Экспорт Bitwarden в JSON: обычный и защищённый паролем.
+// Защищённый файл: соль и параметры KDF открыто, данные — строка "2.iv|шифротекст|mac".
+// Ключ: PBKDF2-SHA256 или Argon2id от пароля, затем HKDF-Expand даёт ключи AES-256-CBC и HMAC-SHA256.
+// Папки, адреса, 2FA, доп. поля, карты, личности, заметки и SSH-ключи раскладываются в записи;
+// всё, что перенести нельзя, попадает в замечания
+use crate::time;
+use crate::totp::OtpConfig;
+use crate::vault::{
+    self, CustomField, Entry, OldPassword, TAG_CARD, TAG_IDENTITY, TAG_NOTE, TAG_SSH_KEY,
+};
+use aes::Aes256;
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, KeyIvInit};
+use base64::{Engine as _, engine::general_purpose};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use zeroize::Zeroize;
+
+// Функции вывода ключа в поле kdfType
+const KDF_PBKDF2: u32 = 0;
+const KDF_ARGON2ID: u32 = 1;
+
+// Типы записей Bitwarden
+const TYPE_LOGIN: u32 = 1;
+const TYPE_SECURE_NOTE: u32 = 2;
+const TYPE_CARD: u32 = 3;
+const TYPE_IDENTITY: u32 = 4;
+const TYPE_SSH_KEY: u32 = 5;
+
+// Типы дополнительных полей
+const FIELD_TEXT: u32 = 0;
+const FIELD_HIDDEN: u32 = 1;
+const FIELD_BOOLEAN: u32 = 2;
+const FIELD_LINKED: u32 = 3;
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Export {
+    encrypted: bool,
+    password_protected: bool,
+    salt: String,
+    kdf_type: u32,
+    kdf_iterations: u32,
+    kdf_memory: Option<u32>,
+    kdf_parallelism: Option<u32>,
+    #[serde(rename = "encKeyValidation_DO_NOT_EDIT")]
+    enc_key_validation: String,
+    data: String,
+
+    folders: Vec<Folder>,
+    collections: Vec<Folder>, // экспорт организации вместо папок содержит коллекции
+    items: Option<Vec<Item>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Item {
+    #[serde(rename = "type")]
+    kind: u32,
+    name: String,
+    notes: Option<String>,
+    favorite: bool,
+    reprompt: u32,
+    folder_id: Option<String>,
+    collection_ids: Option<Vec<String>>,
+    fields: Option<Vec<Field>>,
+    password_history: Option<Vec<HistoryItem>>,
+    creation_date: Option<String>,
+    revision_date: Option<String>,
+    deleted_date: Option<String>,
+
+    login: Option<Login>,
+    card: Option<Card>,
+    identity: Option<Identity>,
+    ssh_key: Option<SshKey>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    uris: Option<Vec<Uri>>,
+    password_revision_date: Option<String>,
+    fido2_credentials: Option<Vec<serde::de::IgnoredAny>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Uri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Field {
+    name: Option<String>,
+    value: Option<String>,
+    #[serde(rename = "type")]
+    kind: u32,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct HistoryItem {
+    password: String,
+    last_used_date: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Card {
+    cardholder_name: Option<String>,
+    brand: Option<String>,
+    number: Option<String>,
+    exp_month: Option<String>,
+    exp_year: Option<String>,
+    code: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Identity {
+    title: Option<String>,
+    first_name: Option<String>,
+    middle_name: Option<String>,
+    last_name: Option<String>,
+    address1: Option<String>,
+    address2: Option<String>,
+    address3: Option<String>,
+    city: Option<String>,
+    state: Option<String>,
+    postal_code: Option<String>,
+    country: Option<String>,
+    company: Option<String>,
+    email: Option<String>,
+    phone: Option<String>,
+    ssn: Option<String>,
+    username: Option<String>,
+    passport_number: Option<String>,
+    license_number: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct SshKey {
+    private_key: Option<String>,
+    public_key: Option<String>,
+    key_fingerprint: Option<String>,
+}
+
+fn parse(text: &str) -> Result<Export, String> {
+    let export: Export = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Это не экспорт Bitwarden в JSON: {}", e))?;
+    if export.encrypted && !export.password_protected {
+        return Err(
+            "Файл зашифрован ключом учётной записи Bitwarden и открывается только в ней. \
+                    Экспортируйте хранилище заново без шифрования или с защитой паролем"
+                .to_string(),
+        );
+    }
+    if !export.password_protected && export.items.is_none() {
+        return Err("Это не экспорт Bitwarden в JSON: нет списка записей".to_string());
+    }
+    Ok(export)
+}
+
+// Нужен ли пароль, чтобы открыть экспорт
+pub fn is_password_protected(text: &str) -> Result<bool, String> {
+    parse(text).map(|export| export.password_protected)
+}
+
+// Записи и замечания из экспорта; пароль нужен только защищённому файлу
+pub fn read(text: &str, password: &str) -> Result<(Vec<Entry>, Vec<String>), String> {
+    let export = parse(text)?;
+    if !export.password_protected {
+        return Ok(entries(export));
+    }
+
+    let (mut enc_key, mut mac_key) = derive_keys(&export, password)?;
+    let result = decrypt(&export.enc_key_validation, &enc_key, &mac_key)
+        .map_err(|_| "Неверный пароль или файл повреждён".to_string())
+        .and_then(|mut validation| {
+            validation.zeroize();
+            decrypt(&export.data, &enc_key, &mac_key)
+        });
+    enc_key.zeroize();
+    mac_key.zeroize();
+
+    let mut data = String::from_utf8(result?).map_err(|_| "Данные повреждены".to_string())?;
+    let inner = parse(&data);
+    data.zeroize();
+    let inner = inner?;
+    if inner.encrypted {
+        return Err("Внутри защищённого файла снова зашифрованные данные".to_string());
+    }
+    Ok(entries(inner))
+}
+
+// Ключ из пароля и соли, растянутый до ключей шифрования и подписи
+fn derive_keys(export: &Export, password: &str) -> Result<([u8; 32], [u8; 32]), String> {
+    let mut key = [0u8; 32];
+    match export.kdf_type {
+        KDF_PBKDF2 => {
+            if export.kdf_iterations == 0 {
+                return Err("Не указано число итераций PBKDF2".to_string());
+            }
+            pbkdf2::pbkdf2_hmac::<Sha256>(
+                password.as_bytes(),
+                export.salt.as_bytes(),
+                export.kdf_iterations,
+                &mut key,
+            );
+        }
+        KDF_ARGON2ID => {
+            // Память указана в МиБ, соль — SHA-256 от строки соли
+            let memory = export.kdf_memory.unwrap_or(64).saturating_mul(1024);
+            let params = argon2::Params::new(
+                memory,
+                export.kdf_iterations,
+                export.kdf_parallelism.unwrap_or(4),
+                Some(key.len()),
+            )
+            .map_err(|e| format!("Неверные параметры Argon2: {}", e))?;
+            let salt = Sha256::digest(export.salt.as_bytes());
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                .hash_password_into(password.as_bytes(), &salt, &mut key)
+                .map_err(|e| format!("Ошибка Argon2: {}", e))?;
+        }
+        other => return Err(format!("Неизвестная функция вывода ключа: {}", other)),
+    }
+    let keys = (hkdf_expand(&key, b"enc"), hkdf_expand(&key, b"mac"));
+    key.zeroize();
+    Ok(keys)
+}
+
+// HKDF-Expand с SHA-256 для ключа длиной в один блок
+fn hkdf_expand(key: &[u8], info: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC принимает любой ключ");
+    mac.update(info);
+    mac.update(&[1]);
+    mac.finalize().into_bytes().into()
+}
+
+// Строка "2.iv|шифротекст|mac" в base64: проверяем HMAC от iv и шифротекста, затем AES-256-CBC
+fn decrypt(value: &str, enc_key: &[u8; 32], mac_key: &[u8; 32]) -> Result<Vec<u8>, String> {
+    let invalid = || "Повреждённые зашифрованные данные".to_string();
+    let parts = value
+        .strip_prefix("2.")
+        .ok_or_else(|| "Неподдерживаемый тип шифрования".to_string())?;
+    let decoded: Vec<Vec<u8>> = parts
+        .split('|')
+        .map(|part| general_purpose::STANDARD.decode(part))
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+    let [iv, data, tag] = decoded.as_slice() else {
+        return Err(invalid());
+    };
+
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(mac_key).expect("HMAC принимает любой ключ");
+    mac.update(iv);
+    mac.update(data);
+    mac.verify_slice(tag)
+        .map_err(|_| "Неверный пароль или файл повреждён".to_string())?;
+
+    cbc::Decryptor::<Aes256>::new_from_slices(enc_key, iv)
+        .map_err(|_| invalid())?
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map_err(|_| invalid())
+}
+
+// Непустое значение необязательного поля
+fn text(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn push_field(entry: &mut Entry, name: &str, value: &Option<String>, protected: bool) {
+    if let Some(value) = text(value) {
+        entry.fields.push(CustomField {
+            name: name.to_string(),
+            value: value.to_string(),
+            protected,
+        });
+    }
+}
+
+fn entries(mut export: Export) -> (Vec<Entry>, Vec<String>) {
+    let folders: HashMap<&str, String> = export
+        .folders
+        .iter()
+        .chain(&export.collections)
+        .map(|folder| (folder.id.as_str(), vault::normalize_folder(&folder.name)))
+        .collect();
+
+    let mut entries = Vec::new();
+    let mut warnings = Vec::new();
+    for item in export.items.iter().flatten() {
+        if item.deleted_date.is_some() {
+            warnings.push(format!("«{}»: запись в корзине — пропущена", item.name));
+            continue;
+        }
+        let mut entry = Entry {
+            service: item.name.clone(),
+            notes: item.notes.clone().unwrap_or_default(),
+            favorite: item.favorite,
+            created_at: item.creation_date.as_deref().and_then(time::parse_iso_time),
+            modified_at: item.revision_date.as_deref().and_then(time::parse_iso_time),
+            ..Entry::default()
+        };
+        entry.password_changed_at = entry.created_at;
+
+        // Папка личного хранилища или коллекция организации
+        let collections = item.collection_ids.as_deref().unwrap_or_default();
+        let folder = item
+            .folder_id
+            .as_deref()
+            .or(collections.first().map(String::as_str));
+        if let Some(folder) = folder.and_then(|id| folders.get(id)) {
+            entry.folder = folder.clone();
+        }
+        if item.folder_id.is_none() && collections.len() > 1 {
+            warnings.push(format!(
+                "«{}»: запись входит в несколько коллекций, перенесена в папку первой",
+                item.name
+            ));
+        }
+
+        match item.kind {
+            TYPE_LOGIN => {
+                if let Some(login) = &item.login {
+                    apply_login(&mut entry, login, &mut warnings);
+                }
+            }
+            TYPE_SECURE_NOTE => entry.tags.push(TAG_NOTE.to_string()),
+            TYPE_CARD => {
+                entry.tags.push(TAG_CARD.to_string());
+                if let Some(card) = &item.card {
+                    apply_card(&mut entry, card);
+                }
+            }
+            TYPE_IDENTITY => {
+                entry.tags.push(TAG_IDENTITY.to_string());
+                if let Some(identity) = &item.identity {
+                    apply_identity(&mut entry, identity);
+                }
+            }
+            TYPE_SSH_KEY => {
+                entry.tags.push(TAG_SSH_KEY.to_string());
+                if let Some(key) = &item.ssh_key {
+                    push_field(&mut entry, "Закрытый ключ", &key.private_key, true);
+                    push_field(&mut entry, "Открытый ключ", &key.public_key, false);
+                    push_field(&mut entry, "Отпечаток", &key.key_fingerprint, false);
+                }
+            }
+            other => warnings.push(format!(
+                "«{}»: неизвестный тип записи {}, перенесены только название и заметки",
+                item.name, other
+            )),
+        }
+
+        for field in item.fields.as_deref().unwrap_or_default() {
+            let name = text(&field.name).unwrap_or("Поле");
+            match field.kind {
+                FIELD_TEXT | FIELD_BOOLEAN => push_field(&mut entry, name, &field.value, false),
+                FIELD_HIDDEN => push_field(&mut entry, name, &field.value, true),
+                FIELD_LINKED => warnings.push(format!(
+                    "«{}»: связанное поле «{}» не переносится",
+                    item.name, name
+                )),
+                other => warnings.push(format!(
+                    "«{}»: поле «{}» неизвестного типа {} пропущено",
+                    item.name, name, other
+                )),
+            }
+        }
+
+        // Bitwarden хранит историю от новых к старым
+        let mut history: Vec<OldPassword> = item
+            .password_history
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .filter(|old| !old.password.is_empty())
+            .map(|old| OldPassword {
+                password: old.password.clone(),
+                replaced_at: old.last_used_date.as_deref().and_then(time::parse_iso_time),
+            })
+            .collect();
+        history.sort_by_key(|old| old.replaced_at);
+        let excess = history.len().saturating_sub(vault::PASSWORD_HISTORY_LIMIT);
+        history.drain(..excess).for_each(|mut old| old.zeroize());
+        if let Some(last) = history.last() {
+            entry.password_changed_at = entry.password_changed_at.max(last.replaced_at);
+        }
+        entry.password_history = history;
+        if let Some(changed) = item
+            .login
+            .as_ref()
+            .and_then(|login| login.password_revision_date.as_deref())
+            .and_then(time::parse_iso_time)
+        {
+            entry.password_changed_at = Some(changed);
+        }
+
+        if item.reprompt != 0 {
+            warnings.push(format!(
+                "«{}»: повторный запрос мастер-пароля не поддерживается",
+                item.name
+            ));
+        }
+        entries.push(entry);
+    }
+    wipe(&mut export);
+    (entries, warnings)
+}
+
+fn apply_login(entry: &mut Entry, login: &Login, warnings: &mut Vec<String>) {
+    entry.login = login.username.clone().unwrap_or_default();
+    entry.password = login.password.clone().unwrap_or_default();
+
+    // Первый адрес — основной, остальные в доп. поля
+    let uris: Vec<&str> = login
+        .uris
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|uri| text(&uri.uri))
+        .collect();
+    for (i, uri) in uris.iter().enumerate() {
+        if i == 0 {
+            entry.url = uri.to_string();
+        } else {
+            entry.fields.push(CustomField {
+                name: format!("URL {}", i + 1),
+                value: uri.to_string(),
+                protected: false,
+            });
+        }
+    }
+
+    if let Some(totp) = text(&login.totp) {
+        let config = if totp.to_ascii_lowercase().starts_with("steam://") {
+            Err("коды Steam не поддерживаются".to_string())
+        } else {
+            OtpConfig::parse(totp).and_then(|config| config.validate().map(|_| config))
+        };
+        match config {
+            Ok(config) => entry.otp = Some(config),
+            Err(e) => {
+                warnings.push(format!(
+                    "«{}»: 2FA не распознан ({}), секрет сохранён в доп. поле",
+                    entry.service, e
+                ));
+                entry.fields.push(CustomField {
+                    name: "TOTP".to_string(),
+                    value: totp.to_string(),
+                    protected: true,
+                });
+            }
+        }
+    }
+
+    let passkeys = login.fido2_credentials.as_ref().map_or(0, Vec::len);
+    if passkeys > 0 {
+        warnings.push(format!(
+            "«{}»: ключи доступа (passkey) не переносятся: {}",
+            entry.service, passkeys
+        ));
+    }
+}
+
+fn apply_card(entry: &mut Entry, card: &Card) {
+    push_field(entry, "Владелец карты", &card.cardholder_name, false);
+    push_field(entry, "Платёжная система", &card.brand, false);
+    push_field(entry, "Номер карты", &card.number, true);
+    let expiry = match (text(&card.exp_month), text(&card.exp_year)) {
+        (Some(month), Some(year)) => Some(format!("{:0>2}/{}", month, year)),
+        (month, year) => month.or(year).map(str::to_string),
+    };
+    push_field(entry, "Срок действия", &expiry, false);
+    push_field(entry, "Код безопасности", &card.code, true);
+}
+
+fn apply_identity(entry: &mut Entry, identity: &Identity) {
+    entry.login = identity.username.clone().unwrap_or_default();
+    let name = [
+        &identity.title,
+        &identity.first_name,
+        &identity.middle_name,
+        &identity.last_name,
+    ]
+    .into_iter()
+    .filter_map(text)
+    .collect::<Vec<_>>()
+    .join(" ");
+    let address = [
+        &identity.address1,
+        &identity.address2,
+        &identity.address3,
+        &identity.postal_code,
+        &identity.city,
+        &identity.state,
+        &identity.country,
+    ]
+    .into_iter()
+    .filter_map(text)
+    .collect::<Vec<_>>()
+    .join(", ");
+    push_field(entry, "Имя", &Some(name), false);
+    push_field(entry, "Организация", &identity.company, false);
+    push_field(entry, "Эл. почта", &identity.email, false);
+    push_field(entry, "Телефон", &identity.phone, false);
+    push_field(entry, "Адрес", &Some(address), false);
+    push_field(entry, "SSN", &identity.ssn, true);
+    push_field(entry, "Номер паспорта", &identity.passport_number, true);
+    push_field(
+        entry,
+        "Номер водительских прав",
+        &identity.license_number,
+        true,
+    );
+}
+
+// Секреты из разобранного файла больше не нужны
+fn wipe(export: &mut Export) {
+    for item in export.items.iter_mut().flatten() {
+        item.notes.zeroize();
+        if let Some(login) = item.login.as_mut() {
+            login.password.zeroize();
+            login.totp.zeroize();
+        }
+        for field in item.fields.iter_mut().flatten() {
+            field.value.zeroize();
+        }
+        for old in item.password_history.iter_mut().flatten() {
+            old.password.zeroize();
+        }
+        if let Some(card) = item.card.as_mut() {
+            card.number.zeroize();
+            card.code.zeroize();
+        }
+        if let Some(identity) = item.identity.as_mut() {
+            identity.ssn.zeroize();
+            identity.passport_number.zeroize();
+            identity.license_number.zeroize();
+        }
+        if let Some(key) = item.ssh_key.as_mut() {
+            key.private_key.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = format!(
+            "{}/tests/fixtures/bitwarden/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+    }
+
+    fn find<'a>(entries: &'a [Entry], service: &str) -> &'a Entry {
+        entries
+            .iter()
+            .find(|entry| entry.service == service)
+            .unwrap_or_else(|| panic!("нет записи «{}»", service))
+    }
+
+    fn field<'a>(entry: &'a Entry, name: &str) -> &'a CustomField {
+        entry
+            .fields
+            .iter()
+            .find(|field| field.name == name)
+            .unwrap_or_else(|| panic!("«{}»: нет поля «{}»", entry.service, name))
+    }
+
+    #[test]
+    fn plain_export() {
+        let text = fixture("plain.json");
+        assert!(!is_password_protected(&text).unwrap());
+        let (entries, warnings) = read(&text, "").unwrap();
+        assert_eq!(entries.len(), 7);
+
+        let mail = find(&entries, "Corporate mail");
+        assert_eq!(mail.folder, "Work/Mail");
+        assert_eq!(mail.login, "alice@example.com");
+        assert_eq!(mail.password, "correct horse battery staple");
+        assert_eq!(mail.url, "https://mail.example.com");
+        assert_eq!(field(mail, "URL 2").value, "https://webmail.example.com");
+        assert_eq!(mail.notes, "Shared with the ops team");
+        assert!(mail.favorite);
+        let otp = mail.otp.as_ref().unwrap();
+        assert_eq!(otp.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(otp.digits, 8);
+        assert_eq!(field(mail, "Department").value, "Ops");
+        assert!(field(mail, "PIN").protected);
+        assert_eq!(field(mail, "VPN only").value, "true");
+        let history: Vec<&str> = mail
+            .password_history
+            .iter()
+            .map(|old| old.password.as_str())
+            .collect();
+        assert_eq!(history, ["old-secret-1", "old-secret-2"]);
+        assert_eq!(
+            mail.created_at,
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_705_314_030))
+        );
+
+        assert!(find(&entries, "Forum").otp.is_some());
+        let launcher = find(&entries, "Game launcher");
+        assert!(launcher.otp.is_none());
+        assert!(field(launcher, "TOTP").protected);
+
+        let note = find(&entries, "Wi-Fi at home");
+        assert_eq!(note.tags, [TAG_NOTE]);
+        assert!(note.notes.contains("12345678"));
+
+        let card = find(&entries, "Visa");
+        assert_eq!(card.folder, "Finance");
+        assert_eq!(card.tags, [TAG_CARD]);
+        assert_eq!(field(card, "Номер карты").value, "4111111111111111");
+        assert_eq!(field(card, "Срок действия").value, "07/2029");
+        assert!(field(card, "Код безопасности").protected);
+
+        let identity = find(&entries, "Passport");
+        assert_eq!(identity.login, "asmith");
+        assert_eq!(field(identity, "Имя").value, "Ms Alice Smith");
+        assert_eq!(
+            field(identity, "Адрес").value,
+            "1 Main St, 12345, Springfield, US"
+        );
+        assert!(field(identity, "Номер паспорта").protected);
+
+        let key = find(&entries, "Deploy key");
+        assert_eq!(key.tags, [TAG_SSH_KEY]);
+        assert!(field(key, "Закрытый ключ").protected);
+
+        // Непереносимое попадает в замечания
+        for expected in ["связанное поле", "passkey", "Steam", "повторный запрос"]
+        {
+            assert!(
+                warnings.iter().any(|warning| warning.contains(expected)),
+                "{}: {:?}",
+                expected,
+                warnings
+            );
+        }
+        assert_eq!(warnings.len(), 4, "{:?}", warnings);
+    }
+
+    #[test]
+    fn password_protected_exports() {
+        let (plain, _) = read(&fixture("plain.json"), "").unwrap();
+        for name in ["encrypted_pbkdf2.json", "encrypted_argon2id.json"] {
+            let text = fixture(name);
+            assert!(is_password_protected(&text).unwrap());
+            let (entries, _) =
+                read(&text, "correct password").unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(entries.len(), plain.len(), "{}", name);
+            for (entry, expected) in entries.iter().zip(&plain) {
+                assert_eq!(entry.service, expected.service, "{}", name);
+                assert_eq!(entry.password, expected.password, "{}", name);
+                assert_eq!(entry.fields, expected.fields, "{}", name);
+            }
+            assert!(read(&text, "wrong password").is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn account_restricted_export() {
+        let text = r#"{"encrypted": true, "encKeyValidation_DO_NOT_EDIT": "2.AAAA|AAAA|AAAA", "data": "2.AAAA|AAAA|AAAA"}"#;
+        assert!(is_password_protected(text).is_err());
+        assert!(read(r#"{"folders": []}"#, "").is_err());
+    }
+}
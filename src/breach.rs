@@ -0,0 +1,529 @@
+// Офлайн-проверка паролей по базе утечек Have I Been Pwned.
+//
+// Дамп HIBP (строки "ХЕШ:КОЛИЧЕСТВО", отсортированные по хешу) занимает десятки
+// гигабайт, поэтому из него строится компактный фильтр — Golomb-coded set:
+// первые 64 бита каждого хеша отображаются в диапазон count × 2^RICE_BITS,
+// а разности соседних значений записываются кодом Райса. Фильтр строится
+// потоково за два прохода по дампу, а при проверке в памяти держится только
+// небольшой индекс блоков — сами блоки читаются с диска.
+use md4::Md4;
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 8] = b"SBHIBP01";
+const HEADER_LEN: u64 = 48;
+// Вероятность ложного срабатывания ≈ 1 / 2^RICE_BITS
+const RICE_BITS: u32 = 16;
+// Через сколько элементов в индекс добавляется точка входа
+const BLOCK_SIZE: u64 = 1024;
+
+// Вид хешей в дампе
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HashKind {
+    Sha1,
+    Ntlm,
+}
+
+impl HashKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            HashKind::Sha1 => "SHA-1",
+            HashKind::Ntlm => "NTLM",
+        }
+    }
+
+    // Первые 64 бита хеша пароля
+    fn prefix(&self, password: &str) -> u64 {
+        let mut prefix = [0u8; 8];
+        match self {
+            HashKind::Sha1 => prefix.copy_from_slice(&Sha1::digest(password.as_bytes())[..8]),
+            HashKind::Ntlm => {
+                // NTLM — это MD4 от пароля в UTF-16LE
+                let utf16: Vec<u8> = password.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                prefix.copy_from_slice(&Md4::digest(&utf16)[..8]);
+            }
+        }
+        u64::from_be_bytes(prefix)
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            HashKind::Sha1 => 0,
+            HashKind::Ntlm => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(HashKind::Sha1),
+            1 => Some(HashKind::Ntlm),
+            _ => None,
+        }
+    }
+}
+
+// Монотонное отображение хеша в диапазон фильтра: порядок дампа сохраняется
+fn scale(prefix: u64, range: u64) -> u64 {
+    ((prefix as u128 * range as u128) >> 64) as u64
+}
+
+// Разбираем строку дампа "ХЕШ:КОЛИЧЕСТВО"
+fn parse_line(line: &str) -> Option<(HashKind, u64)> {
+    let hash = line.split(':').next()?.trim();
+    let kind = match hash.len() {
+        40 => HashKind::Sha1,
+        32 => HashKind::Ntlm,
+        _ => return None,
+    };
+    let prefix = u64::from_str_radix(hash.get(..16)?, 16).ok()?;
+    Some((kind, prefix))
+}
+
+pub struct BreachFilter {
+    kind: HashKind,
+    count: u64,
+    built_at: SystemTime,
+    // (значение перед блоком, смещение блока в битах)
+    index: Vec<(u64, u64)>,
+    file: Mutex<BufReader<File>>,
+}
+
+impl BreachFilter {
+    // Файл фильтра рядом с настройками
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("shroombrella").join("hibp.filter"))
+    }
+
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|_| "Не удалось открыть фильтр утечек")?;
+        let mut header = [0u8; HEADER_LEN as usize];
+        file.read_exact(&mut header)
+            .map_err(|_| "Файл фильтра утечек повреждён")?;
+        if &header[..8] != MAGIC || header[9] as u32 != RICE_BITS {
+            return Err("Неподдерживаемый формат фильтра утечек".to_string());
+        }
+        let kind = HashKind::from_byte(header[8]).ok_or("Неизвестный вид хешей в фильтре")?;
+        let read_u64 =
+            |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap_or([0; 8]));
+        let count = read_u64(16);
+        let built_at = UNIX_EPOCH + Duration::from_secs(read_u64(24));
+        let index_offset = read_u64(32);
+        let index_len = read_u64(40);
+
+        // Индекс небольшой (16 байт на BLOCK_SIZE хешей) и читается целиком
+        file.seek(SeekFrom::Start(index_offset))
+            .map_err(|_| "Файл фильтра утечек повреждён")?;
+        let mut reader = BufReader::new(file);
+        let mut index = Vec::with_capacity(index_len as usize);
+        let mut pair = [0u8; 16];
+        for _ in 0..index_len {
+            reader
+                .read_exact(&mut pair)
+                .map_err(|_| "Файл фильтра утечек повреждён")?;
+            let value = u64::from_le_bytes(pair[..8].try_into().unwrap_or([0; 8]));
+            let offset = u64::from_le_bytes(pair[8..].try_into().unwrap_or([0; 8]));
+            index.push((value, offset));
+        }
+
+        Ok(Self {
+            kind,
+            count,
+            built_at,
+            index,
+            file: Mutex::new(reader),
+        })
+    }
+
+    pub fn kind(&self) -> HashKind {
+        self.kind
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn built_at(&self) -> SystemTime {
+        self.built_at
+    }
+
+    // Есть ли пароль в базе утечек (с малой вероятностью ложного срабатывания)
+    pub fn contains(&self, password: &str) -> Result<bool, String> {
+        if self.count == 0 || password.is_empty() {
+            return Ok(false);
+        }
+        let target = scale(self.kind.prefix(password), self.count << RICE_BITS);
+
+        // Последний блок, начинающийся строго до искомого значения
+        let block = self
+            .index
+            .partition_point(|&(base, _)| base < target)
+            .saturating_sub(1);
+        let Some(&(base, bit_offset)) = self.index.get(block) else {
+            return Ok(false);
+        };
+
+        let mut file = self.file.lock().map_err(|_| "Ошибка доступа к фильтру")?;
+        file.seek(SeekFrom::Start(HEADER_LEN + bit_offset / 8))
+            .map_err(|_| "Ошибка чтения фильтра утечек")?;
+        let mut bits = BitReader::new(&mut *file, (bit_offset % 8) as u32)?;
+
+        let mut value = base;
+        let remaining = self.count - block as u64 * BLOCK_SIZE;
+        for _ in 0..remaining {
+            let mut quotient = 0u64;
+            while bits.read_bit()? {
+                quotient += 1;
+            }
+            let remainder = bits.read_bits(RICE_BITS)?;
+            value += (quotient << RICE_BITS) | remainder;
+            if value >= target {
+                return Ok(value == target);
+            }
+        }
+        Ok(false)
+    }
+}
+
+// Строим фильтр из дампа. `progress` — прочитано байт (дамп читается дважды)
+pub fn build(dump: &Path, output: &Path, progress: &AtomicU64) -> Result<BreachFilter, String> {
+    // Первый проход: число хешей и их вид
+    let mut kind = None;
+    let mut count = 0u64;
+    for_each_line(dump, progress, |line| {
+        if let Some((line_kind, _)) = parse_line(line) {
+            if *kind.get_or_insert(line_kind) != line_kind {
+                return Err("В дампе смешаны хеши разных видов".to_string());
+            }
+            count += 1;
+        }
+        Ok(())
+    })?;
+    let kind = kind.ok_or("В файле нет хешей HIBP (строк вида ХЕШ:КОЛИЧЕСТВО)")?;
+
+    // Второй проход: кодируем разности во временный файл, затем переименовываем
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent).map_err(|_| "Не удалось создать каталог для фильтра")?;
+    }
+    let temp = output.with_extension("filter.tmp");
+    let result = write_filter(dump, &temp, kind, count, progress);
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+    std::fs::rename(&temp, output).map_err(|_| "Не удалось сохранить фильтр")?;
+    BreachFilter::open(output)
+}
+
+fn write_filter(
+    dump: &Path,
+    output: &Path,
+    kind: HashKind,
+    count: u64,
+    progress: &AtomicU64,
+) -> Result<(), String> {
+    let write_error = |_| "Ошибка записи фильтра".to_string();
+    let range = count << RICE_BITS;
+
+    // Заголовок записывается в конце, когда известны число хешей и положение индекса
+    let mut out = BufWriter::new(File::create(output).map_err(write_error)?);
+    out.write_all(&[0u8; HEADER_LEN as usize])
+        .map_err(write_error)?;
+    let mut bits = BitWriter::new(out);
+
+    let mut index = Vec::new();
+    let mut previous = 0u64;
+    let mut written = 0u64;
+    for_each_line(dump, progress, |line| {
+        let Some((_, prefix)) = parse_line(line) else {
+            return Ok(());
+        };
+        let value = scale(prefix, range);
+        if value < previous {
+            return Err("Дамп должен быть отсортирован по хешу".to_string());
+        }
+        if written.is_multiple_of(BLOCK_SIZE) {
+            index.push((previous, bits.position));
+        }
+        let delta = value - previous;
+        for _ in 0..delta >> RICE_BITS {
+            bits.write_bit(true).map_err(write_error)?;
+        }
+        bits.write_bit(false).map_err(write_error)?;
+        bits.write_bits(delta & ((1 << RICE_BITS) - 1), RICE_BITS)
+            .map_err(write_error)?;
+        previous = value;
+        written += 1;
+        Ok(())
+    })?;
+
+    let (mut out, data_bytes) = bits.finish().map_err(write_error)?;
+    for (value, offset) in &index {
+        out.write_all(&value.to_le_bytes()).map_err(write_error)?;
+        out.write_all(&offset.to_le_bytes()).map_err(write_error)?;
+    }
+
+    let built_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut header = Vec::with_capacity(HEADER_LEN as usize);
+    header.extend_from_slice(MAGIC);
+    header.push(kind.to_byte());
+    header.push(RICE_BITS as u8);
+    header.extend_from_slice(&[0u8; 6]);
+    header.extend_from_slice(&written.to_le_bytes());
+    header.extend_from_slice(&built_at.to_le_bytes());
+    header.extend_from_slice(&(HEADER_LEN + data_bytes).to_le_bytes());
+    header.extend_from_slice(&(index.len() as u64).to_le_bytes());
+
+    let mut file = out.into_inner().map_err(|_| "Ошибка записи фильтра")?;
+    file.seek(SeekFrom::Start(0)).map_err(write_error)?;
+    file.write_all(&header).map_err(write_error)?;
+    file.sync_all().map_err(write_error)
+}
+
+// Читаем дамп построчно, не загружая его в память
+fn for_each_line(
+    path: &Path,
+    progress: &AtomicU64,
+    mut f: impl FnMut(&str) -> Result<(), String>,
+) -> Result<(), String> {
+    let file = File::open(path).map_err(|_| "Не удалось открыть дамп HIBP")?;
+    let mut reader = BufReader::with_capacity(1 << 20, file);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|_| "Ошибка чтения дампа HIBP")?;
+        if read == 0 {
+            return Ok(());
+        }
+        progress.fetch_add(read as u64, Ordering::Relaxed);
+        if let Ok(text) = std::str::from_utf8(&line) {
+            f(text)?;
+        }
+    }
+}
+
+// Побитовая запись (старший бит байта — первый)
+struct BitWriter<W: Write> {
+    inner: W,
+    byte: u8,
+    filled: u32,
+    position: u64, // записано бит
+}
+
+impl<W: Write> BitWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            byte: 0,
+            filled: 0,
+            position: 0,
+        }
+    }
+
+    fn write_bit(&mut self, bit: bool) -> std::io::Result<()> {
+        self.byte = (self.byte << 1) | bit as u8;
+        self.filled += 1;
+        self.position += 1;
+        if self.filled == 8 {
+            self.inner.write_all(&[self.byte])?;
+            self.byte = 0;
+            self.filled = 0;
+        }
+        Ok(())
+    }
+
+    fn write_bits(&mut self, value: u64, count: u32) -> std::io::Result<()> {
+        for i in (0..count).rev() {
+            self.write_bit((value >> i) & 1 == 1)?;
+        }
+        Ok(())
+    }
+
+    // Дописываем неполный байт; возвращаем поток и число записанных байт
+    fn finish(mut self) -> std::io::Result<(W, u64)> {
+        if self.filled > 0 {
+            let byte = self.byte << (8 - self.filled);
+            self.inner.write_all(&[byte])?;
+        }
+        Ok((self.inner, self.position.div_ceil(8)))
+    }
+}
+
+struct BitReader<'a, R: Read> {
+    inner: &'a mut R,
+    byte: u8,
+    left: u32, // непрочитанных бит в текущем байте
+}
+
+impl<'a, R: Read> BitReader<'a, R> {
+    // Начинаем с бита `skip` первого байта
+    fn new(inner: &'a mut R, skip: u32) -> Result<Self, String> {
+        let mut reader = Self {
+            inner,
+            byte: 0,
+            left: 0,
+        };
+        for _ in 0..skip {
+            reader.read_bit()?;
+        }
+        Ok(reader)
+    }
+
+    fn read_bit(&mut self) -> Result<bool, String> {
+        if self.left == 0 {
+            let mut buf = [0u8; 1];
+            self.inner
+                .read_exact(&mut buf)
+                .map_err(|_| "Файл фильтра утечек повреждён")?;
+            self.byte = buf[0];
+            self.left = 8;
+        }
+        self.left -= 1;
+        Ok((self.byte >> self.left) & 1 == 1)
+    }
+
+    fn read_bits(&mut self, count: u32) -> Result<u64, String> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit()? as u64;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Отдельный каталог на тест, чтобы параллельные тесты не мешали друг другу
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "shroombrella-breach-{}-{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sha1_hex(password: &str) -> String {
+        Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect()
+    }
+
+    fn build_from(dir: &Path, lines: &[String]) -> Result<BreachFilter, String> {
+        let dump = dir.join("dump.txt");
+        std::fs::write(&dump, lines.join("\r\n")).unwrap();
+        build(&dump, &dir.join("hibp.filter"), &AtomicU64::new(0))
+    }
+
+    // Пароли дампа в порядке их хешей
+    fn sorted_passwords(count: usize) -> Vec<String> {
+        let mut passwords: Vec<String> = (0..count).map(|i| format!("password{}", i)).collect();
+        passwords.sort_by_key(|p| sha1_hex(p));
+        passwords
+    }
+
+    #[test]
+    fn finds_every_member() {
+        let dir = temp_dir("members");
+        // Два полных блока и неполный третий
+        let passwords = sorted_passwords(2 * BLOCK_SIZE as usize + 5);
+        let lines: Vec<String> = passwords
+            .iter()
+            .map(|p| format!("{}:{}", sha1_hex(p), 3))
+            .collect();
+        let filter = build_from(&dir, &lines).unwrap();
+        assert_eq!(filter.kind(), HashKind::Sha1);
+        assert_eq!(filter.count(), passwords.len() as u64);
+        assert_eq!(filter.index.len(), 3);
+
+        for password in &passwords {
+            assert!(filter.contains(password).unwrap(), "{}", password);
+        }
+        assert!(!filter.contains("").unwrap());
+
+        // Открытие с диска даёт тот же результат
+        let reopened = BreachFilter::open(&dir.join("hibp.filter")).unwrap();
+        assert!(reopened.contains(&passwords[0]).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lookups_on_block_boundaries() {
+        let dir = temp_dir("blocks");
+        let size = BLOCK_SIZE as usize;
+        let passwords = sorted_passwords(2 * size + 1);
+        let lines: Vec<String> = passwords
+            .iter()
+            .map(|p| format!("{}:1", sha1_hex(p)))
+            .collect();
+        let filter = build_from(&dir, &lines).unwrap();
+
+        // Последний элемент блока совпадает с началом индекса следующего блока
+        let range = filter.count() << RICE_BITS;
+        let value = |p: &str| scale(HashKind::Sha1.prefix(p), range);
+        assert_eq!(filter.index[1].0, value(&passwords[size - 1]));
+        for position in [size - 1, size, 2 * size - 1, 2 * size] {
+            assert!(filter.contains(&passwords[position]).unwrap());
+        }
+
+        // Значение за последним элементом: поиск доходит до конца последнего блока
+        let last = value(passwords.last().unwrap());
+        let beyond = (0..)
+            .map(|i| format!("absent{}", i))
+            .find(|p| value(p) > last)
+            .unwrap();
+        assert!(!filter.contains(&beyond).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_unsorted_dump() {
+        let dir = temp_dir("unsorted");
+        let mut lines: Vec<String> = sorted_passwords(3)
+            .iter()
+            .map(|p| format!("{}:1", sha1_hex(p)))
+            .collect();
+        lines.reverse();
+        let error = build_from(&dir, &lines).err().unwrap();
+        assert!(error.contains("отсортирован"), "{}", error);
+        assert!(!dir.join("hibp.filter").exists());
+        assert!(!dir.join("hibp.filter.tmp").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_mixed_hash_kinds() {
+        let dir = temp_dir("mixed");
+        let lines = vec![
+            format!("{}:1", sha1_hex("password")),
+            "8846F7EAEE8FB117AD06BDD830B7586C:1".to_string(),
+        ];
+        let error = build_from(&dir, &lines).err().unwrap();
+        assert!(error.contains("смешаны"), "{}", error);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ntlm_dump() {
+        let dir = temp_dir("ntlm");
+        // NTLM-хеш пароля "password"
+        let lines = vec!["8846F7EAEE8FB117AD06BDD830B7586C:1".to_string()];
+        let filter = build_from(&dir, &lines).unwrap();
+        assert_eq!(filter.kind(), HashKind::Ntlm);
+        assert!(filter.contains("password").unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,794 @@
+// Чтение и запись CSV: определение кодировки и разделителя, поля в кавычках (в том
+// числе многострочные) и готовые раскладки колонок браузеров и менеджеров паролей
+use crate::policy;
+use crate::totp::OtpConfig;
+use crate::vault::{self, Entry};
+use std::time::SystemTime;
+use zeroize::Zeroize;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1251,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 4] = [
+        Encoding::Utf8,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Windows1251,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Windows1251 => "Windows-1251",
+        }
+    }
+}
+
+// Кодировка по BOM; без BOM — UTF-8, если байты корректны, иначе Windows-1251
+// (так сохраняет CSV русская версия Excel)
+pub fn detect_encoding(bytes: &[u8]) -> Encoding {
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        Encoding::Utf16Le
+    } else if bytes.starts_with(&[0xFE, 0xFF]) {
+        Encoding::Utf16Be
+    } else if std::str::from_utf8(bytes).is_ok() {
+        Encoding::Utf8
+    } else {
+        Encoding::Windows1251
+    }
+}
+
+// Декодируем текст, отбрасывая BOM; неверные последовательности заменяются на �
+pub fn decode(bytes: &[u8], encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| match encoding {
+                    Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                })
+                .collect();
+            let units = units.strip_prefix(&[0xFEFF]).unwrap_or(&units);
+            String::from_utf16_lossy(units)
+        }
+        Encoding::Windows1251 => bytes.iter().map(|&b| windows1251_char(b)).collect(),
+    }
+}
+
+// Символы 0x80…0xBF кодировки Windows-1251 (0x98 не определён)
+const WINDOWS1251_HIGH: [char; 64] = [
+    'Ђ', 'Ѓ', '‚', 'ѓ', '„', '…', '†', '‡', '€', '‰', 'Љ', '‹', 'Њ', 'Ќ', 'Ћ', 'Џ', //
+    'ђ', '‘', '’', '“', '”', '•', '–', '—', '\u{FFFD}', '™', 'љ', '›', 'њ', 'ќ', 'ћ', 'џ', //
+    '\u{A0}', 'Ў', 'ў', 'Ј', '¤', 'Ґ', '¦', '§', 'Ё', '©', 'Є', '«', '¬', '\u{AD}', '®',
+    'Ї', //
+    '°', '±', 'І', 'і', 'ґ', 'µ', '¶', '·', 'ё', '№', 'є', '»', 'ј', 'Ѕ', 'ѕ', 'ї',
+];
+
+fn windows1251_char(byte: u8) -> char {
+    match byte {
+        0x00..=0x7F => byte as char,
+        0x80..=0xBF => WINDOWS1251_HIGH[(byte - 0x80) as usize],
+        // 0xC0…0xFF — А…я подряд
+        _ => char::from_u32(0x0410 + (byte - 0xC0) as u32).unwrap_or('\u{FFFD}'),
+    }
+}
+
+pub const DELIMITERS: [char; 4] = [',', ';', '\t', '|'];
+
+pub fn delimiter_title(delimiter: char) -> &'static str {
+    match delimiter {
+        ',' => "запятая",
+        ';' => "точка с запятой",
+        '\t' => "табуляция",
+        '|' => "вертикальная черта",
+        _ => "?",
+    }
+}
+
+// Разделитель, при котором первые строки дают одинаковое число колонок (больше одной)
+pub fn detect_delimiter(text: &str) -> char {
+    // Для оценки хватает начала файла
+    let sample = match text.char_indices().nth(64 * 1024) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    };
+    let mut best = (',', 0usize, 0usize);
+    for delimiter in DELIMITERS {
+        let rows = parse(sample, delimiter);
+        let Some(first) = rows.first() else {
+            continue;
+        };
+        let columns = first.len();
+        if columns < 2 {
+            continue;
+        }
+        let consistent = rows.iter().take(20).filter(|r| r.len() == columns).count();
+        if (consistent, columns) > (best.1, best.2) {
+            best = (delimiter, consistent, columns);
+        }
+    }
+    best.0
+}
+
+// Разбор CSV по RFC 4180: поля в кавычках могут содержать разделитель, перевод строки
+// и удвоенные кавычки. Пустые строки пропускаются
+pub fn parse(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut field_start = true;
+    let mut chars = text.chars().peekable();
+
+    let mut end_row = |row: &mut Vec<String>, field: &mut String| {
+        row.push(std::mem::take(field));
+        let row = std::mem::take(row);
+        if !(row.len() == 1 && row[0].is_empty()) {
+            rows.push(row);
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                // Переводы строк внутри полей приводим к \n
+                '\r' if chars.peek() == Some(&'\n') => {}
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field_start => {
+                in_quotes = true;
+                field_start = false;
+            }
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                end_row(&mut row, &mut field);
+                field_start = true;
+            }
+            c if c == delimiter => {
+                row.push(std::mem::take(&mut field));
+                field_start = true;
+            }
+            _ => {
+                field.push(c);
+                field_start = false;
+            }
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        end_row(&mut row, &mut field);
+    }
+    rows
+}
+
+// Поле записи, в которое попадает колонка
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Field {
+    Skip,
+    Service,
+    Login,
+    Password,
+    Url,
+    Notes,
+    Folder,
+    Tags,
+    Totp,
+    Favorite,
+}
+
+impl Field {
+    pub const ALL: [Field; 10] = [
+        Field::Skip,
+        Field::Service,
+        Field::Login,
+        Field::Password,
+        Field::Url,
+        Field::Notes,
+        Field::Folder,
+        Field::Tags,
+        Field::Totp,
+        Field::Favorite,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Field::Skip => "— пропустить —",
+            Field::Service => "🌐 Сервис",
+            Field::Login => "👤 Логин",
+            Field::Password => "🔑 Пароль",
+            Field::Url => "🔗 URL",
+            Field::Notes => "📝 Заметки",
+            Field::Folder => "📁 Папка",
+            Field::Tags => "🏷️ Теги",
+            Field::Totp => "🔢 2FA",
+            Field::Favorite => "⭐ Избранное",
+        }
+    }
+}
+
+// Раскладка колонок известного формата экспорта (имена колонок без учёта регистра)
+pub struct Preset {
+    pub name: &'static str,
+    pub columns: &'static [(&'static str, Field)],
+}
+
+pub const PRESETS: [Preset; 5] = [
+    Preset {
+        name: "Chrome",
+        columns: &[
+            ("name", Field::Service),
+            ("url", Field::Url),
+            ("username", Field::Login),
+            ("password", Field::Password),
+            ("note", Field::Notes),
+        ],
+    },
+    Preset {
+        name: "Firefox",
+        columns: &[
+            ("url", Field::Url),
+            ("username", Field::Login),
+            ("password", Field::Password),
+            ("httpRealm", Field::Skip),
+            ("formActionOrigin", Field::Skip),
+            ("guid", Field::Skip),
+        ],
+    },
+    Preset {
+        name: "Bitwarden",
+        columns: &[
+            ("folder", Field::Folder),
+            ("favorite", Field::Favorite),
+            ("type", Field::Skip),
+            ("name", Field::Service),
+            ("notes", Field::Notes),
+            ("fields", Field::Skip),
+            ("login_uri", Field::Url),
+            ("login_username", Field::Login),
+            ("login_password", Field::Password),
+            ("login_totp", Field::Totp),
+        ],
+    },
+    Preset {
+        name: "LastPass",
+        columns: &[
+            ("url", Field::Url),
+            ("username", Field::Login),
+            ("password", Field::Password),
+            ("totp", Field::Totp),
+            ("extra", Field::Notes),
+            ("name", Field::Service),
+            ("grouping", Field::Folder),
+            ("fav", Field::Favorite),
+        ],
+    },
+    Preset {
+        name: "KeePassXC",
+        columns: &[
+            ("Group", Field::Folder),
+            ("Title", Field::Service),
+            ("Username", Field::Login),
+            ("Password", Field::Password),
+            ("URL", Field::Url),
+            ("Notes", Field::Notes),
+            ("TOTP", Field::Totp),
+        ],
+    },
+];
+
+// Формат, все колонки которого есть в заголовке
+pub fn detect_preset(header: &[String]) -> Option<usize> {
+    PRESETS.iter().position(|preset| {
+        preset.columns.iter().all(|(name, _)| {
+            header
+                .iter()
+                .any(|column| column.trim().eq_ignore_ascii_case(name))
+        })
+    })
+}
+
+// Раскладка по заголовку: по формату, иначе по знакомым названиям колонок
+pub fn guess_mapping(header: &[String]) -> Vec<Field> {
+    if let Some(preset) = detect_preset(header) {
+        return preset_mapping(preset, header);
+    }
+    let mut mapping: Vec<Field> = header.iter().map(|column| guess_field(column)).collect();
+    // Каждое поле, кроме тегов, берём из первой подходящей колонки
+    for i in 0..mapping.len() {
+        let field = mapping[i];
+        if field != Field::Skip && field != Field::Tags && mapping[..i].contains(&field) {
+            mapping[i] = Field::Skip;
+        }
+    }
+    mapping
+}
+
+pub fn preset_mapping(preset: usize, header: &[String]) -> Vec<Field> {
+    header
+        .iter()
+        .map(|column| {
+            PRESETS[preset]
+                .columns
+                .iter()
+                .find(|(name, _)| column.trim().eq_ignore_ascii_case(name))
+                .map_or(Field::Skip, |(_, field)| *field)
+        })
+        .collect()
+}
+
+fn guess_field(column: &str) -> Field {
+    let column = column.trim().to_lowercase();
+    let column = column.as_str();
+    match column {
+        "name" | "title" | "service" | "site" | "account" | "название" | "сервис" | "сайт" => {
+            Field::Service
+        }
+        "user"
+        | "username"
+        | "login"
+        | "email"
+        | "e-mail"
+        | "user name"
+        | "логин"
+        | "пользователь" => Field::Login,
+        "password" | "pass" | "pwd" | "пароль" => Field::Password,
+        "url" | "uri" | "website" | "web site" | "address" | "адрес" => Field::Url,
+        "note" | "notes" | "extra" | "comment" | "comments" | "заметки" | "примечание" => {
+            Field::Notes
+        }
+        "folder" | "group" | "grouping" | "category" | "папка" | "группа" => {
+            Field::Folder
+        }
+        "tags" | "tag" | "labels" | "теги" | "метки" => Field::Tags,
+        "totp" | "otp" | "2fa" | "otpauth" | "login_totp" => Field::Totp,
+        "favorite" | "fav" | "favourite" | "избранное" => Field::Favorite,
+        _ if column.starts_with("login_") || column.ends_with("username") => Field::Login,
+        _ if column.contains("password") => Field::Password,
+        _ => Field::Skip,
+    }
+}
+
+// Записи из строк CSV и замечания по пропущенным и частично перенесённым строкам.
+// `first_line` — номер первой строки данных для сообщений
+pub fn to_entries(
+    rows: &[Vec<String>],
+    mapping: &[Field],
+    first_line: usize,
+) -> (Vec<Entry>, Vec<String>) {
+    let now = SystemTime::now();
+    let mut entries = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        let line = first_line + i;
+        let mut entry = Entry {
+            created_at: Some(now),
+            modified_at: Some(now),
+            ..Entry::default()
+        };
+        for (raw, field) in row.iter().zip(mapping) {
+            let value = raw.trim();
+            if value.is_empty() {
+                continue;
+            }
+            match field {
+                Field::Skip => {}
+                Field::Service => entry.service = value.to_string(),
+                Field::Login => entry.login = value.to_string(),
+                // Пробелы по краям пароля могут быть значимыми
+                Field::Password => entry.password = raw.clone(),
+                Field::Url => entry.url = value.to_string(),
+                Field::Notes => {
+                    if !entry.notes.is_empty() {
+                        entry.notes.push('\n');
+                    }
+                    entry.notes.push_str(value);
+                }
+                Field::Folder => entry.folder = vault::normalize_folder(&value.replace('\\', "/")),
+                Field::Tags => {
+                    for tag in vault::parse_tags(&value.replace(';', ",")) {
+                        if !entry.tags.contains(&tag) {
+                            entry.tags.push(tag);
+                        }
+                    }
+                }
+                Field::Totp => match OtpConfig::parse(value) {
+                    Ok(config) => entry.otp = Some(config),
+                    Err(e) => {
+                        warnings.push(format!(
+                            "Строка {}: 2FA не распознан ({}), значение перенесено в заметки",
+                            line, e
+                        ));
+                        if !entry.notes.is_empty() {
+                            entry.notes.push('\n');
+                        }
+                        entry.notes.push_str(&format!("2FA: {}", value));
+                    }
+                },
+                Field::Favorite => {
+                    entry.favorite = matches!(
+                        value.to_lowercase().as_str(),
+                        "1" | "true" | "yes" | "y" | "да"
+                    )
+                }
+            }
+        }
+
+        // Без названия сервиса подставляем адрес сайта
+        if entry.service.is_empty()
+            && let Some(host) = policy::host_of(&entry.url)
+        {
+            entry.service = host.strip_prefix("www.").unwrap_or(&host).to_string();
+        }
+        if entry.service.is_empty() && entry.login.is_empty() && entry.password.is_empty() {
+            warnings.push(format!(
+                "Строка {}: нет сервиса, логина и пароля — пропущена",
+                line
+            ));
+            continue;
+        }
+        entries.push(entry);
+    }
+    (entries, warnings)
+}
+
+// Формат файла экспорта
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    Generic,
+    KeePassXc,
+    Bitwarden,
+    Chrome,
+}
+
+// Значение колонки экспорта: поле записи или постоянный текст
+#[derive(Clone, Copy)]
+enum Cell {
+    Field(Field),
+    Text(&'static str),
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Generic,
+        ExportFormat::KeePassXc,
+        ExportFormat::Bitwarden,
+        ExportFormat::Chrome,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ExportFormat::Generic => "Универсальный",
+            ExportFormat::KeePassXc => "KeePassXC",
+            ExportFormat::Bitwarden => "Bitwarden",
+            ExportFormat::Chrome => "Chrome / Edge",
+        }
+    }
+
+    // Поля, которые формат умеет хранить
+    pub fn supports(&self, field: Field) -> bool {
+        self.columns()
+            .iter()
+            .any(|(_, cell)| matches!(cell, Cell::Field(f) if *f == field))
+    }
+
+    fn columns(&self) -> &'static [(&'static str, Cell)] {
+        match self {
+            ExportFormat::Generic => &[
+                ("service", Cell::Field(Field::Service)),
+                ("login", Cell::Field(Field::Login)),
+                ("password", Cell::Field(Field::Password)),
+                ("url", Cell::Field(Field::Url)),
+                ("notes", Cell::Field(Field::Notes)),
+                ("folder", Cell::Field(Field::Folder)),
+                ("tags", Cell::Field(Field::Tags)),
+                ("totp", Cell::Field(Field::Totp)),
+                ("favorite", Cell::Field(Field::Favorite)),
+            ],
+            ExportFormat::KeePassXc => &[
+                ("Group", Cell::Field(Field::Folder)),
+                ("Title", Cell::Field(Field::Service)),
+                ("Username", Cell::Field(Field::Login)),
+                ("Password", Cell::Field(Field::Password)),
+                ("URL", Cell::Field(Field::Url)),
+                ("Notes", Cell::Field(Field::Notes)),
+                ("TOTP", Cell::Field(Field::Totp)),
+            ],
+            ExportFormat::Bitwarden => &[
+                ("folder", Cell::Field(Field::Folder)),
+                ("favorite", Cell::Field(Field::Favorite)),
+                ("type", Cell::Text("login")),
+                ("name", Cell::Field(Field::Service)),
+                ("notes", Cell::Field(Field::Notes)),
+                ("fields", Cell::Text("")),
+                ("reprompt", Cell::Text("0")),
+                ("login_uri", Cell::Field(Field::Url)),
+                ("login_username", Cell::Field(Field::Login)),
+                ("login_password", Cell::Field(Field::Password)),
+                ("login_totp", Cell::Field(Field::Totp)),
+            ],
+            ExportFormat::Chrome => &[
+                ("name", Cell::Field(Field::Service)),
+                ("url", Cell::Field(Field::Url)),
+                ("username", Cell::Field(Field::Login)),
+                ("password", Cell::Field(Field::Password)),
+                ("note", Cell::Field(Field::Notes)),
+            ],
+        }
+    }
+}
+
+// Записи в CSV выбранного формата. Невыбранные поля остаются пустыми,
+// но колонки сохраняются, чтобы файл распознавался программой-получателем
+pub fn export(entries: &[Entry], format: ExportFormat, fields: &[Field]) -> String {
+    let columns = format.columns();
+    let mut out = String::new();
+    let header: Vec<String> = columns.iter().map(|(name, _)| name.to_string()).collect();
+    write_record(&mut out, &header);
+
+    for entry in entries {
+        let mut record: Vec<String> = columns
+            .iter()
+            .map(|(_, cell)| match cell {
+                Cell::Text(text) => text.to_string(),
+                // Пароль и 2FA выгружаем как есть: апостроф изменил бы секрет при импорте
+                Cell::Field(field @ (Field::Password | Field::Totp)) if fields.contains(field) => {
+                    field_value(entry, *field)
+                }
+                Cell::Field(field) if fields.contains(field) => {
+                    escape_formula(&field_value(entry, *field))
+                }
+                Cell::Field(_) => String::new(),
+            })
+            .collect();
+        write_record(&mut out, &record);
+        record.zeroize();
+    }
+    out
+}
+
+fn field_value(entry: &Entry, field: Field) -> String {
+    match field {
+        Field::Skip => String::new(),
+        Field::Service => entry.service.clone(),
+        Field::Login => entry.login.clone(),
+        Field::Password => entry.password.clone(),
+        Field::Url => entry.url.clone(),
+        Field::Notes => entry.notes.clone(),
+        Field::Folder => entry.folder.clone(),
+        Field::Tags => entry.tags.join(", "),
+        Field::Totp => entry
+            .otp
+            .as_ref()
+            .map(|config| config.to_uri())
+            .unwrap_or_default(),
+        Field::Favorite => if entry.favorite { "1" } else { "" }.to_string(),
+    }
+}
+
+// Таблицы выполняют ячейки, начинающиеся с "=", "+", "-" или "@", как формулы:
+// такие значения предваряем апострофом, чтобы они открылись как текст
+pub fn escape_formula(value: &str) -> String {
+    if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+// Строка CSV: поля с разделителем, кавычками, переводами строк или пробелами
+// по краям берутся в кавычки
+pub fn write_record(out: &mut String, fields: &[String]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let needs_quotes = field.contains([',', '"', '\n', '\r'])
+            || field.starts_with(' ')
+            || field.ends_with(' ');
+        if needs_quotes {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(field);
+        }
+    }
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Заголовки из экспорта самих программ
+    const CHROME: &str = "name,url,username,password,note";
+    const FIREFOX: &str = r#""url","username","password","httpRealm","formActionOrigin","guid","timeCreated","timeLastUsed","timePasswordChanged""#;
+    const BITWARDEN: &str = "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp";
+    const LASTPASS: &str = "url,username,password,totp,extra,name,grouping,fav";
+    const KEEPASSXC: &str = r#""Group","Title","Username","Password","URL","Notes","TOTP","Icon","Last Modified","Created""#;
+
+    fn header(line: &str) -> Vec<String> {
+        parse(line, detect_delimiter(line)).remove(0)
+    }
+
+    #[test]
+    fn parse_quotes_and_line_breaks() {
+        let text = "a,b\r\n\"x, y\",\"say \"\"hi\"\"\"\r\n\r\n\"multi\r\nline\",z";
+        let rows = parse(text, ',');
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a".to_string(), "b".to_string()],
+                vec!["x, y".to_string(), "say \"hi\"".to_string()],
+                vec!["multi\nline".to_string(), "z".to_string()],
+            ]
+        );
+        // Пустое последнее поле сохраняется
+        assert_eq!(
+            parse("a,\n", ','),
+            vec![vec!["a".to_string(), String::new()]]
+        );
+    }
+
+    #[test]
+    fn delimiter_detection() {
+        assert_eq!(detect_delimiter("a;b;c\n1;2;3\n"), ';');
+        assert_eq!(detect_delimiter("a\tb\n1\t2\n"), '\t');
+        assert_eq!(detect_delimiter("a|b\n1|2\n"), '|');
+        // Запятая внутри кавычек не сбивает выбор
+        assert_eq!(detect_delimiter("\"x,y\";b\n\"1,2\";3\n"), ';');
+        // Одна колонка — разделитель по умолчанию
+        assert_eq!(detect_delimiter("single\nvalue\n"), ',');
+        assert_eq!(detect_delimiter(KEEPASSXC), ',');
+    }
+
+    #[test]
+    fn encoding_detection() {
+        let utf8 = "\u{FEFF}Сервис,Пароль".as_bytes();
+        assert_eq!(detect_encoding(utf8), Encoding::Utf8);
+        assert_eq!(decode(utf8, Encoding::Utf8), "Сервис,Пароль");
+
+        let utf16: Vec<u8> = "\u{FEFF}Ёж;1"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(detect_encoding(&utf16), Encoding::Utf16Le);
+        assert_eq!(decode(&utf16, Encoding::Utf16Le), "Ёж;1");
+
+        let utf16: Vec<u8> = "\u{FEFF}Ёж"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        assert_eq!(detect_encoding(&utf16), Encoding::Utf16Be);
+        assert_eq!(decode(&utf16, Encoding::Utf16Be), "Ёж");
+
+        // «Пароль;Ёж №1» в Windows-1251, как сохраняет русский Excel
+        let cp1251 = [
+            0xCF, 0xE0, 0xF0, 0xEE, 0xEB, 0xFC, b';', 0xA8, 0xE6, b' ', 0xB9, b'1',
+        ];
+        assert_eq!(detect_encoding(&cp1251), Encoding::Windows1251);
+        let text = decode(&cp1251, Encoding::Windows1251);
+        assert_eq!(text, "Пароль;Ёж №1");
+        assert_eq!(guess_mapping(&header(&text))[0], Field::Password);
+    }
+
+    #[test]
+    fn presets_from_real_headers() {
+        for (line, name) in [
+            (CHROME, "Chrome"),
+            (FIREFOX, "Firefox"),
+            (BITWARDEN, "Bitwarden"),
+            (LASTPASS, "LastPass"),
+            (KEEPASSXC, "KeePassXC"),
+        ] {
+            let preset = detect_preset(&header(line)).map(|i| PRESETS[i].name);
+            assert_eq!(preset, Some(name), "{}", line);
+        }
+        assert_eq!(detect_preset(&header("Сайт;Логин;Пароль")), None);
+
+        // Колонки, которых нет в раскладке, пропускаются
+        let mapping = guess_mapping(&header(KEEPASSXC));
+        assert_eq!(
+            &mapping[..4],
+            &[Field::Folder, Field::Service, Field::Login, Field::Password]
+        );
+        assert_eq!(&mapping[7..], &[Field::Skip; 3]);
+    }
+
+    #[test]
+    fn guess_mapping_without_preset() {
+        let mapping = guess_mapping(&header("Сайт;Логин;Пароль;E-mail;Теги;Метки"));
+        assert_eq!(
+            mapping,
+            vec![
+                Field::Service,
+                Field::Login,
+                Field::Password,
+                Field::Skip, // логин уже взят из первой колонки
+                Field::Tags,
+                Field::Tags,
+            ]
+        );
+    }
+
+    #[test]
+    fn bitwarden_rows_to_entries() {
+        let text = format!(
+            "{}\r\n\
+             Работа\\Почта,1,login,Почта,\"строка 1\nстрока 2\",,0,https://mail.example.com,ivan,\" pa,ss \",JBSWY3DPEHPK3PXP\r\n\
+             ,,login,,,,0,https://www.example.org/login,,secret,\r\n\
+             ,,login,Банк,,,0,,anna,pw,not a secret!\r\n\
+             ,,note,,,,0,,,,\r\n",
+            BITWARDEN
+        );
+        let rows = parse(&text, detect_delimiter(&text));
+        let mapping = guess_mapping(&rows[0]);
+        let (entries, warnings) = to_entries(&rows[1..], &mapping, 2);
+        assert_eq!(entries.len(), 3);
+
+        let mail = &entries[0];
+        assert_eq!(mail.service, "Почта");
+        assert_eq!(mail.login, "ivan");
+        assert_eq!(mail.password, " pa,ss ");
+        assert_eq!(mail.url, "https://mail.example.com");
+        assert_eq!(mail.notes, "строка 1\nстрока 2");
+        assert_eq!(mail.folder, "Работа/Почта");
+        assert!(mail.favorite);
+        assert_eq!(mail.otp.as_ref().unwrap().secret, "JBSWY3DPEHPK3PXP");
+
+        // Без названия сервис берётся из адреса
+        assert_eq!(entries[1].service, "example.org");
+        // Неверный 2FA не теряется
+        assert!(entries[2].otp.is_none());
+        assert_eq!(entries[2].notes, "2FA: not a secret!");
+
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Строка 4:"), "{}", warnings[0]);
+        assert!(warnings[1].starts_with("Строка 5:"), "{}", warnings[1]);
+    }
+
+    #[test]
+    fn export_escapes_formulas_but_not_secrets() {
+        let entries = vec![Entry {
+            service: "=HYPERLINK(\"http://evil\")".to_string(),
+            login: "@admin".to_string(),
+            password: "-p=ss".to_string(),
+            notes: "+1 555".to_string(),
+            ..Entry::default()
+        }];
+        let text = export(&entries, ExportFormat::Chrome, &Field::ALL);
+        let rows = parse(&text, ',');
+        assert_eq!(rows[0], ["name", "url", "username", "password", "note"]);
+        assert_eq!(rows[1][0], "'=HYPERLINK(\"http://evil\")");
+        assert_eq!(rows[1][2], "'@admin");
+        assert_eq!(rows[1][3], "-p=ss");
+        assert_eq!(rows[1][4], "'+1 555");
+
+        // Невыбранные поля остаются пустыми
+        let text = export(&entries, ExportFormat::Chrome, &[Field::Service]);
+        assert_eq!(parse(&text, ',')[1][3], "");
+    }
+}
@@ -0,0 +1,411 @@
+use crate::passphrase::{self, PassphraseOptions};
+use rand::Rng;
+use rand::distr::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const DIGITS: &str = "0123456789";
+pub const DEFAULT_SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.<>/?~";
+
+// Символы, которые легко перепутать при чтении: 0/O/o, 1/l/I/|, 5/S, 2/Z
+const LOOK_ALIKES: &str = "0Oo1lI|5S2Z`'\"";
+
+// Сколько раз пробуем заново, если случайная перестановка нарушила запрет повторов подряд
+const MAX_ATTEMPTS: usize = 1000;
+
+// Что генерируем: случайные символы или парольную фразу из слов
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum GeneratorMode {
+    Password,
+    Passphrase,
+}
+
+// Параметры генерации пароля
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct GeneratorOptions {
+    pub mode: GeneratorMode,
+    pub passphrase: PassphraseOptions,
+    pub length: usize,
+    pub upper: bool,
+    pub lower: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub symbol_set: String,
+    pub exclude_look_alikes: bool,
+    // Минимальное количество символов каждого включённого класса
+    pub min_upper: usize,
+    pub min_lower: usize,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+    pub no_repeat: bool,      // каждый символ встречается не более одного раза
+    pub no_consecutive: bool, // одинаковые символы не стоят рядом
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            mode: GeneratorMode::Password,
+            passphrase: PassphraseOptions::default(),
+            length: 20,
+            upper: true,
+            lower: true,
+            digits: true,
+            symbols: true,
+            symbol_set: DEFAULT_SYMBOLS.to_string(),
+            exclude_look_alikes: false,
+            min_upper: 1,
+            min_lower: 1,
+            min_digits: 1,
+            min_symbols: 1,
+            no_repeat: false,
+            no_consecutive: false,
+        }
+    }
+}
+
+// Именованный набор параметров, сохраняемый в настройках
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct GeneratorProfile {
+    pub name: String,
+    pub options: GeneratorOptions,
+}
+
+// Класс символов с требуемым минимумом
+struct CharClass {
+    chars: Vec<char>,
+    min: usize,
+}
+
+impl GeneratorOptions {
+    // Включённые классы символов (без повторов и, при необходимости, без похожих символов)
+    fn classes(&self) -> Vec<CharClass> {
+        let mut seen = Vec::new();
+        let mut classes = Vec::new();
+        let sets = [
+            (self.upper, UPPER, self.min_upper),
+            (self.lower, LOWER, self.min_lower),
+            (self.digits, DIGITS, self.min_digits),
+            (self.symbols, self.symbol_set.as_str(), self.min_symbols),
+        ];
+
+        for (enabled, set, min) in sets {
+            if !enabled {
+                continue;
+            }
+            let chars: Vec<char> = set
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .filter(|c| !(self.exclude_look_alikes && LOOK_ALIKES.contains(*c)))
+                .filter(|c| {
+                    // Символ, уже входящий в другой класс, не учитываем дважды
+                    let new = !seen.contains(c);
+                    if new {
+                        seen.push(*c);
+                    }
+                    new
+                })
+                .collect();
+            if !chars.is_empty() {
+                classes.push(CharClass { chars, min });
+            }
+        }
+        classes
+    }
+
+    // Размер итогового алфавита
+    pub fn pool_size(&self) -> usize {
+        self.classes().iter().map(|class| class.chars.len()).sum()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.mode {
+            GeneratorMode::Password => self.validate_password(),
+            GeneratorMode::Passphrase => self.passphrase.validate(),
+        }
+    }
+
+    pub fn entropy_bits(&self) -> f64 {
+        match self.mode {
+            GeneratorMode::Password => self.password_entropy_bits(),
+            GeneratorMode::Passphrase => self.passphrase.entropy_bits(),
+        }
+    }
+
+    fn validate_password(&self) -> Result<(), String> {
+        let classes = self.classes();
+        if classes.is_empty() {
+            return Err("Не выбран ни один набор символов".to_string());
+        }
+        if self.length == 0 {
+            return Err("Длина пароля должна быть больше нуля".to_string());
+        }
+
+        let required: usize = classes.iter().map(|class| class.min).sum();
+        if required > self.length {
+            return Err(format!(
+                "Сумма минимумов ({}) больше длины пароля ({})",
+                required, self.length
+            ));
+        }
+
+        if self.no_repeat {
+            let pool: usize = classes.iter().map(|class| class.chars.len()).sum();
+            if self.length > pool {
+                return Err(format!(
+                    "Без повторов можно получить не больше {} символов",
+                    pool
+                ));
+            }
+            if classes.iter().any(|class| class.min > class.chars.len()) {
+                return Err("Минимум для набора больше числа его символов".to_string());
+            }
+        }
+        // Из одного символа пароль без повторов подряд не составить
+        if self.no_consecutive && self.length > 1 && self.pool_size() == 1 {
+            return Err("Без повторов подряд нужен алфавит хотя бы из двух символов".to_string());
+        }
+        Ok(())
+    }
+
+    // Оценка энтропии в битах: log2 числа равновероятных паролей без учёта минимумов
+    fn password_entropy_bits(&self) -> f64 {
+        let pool = self.pool_size();
+        if pool == 0 {
+            return 0.0;
+        }
+        if self.no_repeat {
+            (0..self.length.min(pool))
+                .map(|i| ((pool - i) as f64).log2())
+                .sum()
+        } else {
+            self.length as f64 * (pool as f64).log2()
+        }
+    }
+}
+
+// Генерируем пароль криптостойким ГПСЧ. Выбор символа — через Uniform,
+// который отбрасывает «лишние» значения и потому не смещает распределение.
+pub fn generate(options: &GeneratorOptions) -> Result<String, String> {
+    if options.mode == GeneratorMode::Passphrase {
+        return passphrase::generate(&options.passphrase);
+    }
+    options.validate_password()?;
+    let classes = options.classes();
+    let mut rng = rand::rng();
+
+    for _ in 0..MAX_ATTEMPTS {
+        let mut available: Vec<Vec<char>> = classes.iter().map(|c| c.chars.clone()).collect();
+        let mut password = Vec::with_capacity(options.length);
+
+        // Сначала обязательные символы каждого класса
+        for (class_index, class) in classes.iter().enumerate() {
+            for _ in 0..class.min {
+                password.push(pick(
+                    &mut rng,
+                    &mut available[class_index],
+                    options.no_repeat,
+                )?);
+            }
+        }
+
+        // Остальное — из общего алфавита
+        while password.len() < options.length {
+            let total: usize = available.iter().map(Vec::len).sum();
+            let mut index = sample_index(&mut rng, total)?;
+            let class_index = available
+                .iter()
+                .position(|chars| {
+                    if index < chars.len() {
+                        true
+                    } else {
+                        index -= chars.len();
+                        false
+                    }
+                })
+                .ok_or("Недостаточно символов для генерации")?;
+            let c = if options.no_repeat {
+                available[class_index].remove(index)
+            } else {
+                available[class_index][index]
+            };
+            password.push(c);
+        }
+
+        // Перемешиваем, чтобы обязательные символы не стояли в начале
+        for i in (1..password.len()).rev() {
+            let j = sample_index(&mut rng, i + 1)?;
+            password.swap(i, j);
+        }
+
+        if options.no_consecutive && password.windows(2).any(|pair| pair[0] == pair[1]) {
+            continue;
+        }
+        return Ok(password.into_iter().collect());
+    }
+
+    Err("Не удалось подобрать пароль с заданными ограничениями".to_string())
+}
+
+fn pick(rng: &mut impl Rng, chars: &mut Vec<char>, remove: bool) -> Result<char, String> {
+    let index = sample_index(rng, chars.len())?;
+    Ok(if remove {
+        chars.remove(index)
+    } else {
+        chars[index]
+    })
+}
+
+// Равномерный индекс в диапазоне 0..n
+fn sample_index(rng: &mut impl Rng, n: usize) -> Result<usize, String> {
+    let uniform = Uniform::new(0, n).map_err(|_| "Недостаточно символов для генерации")?;
+    Ok(uniform.sample(rng))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn count(password: &str, set: &str) -> usize {
+        password.chars().filter(|c| set.contains(*c)).count()
+    }
+
+    #[test]
+    fn meets_class_minimums() {
+        let options = GeneratorOptions {
+            length: 12,
+            min_upper: 3,
+            min_lower: 2,
+            min_digits: 4,
+            min_symbols: 3,
+            ..GeneratorOptions::default()
+        };
+        for _ in 0..200 {
+            let password = generate(&options).unwrap();
+            assert_eq!(password.chars().count(), 12);
+            assert!(count(&password, UPPER) >= 3, "{}", password);
+            assert!(count(&password, LOWER) >= 2, "{}", password);
+            assert!(count(&password, DIGITS) >= 4, "{}", password);
+            assert!(count(&password, DEFAULT_SYMBOLS) >= 3, "{}", password);
+        }
+    }
+
+    #[test]
+    fn excluded_characters_never_appear() {
+        let options = GeneratorOptions {
+            length: 64,
+            exclude_look_alikes: true,
+            symbol_set: "!|`'\"".to_string(),
+            ..GeneratorOptions::default()
+        };
+        for _ in 0..100 {
+            let password = generate(&options).unwrap();
+            assert_eq!(count(&password, LOOK_ALIKES), 0, "{}", password);
+        }
+        // Отключённые классы тоже не попадают в пароль
+        let options = GeneratorOptions {
+            upper: false,
+            symbols: false,
+            ..GeneratorOptions::default()
+        };
+        let password = generate(&options).unwrap();
+        assert_eq!(
+            count(&password, UPPER) + count(&password, DEFAULT_SYMBOLS),
+            0
+        );
+    }
+
+    #[test]
+    fn repeat_constraints_hold() {
+        let options = GeneratorOptions {
+            length: 40,
+            upper: false,
+            symbols: false,
+            no_consecutive: true,
+            ..GeneratorOptions::default()
+        };
+        for _ in 0..100 {
+            let password: Vec<char> = generate(&options).unwrap().chars().collect();
+            assert!(password.windows(2).all(|pair| pair[0] != pair[1]));
+        }
+
+        // Без повторов весь алфавит цифр укладывается ровно в десять символов
+        let options = GeneratorOptions {
+            length: 10,
+            upper: false,
+            lower: false,
+            symbols: false,
+            min_digits: 10,
+            no_repeat: true,
+            ..GeneratorOptions::default()
+        };
+        let mut password: Vec<char> = generate(&options).unwrap().chars().collect();
+        password.sort_unstable();
+        assert_eq!(password.into_iter().collect::<String>(), DIGITS);
+    }
+
+    #[test]
+    fn impossible_settings_fail_fast() {
+        let only_digits = GeneratorOptions {
+            upper: false,
+            lower: false,
+            symbols: false,
+            ..GeneratorOptions::default()
+        };
+        let cases = [
+            GeneratorOptions {
+                length: 3,
+                ..GeneratorOptions::default()
+            },
+            GeneratorOptions {
+                length: 11,
+                no_repeat: true,
+                ..only_digits.clone()
+            },
+            GeneratorOptions {
+                length: 5,
+                symbol_set: "!".to_string(),
+                min_symbols: 2,
+                no_repeat: true,
+                ..GeneratorOptions::default()
+            },
+            GeneratorOptions {
+                length: 2,
+                digits: false,
+                symbols: true,
+                symbol_set: "!".to_string(),
+                no_consecutive: true,
+                ..only_digits.clone()
+            },
+            GeneratorOptions {
+                length: 0,
+                ..GeneratorOptions::default()
+            },
+            GeneratorOptions {
+                digits: false,
+                ..only_digits
+            },
+        ];
+        for options in cases {
+            assert!(options.validate().is_err(), "{:?}", options);
+            assert!(generate(&options).is_err());
+        }
+    }
+
+    #[test]
+    fn sample_index_is_uniform() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut buckets = [0usize; 10];
+        for _ in 0..100_000 {
+            buckets[sample_index(&mut rng, 10).unwrap()] += 1;
+        }
+        // Ожидаем по 10 000 в каждой корзине; отклонение больше 5% означало бы смещение
+        for count in buckets {
+            assert!((9_500..=10_500).contains(&count), "{:?}", buckets);
+        }
+        assert!(sample_index(&mut rng, 0).is_err());
+    }
+}
@@ -0,0 +1,478 @@
+// Проверка «здоровья» хранилища: слабые, повторяющиеся и старые пароли,
+// дубликаты учётных записей, незаполненные поля, адреса без HTTPS и
+// сервисы, где доступна, но не настроена двухфакторная аутентификация.
+// Всё считается локально, пароли в отчёт не попадают
+use crate::csv;
+use crate::strength;
+use crate::twofactor::Directory;
+use crate::vault::Entry;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+const MONTH: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum IssueKind {
+    Breached,
+    Weak,
+    Reused,
+    Old,
+    Duplicate,
+    EmptyFields,
+    Insecure,
+    NoTwoFactor,
+}
+
+impl IssueKind {
+    pub const ALL: [IssueKind; 8] = [
+        IssueKind::Breached,
+        IssueKind::Weak,
+        IssueKind::Reused,
+        IssueKind::Old,
+        IssueKind::Duplicate,
+        IssueKind::EmptyFields,
+        IssueKind::Insecure,
+        IssueKind::NoTwoFactor,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            IssueKind::Breached => "Найдены в утечках",
+            IssueKind::Weak => "Слабые пароли",
+            IssueKind::Reused => "Повторяющиеся пароли",
+            IssueKind::Old => "Давно не менялись",
+            IssueKind::Duplicate => "Дубликаты учётных записей",
+            IssueKind::EmptyFields => "Незаполненные поля",
+            IssueKind::Insecure => "Адреса без HTTPS",
+            IssueKind::NoTwoFactor => "2FA доступна, но не настроена",
+        }
+    }
+
+    // Доля «здоровья», которую теряет запись с такой проблемой
+    fn penalty(&self) -> f64 {
+        match self {
+            IssueKind::Breached | IssueKind::Weak | IssueKind::Reused => 1.0,
+            IssueKind::Old => 0.5,
+            IssueKind::Duplicate | IssueKind::Insecure | IssueKind::NoTwoFactor => 0.3,
+            IssueKind::EmptyFields => 0.2,
+        }
+    }
+}
+
+// Проблема конкретной записи
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub index: usize,
+    pub kind: IssueKind,
+    pub details: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct HealthReport {
+    pub findings: Vec<Finding>,
+    pub entry_count: usize,
+    pub score: u8, // 0…100
+}
+
+impl HealthReport {
+    pub fn count(&self, kind: IssueKind) -> usize {
+        self.findings.iter().filter(|f| f.kind == kind).count()
+    }
+}
+
+// Анализируем записи. `scores` — оценки надёжности паролей (0…4), `breached` —
+// найден ли пароль в базе утечек; оба в порядке записей. `directory` — справочник
+// сервисов с 2FA
+pub fn analyze(
+    entries: &[Entry],
+    scores: &[u8],
+    breached: &[bool],
+    directory: &Directory,
+    max_age_months: u32,
+) -> HealthReport {
+    let mut findings = Vec::new();
+    let now = SystemTime::now();
+
+    // Пароли группируем по ссылкам на сами записи, не копируя их
+    let mut by_password: HashMap<&str, Vec<usize>> = HashMap::new();
+    let mut by_account: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if !entry.password.is_empty() {
+            by_password
+                .entry(entry.password.as_str())
+                .or_default()
+                .push(index);
+        }
+        if !entry.service.trim().is_empty() {
+            let account = (
+                entry.service.trim().to_lowercase(),
+                entry.login.trim().to_lowercase(),
+            );
+            by_account.entry(account).or_default().push(index);
+        }
+    }
+
+    for (index, entry) in entries.iter().enumerate() {
+        let mut empty = Vec::new();
+        if entry.service.trim().is_empty() {
+            empty.push("сервис");
+        }
+        if entry.login.trim().is_empty() {
+            empty.push("логин");
+        }
+        if entry.password.is_empty() {
+            empty.push("пароль");
+        }
+        if !empty.is_empty() {
+            findings.push(Finding {
+                index,
+                kind: IssueKind::EmptyFields,
+                details: format!("Не заполнено: {}", empty.join(", ")),
+            });
+        }
+
+        if !entry.password.is_empty() {
+            if breached.get(index).copied().unwrap_or(false) {
+                findings.push(Finding {
+                    index,
+                    kind: IssueKind::Breached,
+                    details: "Пароль есть в базе утечек HIBP".to_string(),
+                });
+            }
+
+            let score = scores.get(index).copied().unwrap_or(0);
+            if strength::is_weak_score(score) {
+                findings.push(Finding {
+                    index,
+                    kind: IssueKind::Weak,
+                    details: format!("Надёжность: {}", strength::score_label(score)),
+                });
+            }
+
+            if let Some(same) = by_password.get(entry.password.as_str())
+                && same.len() > 1
+            {
+                findings.push(Finding {
+                    index,
+                    kind: IssueKind::Reused,
+                    details: format!("Используется в {} записях", same.len()),
+                });
+            }
+        }
+
+        if max_age_months > 0
+            && let Some(changed) = entry.password_changed()
+            && let Ok(age) = now.duration_since(changed)
+            && age > MONTH * max_age_months
+        {
+            findings.push(Finding {
+                index,
+                kind: IssueKind::Old,
+                details: format!("Не менялся {} мес.", age.as_secs() / MONTH.as_secs()),
+            });
+        }
+
+        let account = (
+            entry.service.trim().to_lowercase(),
+            entry.login.trim().to_lowercase(),
+        );
+        if let Some(same) = by_account.get(&account)
+            && same.len() > 1
+        {
+            findings.push(Finding {
+                index,
+                kind: IssueKind::Duplicate,
+                details: format!("Таких записей: {}", same.len()),
+            });
+        }
+
+        if entry
+            .url
+            .trim()
+            .get(..7)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("http://"))
+        {
+            findings.push(Finding {
+                index,
+                kind: IssueKind::Insecure,
+                details: entry.url.trim().to_string(),
+            });
+        }
+
+        // Без адреса пробуем сервис, если он записан доменом ("github.com")
+        let address = if entry.url.trim().is_empty() && entry.service.contains('.') {
+            &entry.service
+        } else {
+            &entry.url
+        };
+        if entry.otp.is_none()
+            && let Some(service) = directory.lookup(address)
+        {
+            let hint = if service.supports_totp() {
+                " — коды TOTP можно хранить в записи"
+            } else {
+                ""
+            };
+            findings.push(Finding {
+                index,
+                kind: IssueKind::NoTwoFactor,
+                details: format!("{}: {}{}", service.name, service.methods_title(), hint),
+            });
+        }
+    }
+
+    // Итоговая оценка: каждая запись теряет долю по самой серьёзной своей проблеме
+    let mut worst: HashMap<usize, f64> = HashMap::new();
+    for finding in &findings {
+        let penalty = worst.entry(finding.index).or_default();
+        *penalty = penalty.max(finding.kind.penalty());
+    }
+    let score = if entries.is_empty() {
+        100
+    } else {
+        let lost: f64 = worst.values().sum();
+        (100.0 * (1.0 - lost / entries.len() as f64)).round() as u8
+    };
+
+    HealthReport {
+        findings,
+        entry_count: entries.len(),
+        score,
+    }
+}
+
+// Отчёт в CSV: запись, проблема, подробности
+pub fn to_csv(report: &HealthReport, entries: &[Entry]) -> String {
+    let mut out = String::new();
+    let header = ["service", "login", "url", "issue", "details"].map(String::from);
+    csv::write_record(&mut out, &header);
+    for finding in &report.findings {
+        let entry = &entries[finding.index];
+        let fields = [
+            entry.service.as_str(),
+            entry.login.as_str(),
+            entry.url.as_str(),
+            finding.kind.title(),
+            finding.details.as_str(),
+        ]
+        .map(csv::escape_formula);
+        csv::write_record(&mut out, &fields);
+    }
+    out
+}
+
+// Отчёт в HTML: сводка и таблица по каждому виду проблем
+pub fn to_html(report: &HealthReport, entries: &[Entry]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html lang=\"ru\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Здоровье хранилища</title>\n<style>\
+         body{font-family:sans-serif;margin:2em}\
+         table{border-collapse:collapse;margin-bottom:2em}\
+         td,th{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+         </style>\n</head>\n<body>\n",
+    );
+    html.push_str(&format!(
+        "<h1>Здоровье хранилища: {}/100</h1>\n<p>Записей: {}, проблем: {}</p>\n",
+        report.score,
+        report.entry_count,
+        report.findings.len()
+    ));
+
+    for kind in IssueKind::ALL {
+        let findings: Vec<&Finding> = report.findings.iter().filter(|f| f.kind == kind).collect();
+        if findings.is_empty() {
+            continue;
+        }
+        html.push_str(&format!(
+            "<h2>{} ({})</h2>\n<table>\n<tr><th>Сервис</th><th>Логин</th><th>Подробности</th></tr>\n",
+            kind.title(),
+            findings.len()
+        ));
+        for finding in findings {
+            let entry = &entries[finding.index];
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                html_escape(&entry.service),
+                html_escape(&entry.login),
+                html_escape(&finding.details)
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRECTORY: &str = r#"[
+        ["GitHub", {"domain": "github.com", "tfa": ["totp"]}]
+    ]"#;
+
+    fn entry(service: &str, login: &str, password: &str) -> Entry {
+        let now = SystemTime::now();
+        Entry {
+            service: service.to_string(),
+            login: login.to_string(),
+            password: password.to_string(),
+            url: "https://example.com".to_string(),
+            created_at: Some(now),
+            modified_at: Some(now),
+            ..Entry::default()
+        }
+    }
+
+    fn kinds(report: &HealthReport, index: usize) -> Vec<IssueKind> {
+        report
+            .findings
+            .iter()
+            .filter(|f| f.index == index)
+            .map(|f| f.kind)
+            .collect()
+    }
+
+    #[test]
+    fn classifies_entries() {
+        let mut old = entry("Старый", "anna", "old-but-strong");
+        old.password_changed_at = Some(SystemTime::now() - MONTH * 13);
+        let mut insecure = entry("Форум", "ivan", "forum-strong");
+        insecure.url = "HTTP://forum.example".to_string();
+        let mut github = entry("github.com", "dev", "github-strong");
+        github.url.clear();
+        let entries = vec![
+            entry("Почта", "ivan", "shared"),  // 0: повтор
+            entry("Банк", "ivan", "shared"),   // 1: повтор
+            entry("Слабый", "ivan", "123"),    // 2: слабый
+            entry("Утечка", "ivan", "leaked"), // 3: в утечке
+            entry("Дубль", "Petr", "one"),     // 4: дубликат
+            entry(" дубль ", "petr", "two"),   // 5: дубликат
+            old,                               // 6: давно не менялся
+            entry("", "", ""),                 // 7: пустые поля
+            insecure,                          // 8: без HTTPS
+            github,                            // 9: 2FA не настроена
+            entry("Порядок", "ivan", "fine"),  // 10: без проблем
+        ];
+        let mut scores = vec![4; entries.len()];
+        scores[2] = 1;
+        let mut breached = vec![false; entries.len()];
+        breached[3] = true;
+        let directory = Directory::parse(DIRECTORY).unwrap();
+
+        let report = analyze(&entries, &scores, &breached, &directory, 12);
+        assert_eq!(report.entry_count, 11);
+        assert_eq!(kinds(&report, 0), [IssueKind::Reused]);
+        assert_eq!(kinds(&report, 1), [IssueKind::Reused]);
+        assert_eq!(kinds(&report, 2), [IssueKind::Weak]);
+        assert_eq!(kinds(&report, 3), [IssueKind::Breached]);
+        assert_eq!(kinds(&report, 4), [IssueKind::Duplicate]);
+        assert_eq!(kinds(&report, 5), [IssueKind::Duplicate]);
+        assert_eq!(kinds(&report, 6), [IssueKind::Old]);
+        assert_eq!(kinds(&report, 7), [IssueKind::EmptyFields]);
+        assert_eq!(kinds(&report, 8), [IssueKind::Insecure]);
+        assert_eq!(kinds(&report, 9), [IssueKind::NoTwoFactor]);
+        assert!(kinds(&report, 10).is_empty());
+
+        assert_eq!(report.count(IssueKind::Reused), 2);
+        assert_eq!(report.findings[0].details, "Используется в 2 записях");
+        assert!(
+            report
+                .findings
+                .iter()
+                .any(|f| f.details == "Не менялся 13 мес.")
+        );
+        assert!(
+            report
+                .findings
+                .iter()
+                .any(|f| f.details == "Не заполнено: сервис, логин, пароль")
+        );
+
+        // Потери: 4 × 1.0 + 0.5 + 4 × 0.3 + 0.2 из 11 записей
+        assert_eq!(report.score, 46);
+
+        // Без ограничения срока старые пароли не отмечаются
+        let report = analyze(&entries, &scores, &breached, &directory, 0);
+        assert!(kinds(&report, 6).is_empty());
+    }
+
+    #[test]
+    fn reuse_groups_only_equal_passwords() {
+        let entries = vec![
+            entry("a", "1", "same"),
+            entry("b", "2", "Same"),
+            entry("c", "3", "same"),
+            entry("d", "4", "same "),
+            entry("e", "5", "same"),
+        ];
+        let scores = vec![4; entries.len()];
+        let report = analyze(
+            &entries,
+            &scores,
+            &[],
+            &Directory::parse(DIRECTORY).unwrap(),
+            0,
+        );
+        let reused: Vec<(usize, &str)> = report
+            .findings
+            .iter()
+            .filter(|f| f.kind == IssueKind::Reused)
+            .map(|f| (f.index, f.details.as_str()))
+            .collect();
+        assert_eq!(
+            reused,
+            [
+                (0, "Используется в 3 записях"),
+                (2, "Используется в 3 записях"),
+                (4, "Используется в 3 записях"),
+            ]
+        );
+        assert_eq!(report.score, 40);
+
+        let report = analyze(&[], &[], &[], &Directory::parse(DIRECTORY).unwrap(), 12);
+        assert_eq!(report.score, 100);
+    }
+
+    #[test]
+    fn exports_escape_values_and_skip_passwords() {
+        let mut first = entry("=cmd|' /C calc'!A0", "ivan, jr", "hunter2");
+        first.url = "http://a.example/?q=\"x\"".to_string();
+        let entries = vec![first, entry("<b>Банк</b>", "anna", "hunter2")];
+        let scores = vec![4; entries.len()];
+        let report = analyze(
+            &entries,
+            &scores,
+            &[],
+            &Directory::parse(DIRECTORY).unwrap(),
+            0,
+        );
+
+        let text = to_csv(&report, &entries);
+        assert!(!text.contains("hunter2"));
+        let rows = csv::parse(&text, ',');
+        assert_eq!(rows[0], ["service", "login", "url", "issue", "details"]);
+        let insecure = rows
+            .iter()
+            .find(|row| row[3] == IssueKind::Insecure.title())
+            .unwrap();
+        assert_eq!(insecure[0], "'=cmd|' /C calc'!A0");
+        assert_eq!(insecure[1], "ivan, jr");
+        assert_eq!(insecure[2], "http://a.example/?q=\"x\"");
+        assert_eq!(rows.len(), 1 + report.findings.len());
+
+        let html = to_html(&report, &entries);
+        assert!(!html.contains("hunter2"));
+        assert!(!html.contains("<b>Банк"));
+        assert!(html.contains("&lt;b&gt;Банк&lt;/b&gt;"));
+    }
+}
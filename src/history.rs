@@ -0,0 +1,450 @@
+use crate::vault::{Entry, TrashedEntry};
+use std::time::SystemTime;
+use zeroize::Zeroize;
+
+// Максимальное количество операций в стеке отмены
+const HISTORY_LIMIT: usize = 200;
+
+// Элементарное обратимое изменение списка записей или корзины
+enum Op {
+    InsertEntry(usize, Entry),
+    RemoveEntry(usize, Entry),
+    ReplaceEntry(usize, Box<Entry>, Box<Entry>), // индекс, было, стало
+    InsertTrash(usize, TrashedEntry),
+    RemoveTrash(usize, TrashedEntry),
+}
+
+impl Op {
+    fn apply(&self, entries: &mut Vec<Entry>, trash: &mut Vec<TrashedEntry>) {
+        match self {
+            Op::InsertEntry(index, entry) => {
+                entries.insert((*index).min(entries.len()), entry.clone())
+            }
+            Op::RemoveEntry(index, _) => {
+                if *index < entries.len() {
+                    entries.remove(*index).zeroize();
+                }
+            }
+            Op::ReplaceEntry(index, _, after) => {
+                if let Some(entry) = entries.get_mut(*index) {
+                    let mut replacement = (**after).clone();
+                    keep_usage(entry, &mut replacement);
+                    entry.zeroize();
+                    *entry = replacement;
+                }
+            }
+            Op::InsertTrash(index, trashed) => {
+                trash.insert((*index).min(trash.len()), trashed.clone())
+            }
+            Op::RemoveTrash(index, _) => {
+                if *index < trash.len() {
+                    trash.remove(*index).zeroize();
+                }
+            }
+        }
+    }
+
+    fn inverse(&self) -> Op {
+        match self {
+            Op::InsertEntry(index, entry) => Op::RemoveEntry(*index, entry.clone()),
+            Op::RemoveEntry(index, entry) => Op::InsertEntry(*index, entry.clone()),
+            Op::ReplaceEntry(index, before, after) => {
+                Op::ReplaceEntry(*index, after.clone(), before.clone())
+            }
+            Op::InsertTrash(index, trashed) => Op::RemoveTrash(*index, trashed.clone()),
+            Op::RemoveTrash(index, trashed) => Op::InsertTrash(*index, trashed.clone()),
+        }
+    }
+}
+
+// Счётчики использования и HOTP меняются вне истории, и отмена правки не должна
+// откатывать их: иначе уже выданные HOTP-коды выдавались бы повторно
+fn keep_usage(current: &Entry, replacement: &mut Entry) {
+    replacement.use_count = current.use_count;
+    replacement.last_used_at = current.last_used_at;
+    if let (Some(current), Some(replacement)) = (&current.otp, &mut replacement.otp)
+        && current.secret == replacement.secret
+    {
+        replacement.counter = replacement.counter.max(current.counter);
+    }
+}
+
+impl Zeroize for Op {
+    fn zeroize(&mut self) {
+        match self {
+            Op::InsertEntry(_, entry) | Op::RemoveEntry(_, entry) => entry.zeroize(),
+            Op::ReplaceEntry(_, before, after) => {
+                before.zeroize();
+                after.zeroize();
+            }
+            Op::InsertTrash(_, trashed) | Op::RemoveTrash(_, trashed) => trashed.zeroize(),
+        }
+    }
+}
+
+// Пользовательская операция: набор элементарных изменений, отменяемых как одно целое
+pub struct Command {
+    pub label: String,
+    ops: Vec<Op>,
+}
+
+impl Command {
+    fn undo(&self, entries: &mut Vec<Entry>, trash: &mut Vec<TrashedEntry>) {
+        for op in self.ops.iter().rev() {
+            let mut inverse = op.inverse();
+            inverse.apply(entries, trash);
+            inverse.zeroize();
+        }
+    }
+
+    fn redo(&self, entries: &mut Vec<Entry>, trash: &mut Vec<TrashedEntry>) {
+        for op in &self.ops {
+            op.apply(entries, trash);
+        }
+    }
+}
+
+// Применяем изменения сразу и одновременно записываем их в операцию.
+// Индексы каждого следующего изменения отсчитываются от состояния после предыдущих.
+pub struct Transaction<'a> {
+    entries: &'a mut Vec<Entry>,
+    trash: &'a mut Vec<TrashedEntry>,
+    command: Command,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn new(
+        label: impl Into<String>,
+        entries: &'a mut Vec<Entry>,
+        trash: &'a mut Vec<TrashedEntry>,
+    ) -> Self {
+        Self {
+            entries,
+            trash,
+            command: Command {
+                label: label.into(),
+                ops: Vec::new(),
+            },
+        }
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        self.entries
+    }
+
+    fn push(&mut self, op: Op) {
+        op.apply(self.entries, self.trash);
+        self.command.ops.push(op);
+    }
+
+    pub fn insert_entry(&mut self, index: usize, entry: Entry) {
+        let index = index.min(self.entries.len());
+        self.push(Op::InsertEntry(index, entry));
+    }
+
+    pub fn push_entry(&mut self, entry: Entry) {
+        self.insert_entry(self.entries.len(), entry);
+    }
+
+    pub fn remove_entry(&mut self, index: usize) -> Option<Entry> {
+        let entry = self.entries.get(index)?.clone();
+        self.push(Op::RemoveEntry(index, entry.clone()));
+        Some(entry)
+    }
+
+    pub fn replace_entry(&mut self, index: usize, entry: Entry) {
+        if let Some(before) = self.entries.get(index).cloned() {
+            self.push(Op::ReplaceEntry(index, Box::new(before), Box::new(entry)));
+        }
+    }
+
+    // Перемещаем запись в конец корзины
+    pub fn trash_entry(&mut self, index: usize) {
+        if let Some(entry) = self.remove_entry(index) {
+            let trashed = TrashedEntry {
+                entry,
+                deleted_at: SystemTime::now(),
+            };
+            self.push(Op::InsertTrash(self.trash.len(), trashed));
+        }
+    }
+
+    // Возвращаем запись из корзины в конец списка
+    pub fn restore_entry(&mut self, trash_index: usize) {
+        if let Some(trashed) = self.remove_trash(trash_index) {
+            self.push_entry(trashed.entry);
+        }
+    }
+
+    pub fn remove_trash(&mut self, index: usize) -> Option<TrashedEntry> {
+        let trashed = self.trash.get(index)?.clone();
+        self.push(Op::RemoveTrash(index, trashed.clone()));
+        Some(trashed)
+    }
+
+    pub fn finish(self) -> Command {
+        self.command
+    }
+}
+
+impl Zeroize for Command {
+    fn zeroize(&mut self) {
+        self.label.zeroize();
+        self.ops.zeroize();
+    }
+}
+
+// Стек отмены/повтора на время сессии
+#[derive(Default)]
+pub struct History {
+    undo_stack: Vec<Command>,
+    redo_stack: Vec<Command>,
+    revision: u64, // растёт при каждом изменении записей через историю
+}
+
+impl History {
+    // Запоминаем уже применённую операцию
+    pub fn record(&mut self, command: Command) {
+        if command.ops.is_empty() {
+            return;
+        }
+        self.redo_stack.zeroize();
+        self.redo_stack.clear();
+        self.undo_stack.push(command);
+        self.revision += 1;
+        if self.undo_stack.len() > HISTORY_LIMIT {
+            self.undo_stack.remove(0).zeroize();
+        }
+    }
+
+    // Номер текущего состояния: индексы записей сохраняют смысл, пока он не изменился
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo_stack.last().map(|c| c.label.as_str())
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo_stack.last().map(|c| c.label.as_str())
+    }
+
+    // Отменяем последнюю операцию; возвращаем её название
+    pub fn undo(
+        &mut self,
+        entries: &mut Vec<Entry>,
+        trash: &mut Vec<TrashedEntry>,
+    ) -> Option<String> {
+        let command = self.undo_stack.pop()?;
+        command.undo(entries, trash);
+        self.revision += 1;
+        let label = command.label.clone();
+        self.redo_stack.push(command);
+        Some(label)
+    }
+
+    // Повторяем последнюю отменённую операцию
+    pub fn redo(
+        &mut self,
+        entries: &mut Vec<Entry>,
+        trash: &mut Vec<TrashedEntry>,
+    ) -> Option<String> {
+        let command = self.redo_stack.pop()?;
+        command.redo(entries, trash);
+        self.revision += 1;
+        let label = command.label.clone();
+        self.undo_stack.push(command);
+        Some(label)
+    }
+}
+
+impl Zeroize for History {
+    fn zeroize(&mut self) {
+        self.undo_stack.zeroize();
+        self.undo_stack.clear();
+        self.redo_stack.zeroize();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::totp::{OtpConfig, OtpKind};
+
+    fn entry(service: &str) -> Entry {
+        Entry {
+            service: service.to_string(),
+            ..Entry::default()
+        }
+    }
+
+    fn services(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|e| e.service.as_str()).collect()
+    }
+
+    fn trashed(trash: &[TrashedEntry]) -> Vec<&str> {
+        trash.iter().map(|t| t.entry.service.as_str()).collect()
+    }
+
+    #[test]
+    fn transaction_applies_and_reverts() {
+        let mut entries = vec![entry("a"), entry("b"), entry("c")];
+        let mut trash = Vec::new();
+        let mut history = History::default();
+
+        let mut tx = Transaction::new("Правка", &mut entries, &mut trash);
+        tx.trash_entry(1);
+        // Индекс следующего изменения — уже после удаления "b"
+        tx.replace_entry(1, entry("c2"));
+        tx.insert_entry(0, entry("new"));
+        assert_eq!(services(tx.entries()), ["new", "a", "c2"]);
+        history.record(tx.finish());
+        assert_eq!(trashed(&trash), ["b"]);
+
+        assert_eq!(
+            history.undo(&mut entries, &mut trash).as_deref(),
+            Some("Правка")
+        );
+        assert_eq!(services(&entries), ["a", "b", "c"]);
+        assert!(trash.is_empty());
+
+        assert_eq!(history.redo_label(), Some("Правка"));
+        history.redo(&mut entries, &mut trash);
+        assert_eq!(services(&entries), ["new", "a", "c2"]);
+        assert_eq!(trashed(&trash), ["b"]);
+    }
+
+    #[test]
+    fn restore_and_purge() {
+        let mut entries = vec![entry("a")];
+        let mut trash = vec![
+            TrashedEntry {
+                entry: entry("b"),
+                deleted_at: SystemTime::now(),
+            },
+            TrashedEntry {
+                entry: entry("c"),
+                deleted_at: SystemTime::now(),
+            },
+        ];
+        let mut history = History::default();
+
+        let mut tx = Transaction::new("Восстановление", &mut entries, &mut trash);
+        tx.restore_entry(1);
+        history.record(tx.finish());
+        let mut tx = Transaction::new("Очистка", &mut entries, &mut trash);
+        tx.remove_trash(0);
+        history.record(tx.finish());
+        assert_eq!(services(&entries), ["a", "c"]);
+        assert!(trash.is_empty());
+
+        history.undo(&mut entries, &mut trash);
+        history.undo(&mut entries, &mut trash);
+        assert_eq!(services(&entries), ["a"]);
+        assert_eq!(trashed(&trash), ["b", "c"]);
+        assert!(history.undo(&mut entries, &mut trash).is_none());
+    }
+
+    #[test]
+    fn new_edit_drops_redo() {
+        let mut entries = vec![entry("a")];
+        let mut trash = Vec::new();
+        let mut history = History::default();
+
+        let mut tx = Transaction::new("Первая", &mut entries, &mut trash);
+        tx.replace_entry(0, entry("b"));
+        history.record(tx.finish());
+        history.undo(&mut entries, &mut trash);
+        assert_eq!(history.redo_label(), Some("Первая"));
+
+        let mut tx = Transaction::new("Вторая", &mut entries, &mut trash);
+        tx.replace_entry(0, entry("c"));
+        history.record(tx.finish());
+        assert_eq!(history.redo_label(), None);
+        assert!(history.redo(&mut entries, &mut trash).is_none());
+        assert_eq!(services(&entries), ["c"]);
+
+        // Пустая операция в историю не попадает
+        let tx = Transaction::new("Ничего", &mut entries, &mut trash);
+        history.record(tx.finish());
+        assert_eq!(history.undo_label(), Some("Вторая"));
+    }
+
+    #[test]
+    fn oldest_commands_are_evicted() {
+        let mut entries = vec![entry("0")];
+        let mut trash = Vec::new();
+        let mut history = History::default();
+        for i in 1..=HISTORY_LIMIT + 5 {
+            let mut tx = Transaction::new(i.to_string(), &mut entries, &mut trash);
+            tx.replace_entry(0, entry(&i.to_string()));
+            history.record(tx.finish());
+        }
+
+        let mut undone = 0;
+        while history.undo(&mut entries, &mut trash).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_LIMIT);
+        // Откатиться можно только до состояния после вытесненных операций
+        assert_eq!(services(&entries), ["5"]);
+    }
+
+    #[test]
+    fn revision_counts_changes() {
+        let mut entries = vec![entry("a")];
+        let mut trash = Vec::new();
+        let mut history = History::default();
+        assert_eq!(history.revision(), 0);
+
+        let tx = Transaction::new("Ничего", &mut entries, &mut trash);
+        history.record(tx.finish());
+        assert_eq!(history.revision(), 0);
+
+        let mut tx = Transaction::new("Правка", &mut entries, &mut trash);
+        tx.replace_entry(0, entry("b"));
+        history.record(tx.finish());
+        assert_eq!(history.revision(), 1);
+        history.undo(&mut entries, &mut trash);
+        history.redo(&mut entries, &mut trash);
+        assert_eq!(history.revision(), 3);
+        // Пустые стеки ничего не меняют
+        history.redo(&mut entries, &mut trash);
+        assert_eq!(history.revision(), 3);
+    }
+
+    #[test]
+    fn undo_keeps_usage_and_hotp_counter() {
+        let mut hotp = entry("bank");
+        hotp.otp = Some(OtpConfig {
+            kind: OtpKind::Hotp,
+            secret: "JBSWY3DPEHPK3PXP".to_string(),
+            counter: 5,
+            ..OtpConfig::default()
+        });
+        let mut entries = vec![hotp];
+        let mut trash = Vec::new();
+        let mut history = History::default();
+
+        let mut edited = entries[0].clone();
+        edited.notes = "правка".to_string();
+        let mut tx = Transaction::new("Правка", &mut entries, &mut trash);
+        tx.replace_entry(0, edited);
+        history.record(tx.finish());
+
+        // Коды выдавались уже после правки
+        entries[0].otp.as_mut().unwrap().counter = 8;
+        entries[0].use_count = 3;
+
+        history.undo(&mut entries, &mut trash);
+        assert!(entries[0].notes.is_empty());
+        assert_eq!(entries[0].otp.as_ref().unwrap().counter, 8);
+        assert_eq!(entries[0].use_count, 3);
+
+        history.redo(&mut entries, &mut trash);
+        assert_eq!(entries[0].notes, "правка");
+        assert_eq!(entries[0].otp.as_ref().unwrap().counter, 8);
+    }
+}
@@ -0,0 +1,235 @@
+// Общая часть импорта из других форматов: сопоставление с существующими записями,
+// устранение дубликатов и итоговый план (что добавить, что объединить, что пропустить)
+use crate::vault::Entry;
+use std::collections::HashMap;
+use zeroize::Zeroize;
+
+// Что делать с записью, учётная запись которой уже есть в хранилище
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Duplicates {
+    Skip,
+    Merge,
+    KeepBoth,
+}
+
+impl Duplicates {
+    pub const ALL: [Duplicates; 3] = [Duplicates::Skip, Duplicates::Merge, Duplicates::KeepBoth];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Duplicates::Skip => "Пропустить",
+            Duplicates::Merge => "Объединить (обновить пароль и дополнить поля)",
+            Duplicates::KeepBoth => "Добавить как новые записи",
+        }
+    }
+}
+
+// План импорта
+#[derive(Default)]
+pub struct Plan {
+    pub added: Vec<Entry>,
+    pub merged: Vec<(usize, Entry)>, // индекс существующей записи → объединённая запись
+    pub skipped: usize,
+}
+
+impl Zeroize for Plan {
+    fn zeroize(&mut self) {
+        self.added.zeroize();
+        for (_, entry) in &mut self.merged {
+            entry.zeroize();
+        }
+        self.merged.clear();
+    }
+}
+
+// Куда ведёт учётная запись: в существующую запись или в уже добавленную импортом
+#[derive(Clone, Copy)]
+enum Target {
+    Existing(usize),
+    Added(usize),
+}
+
+// Учётная запись — сервис и логин без учёта регистра
+fn account_key(entry: &Entry) -> (String, String) {
+    (
+        entry.service.trim().to_lowercase(),
+        entry.login.trim().to_lowercase(),
+    )
+}
+
+// Составляем план. Полные копии (тот же пароль) пропускаются всегда,
+// повторы внутри импортируемых данных сравниваются так же, как с хранилищем
+pub fn plan(existing: &[Entry], incoming: &[Entry], duplicates: Duplicates) -> Plan {
+    let mut plan = Plan::default();
+    let mut accounts: HashMap<(String, String), Target> = existing
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (account_key(entry), Target::Existing(index)))
+        .collect();
+
+    for entry in incoming {
+        let key = account_key(entry);
+        let Some(&target) = accounts.get(&key) else {
+            accounts.insert(key, Target::Added(plan.added.len()));
+            plan.added.push(entry.clone());
+            continue;
+        };
+
+        let current = match target {
+            Target::Existing(index) => plan
+                .merged
+                .iter()
+                .find(|(merged, _)| *merged == index)
+                .map_or(&existing[index], |(_, entry)| entry),
+            Target::Added(index) => &plan.added[index],
+        };
+        if same_entry(current, entry) {
+            plan.skipped += 1;
+            continue;
+        }
+
+        match duplicates {
+            Duplicates::Skip => plan.skipped += 1,
+            Duplicates::KeepBoth => plan.added.push(entry.clone()),
+            Duplicates::Merge => {
+                let mut merged = current.clone();
+                merge_into(&mut merged, entry);
+                match target {
+                    Target::Existing(index) => {
+                        match plan.merged.iter_mut().find(|(i, _)| *i == index) {
+                            Some((_, slot)) => *slot = merged,
+                            None => plan.merged.push((index, merged)),
+                        }
+                    }
+                    Target::Added(index) => plan.added[index] = merged,
+                }
+            }
+        }
+    }
+    plan
+}
+
+fn same_entry(a: &Entry, b: &Entry) -> bool {
+    a.password == b.password && a.url.trim() == b.url.trim()
+}
+
+// Новые значения дополняют запись; изменившийся пароль уходит в историю
+fn merge_into(entry: &mut Entry, incoming: &Entry) {
+    if !incoming.password.is_empty() {
+        entry.set_password(incoming.password.clone());
+    }
+    if entry.url.is_empty() {
+        entry.url = incoming.url.clone();
+    }
+    if entry.otp.is_none() {
+        entry.otp = incoming.otp.clone();
+    }
+    if entry.folder.is_empty() {
+        entry.folder = incoming.folder.clone();
+    }
+    if !incoming.notes.is_empty() && !entry.notes.contains(incoming.notes.as_str()) {
+        if !entry.notes.is_empty() {
+            entry.notes.push_str("\n\n");
+        }
+        entry.notes.push_str(&incoming.notes);
+    }
+    for tag in &incoming.tags {
+        if !entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            entry.tags.push(tag.clone());
+        }
+    }
+    for field in &incoming.fields {
+        if !entry.fields.iter().any(|f| f.name == field.name) {
+            entry.fields.push(field.clone());
+        }
+    }
+    entry
+        .attachments
+        .extend(incoming.attachments.iter().cloned());
+    entry.favorite |= incoming.favorite;
+    entry.modified_at = Some(std::time::SystemTime::now());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(service: &str, login: &str, password: &str) -> Entry {
+        Entry {
+            service: service.to_string(),
+            login: login.to_string(),
+            password: password.to_string(),
+            ..Entry::default()
+        }
+    }
+
+    #[test]
+    fn exact_copies_are_always_skipped() {
+        let existing = [entry("Mail", "ivan", "one")];
+        // Учётная запись сравнивается без учёта регистра и пробелов
+        let incoming = [entry(" mail ", "IVAN", "one")];
+        for duplicates in Duplicates::ALL {
+            let plan = plan(&existing, &incoming, duplicates);
+            assert!(plan.added.is_empty());
+            assert!(plan.merged.is_empty());
+            assert_eq!(plan.skipped, 1);
+        }
+    }
+
+    #[test]
+    fn skip_and_keep_both() {
+        let existing = [entry("Mail", "ivan", "one")];
+        let incoming = [entry("Mail", "ivan", "two"), entry("Bank", "ivan", "three")];
+
+        let skip = plan(&existing, &incoming, Duplicates::Skip);
+        assert_eq!(skip.skipped, 1);
+        assert_eq!(skip.added.len(), 1);
+        assert_eq!(skip.added[0].service, "Bank");
+
+        let both = plan(&existing, &incoming, Duplicates::KeepBoth);
+        assert_eq!(both.skipped, 0);
+        assert_eq!(both.added.len(), 2);
+        assert_eq!(both.added[0].password, "two");
+    }
+
+    #[test]
+    fn merge_updates_password_and_fills_fields() {
+        let mut current = entry("Mail", "ivan", "one");
+        current.tags = vec!["work".to_string()];
+        let mut newer = entry("Mail", "ivan", "two");
+        newer.url = "https://mail.example.com".to_string();
+        newer.notes = "из Chrome".to_string();
+        newer.tags = vec!["Work".to_string(), "mail".to_string()];
+        let newest = entry("Mail", "ivan", "three");
+
+        let plan = plan(&[current], &[newer, newest], Duplicates::Merge);
+        assert!(plan.added.is_empty());
+        assert_eq!(plan.merged.len(), 1);
+        let (index, merged) = &plan.merged[0];
+        assert_eq!(*index, 0);
+        // Второе совпадение объединяется с уже объединённой записью
+        assert_eq!(merged.password, "three");
+        let history: Vec<&str> = merged
+            .password_history
+            .iter()
+            .map(|old| old.password.as_str())
+            .collect();
+        assert_eq!(history, ["one", "two"]);
+        assert_eq!(merged.url, "https://mail.example.com");
+        assert_eq!(merged.notes, "из Chrome");
+        assert_eq!(merged.tags, ["work", "mail"]);
+    }
+
+    #[test]
+    fn duplicates_inside_import() {
+        let incoming = [
+            entry("Mail", "ivan", "one"),
+            entry("Mail", "ivan", "one"),
+            entry("Mail", "ivan", "two"),
+        ];
+        let plan = plan(&[], &incoming, Duplicates::Merge);
+        assert_eq!(plan.skipped, 1);
+        assert_eq!(plan.added.len(), 1);
+        assert_eq!(plan.added[0].password, "two");
+    }
+}
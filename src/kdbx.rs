@@ -0,0 +1,1693 @@
+// Базы KeePass в формате KDBX 4.x и незашифрованный XML KeePass 2.x: чтение и запись.
+// Файл: сигнатура и заголовок (шифр, параметры вывода ключа, соль) → SHA-256 и HMAC заголовка →
+// блоки с HMAC → расшифрованные AES-256-CBC или ChaCha20 данные, при необходимости сжатые gzip →
+// внутренний заголовок (ключ защиты значений, вложения) → XML с группами и записями.
+// Ключ базы: SHA-256 от хеша пароля и ключа из ключевого файла, затем AES-KDF или Argon2d/id.
+// XML внутри KDBX и XML-экспорт KeePass разбираются и собираются одним кодом
+use crate::time;
+use crate::totp::OtpConfig;
+use crate::vault::{self, Attachment, CustomField, Entry, Expiry, OldPassword};
+use aes::Aes256;
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{
+    BlockDecryptMut, BlockEncrypt, BlockEncryptMut, KeyInit, KeyIvInit, StreamCipher,
+};
+use base64::{Engine as _, engine::general_purpose};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use roxmltree::{Document, Node, NodeId};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroize;
+
+const SIGNATURE: [u8; 8] = [0x03, 0xD9, 0xA2, 0x9A, 0x67, 0xFB, 0x4B, 0xB5];
+const MAJOR_VERSION: u16 = 4;
+
+// Идентификаторы шифров и функций вывода ключа
+const CIPHER_AES256: [u8; 16] = [
+    0x31, 0xC1, 0xF2, 0xE6, 0xBF, 0x71, 0x43, 0x50, 0xBE, 0x58, 0x05, 0x21, 0x6A, 0xFC, 0x5A, 0xFF,
+];
+const CIPHER_CHACHA20: [u8; 16] = [
+    0xD6, 0x03, 0x8A, 0x2B, 0x8B, 0x6F, 0x4C, 0xB5, 0xA5, 0x24, 0x33, 0x9A, 0x31, 0xDB, 0xB5, 0x9A,
+];
+const CIPHER_TWOFISH: [u8; 16] = [
+    0xAD, 0x68, 0xF2, 0x9F, 0x57, 0x6F, 0x4B, 0xB9, 0xA3, 0x6A, 0xD4, 0x7A, 0xF9, 0x65, 0x34, 0x6C,
+];
+const KDF_AES_KDBX3: [u8; 16] = [
+    0xC9, 0xD9, 0xF3, 0x9A, 0x62, 0x8A, 0x44, 0x60, 0xBF, 0x74, 0x0D, 0x08, 0xC1, 0x8A, 0x4F, 0xEA,
+];
+const KDF_AES_KDBX4: [u8; 16] = [
+    0x7C, 0x02, 0xBB, 0x82, 0x79, 0xA7, 0x4A, 0xC0, 0x92, 0x7D, 0x11, 0x4A, 0x00, 0x64, 0x82, 0x38,
+];
+const KDF_ARGON2D: [u8; 16] = [
+    0xEF, 0x63, 0x6D, 0xDF, 0x8C, 0x29, 0x44, 0x4B, 0x91, 0xF7, 0xA9, 0xA4, 0x03, 0xE3, 0x0A, 0x0C,
+];
+const KDF_ARGON2ID: [u8; 16] = [
+    0x9E, 0x29, 0x8B, 0x19, 0x56, 0xDB, 0x47, 0x73, 0xB2, 0x3D, 0xFC, 0x3E, 0xC6, 0xF0, 0xA1, 0xE6,
+];
+
+// Поля внешнего заголовка
+const HEADER_END: u8 = 0;
+const HEADER_CIPHER: u8 = 2;
+const HEADER_COMPRESSION: u8 = 3;
+const HEADER_MASTER_SEED: u8 = 4;
+const HEADER_IV: u8 = 7;
+const HEADER_KDF: u8 = 11;
+
+// Поля внутреннего заголовка
+const INNER_END: u8 = 0;
+const INNER_STREAM_ID: u8 = 1;
+const INNER_STREAM_KEY: u8 = 2;
+const INNER_BINARY: u8 = 3;
+
+// Шифры защищённых значений
+const STREAM_SALSA20: u32 = 2;
+const STREAM_CHACHA20: u32 = 3;
+const SALSA20_NONCE: [u8; 8] = [0xE8, 0x30, 0x09, 0x4B, 0x97, 0x20, 0x5D, 0x2A];
+
+// Типы значений в словаре параметров
+const VARIANT_UINT32: u8 = 0x04;
+const VARIANT_UINT64: u8 = 0x05;
+const VARIANT_BYTES: u8 = 0x42;
+
+// Время в KDBX 4 — секунды от 0001-01-01
+const SECONDS_BEFORE_UNIX_EPOCH: i64 = 62_135_596_800;
+
+// Параметры Argon2id для новых баз (как у KeePassXC по умолчанию)
+const ARGON2_MEMORY: u64 = 64 * 1024 * 1024;
+const ARGON2_ITERATIONS: u64 = 10;
+const ARGON2_PARALLELISM: u32 = 2;
+
+// Размер блока зашифрованных данных
+const BLOCK_SIZE: usize = 1024 * 1024;
+
+// Поля записи, которые переносятся в одноимённые поля Shroombrella
+const TITLE: &str = "Title";
+const USER_NAME: &str = "UserName";
+const PASSWORD: &str = "Password";
+const URL: &str = "URL";
+const NOTES: &str = "Notes";
+const OTP: &str = "otp";
+const TOTP_SEED: &str = "TOTP Seed";
+const TOTP_SETTINGS: &str = "TOTP Settings";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Cipher {
+    Aes256,
+    ChaCha20,
+}
+
+enum Kdf {
+    Aes {
+        seed: Vec<u8>,
+        rounds: u64,
+    },
+    Argon2 {
+        algorithm: argon2::Algorithm,
+        salt: Vec<u8>,
+        memory: u64, // в байтах
+        iterations: u64,
+        parallelism: u32,
+        version: u32,
+    },
+}
+
+struct Header {
+    cipher: Cipher,
+    compressed: bool,
+    master_seed: Vec<u8>,
+    iv: Vec<u8>,
+    kdf: Kdf,
+}
+
+// Последовательное чтение двоичных полей
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or("Файл повреждён или обрезан")?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.data[self.position..];
+        self.position = self.data.len();
+        bytes
+    }
+}
+
+// Записи из базы KDBX и замечания о том, что перенесено не полностью.
+// Пароль можно не указывать, если база защищена только ключевым файлом
+pub fn read(
+    data: &[u8],
+    password: &str,
+    key_file: Option<&[u8]>,
+) -> Result<(Vec<Entry>, Vec<String>), String> {
+    let mut reader = Reader::new(data);
+    if reader.take(8).ok() != Some(&SIGNATURE[..]) {
+        return Err("Это не база KeePass (.kdbx)".to_string());
+    }
+    let _minor = reader.u16()?;
+    let major = reader.u16()?;
+    if major != MAJOR_VERSION {
+        return Err(format!(
+            "Поддерживается только KDBX 4 (у файла версия {}). \
+             Пересохраните базу в KeePassXC или KeePass 2.35+",
+            major
+        ));
+    }
+
+    let header = read_header(&mut reader)?;
+    let header_bytes = &data[..reader.position];
+    let header_hash = reader.take(32)?;
+    if Sha256::digest(header_bytes).as_slice() != header_hash {
+        return Err("Заголовок базы повреждён".to_string());
+    }
+
+    let (mut cipher_key, mut hmac_key) = derive_keys(&header, password, key_file)?;
+
+    // HMAC заголовка проверяет ключ: при неверном пароле он не совпадёт
+    let header_mac = reader.take(32)?;
+    let mut mac = block_hmac(&hmac_key, u64::MAX);
+    mac.update(header_bytes);
+    if mac.verify_slice(header_mac).is_err() {
+        cipher_key.zeroize();
+        hmac_key.zeroize();
+        return Err("Неверный пароль или ключевой файл".to_string());
+    }
+
+    let encrypted = read_blocks(&mut reader, &hmac_key);
+    hmac_key.zeroize();
+    let decrypted = encrypted.and_then(|encrypted| decrypt(&header, &cipher_key, &encrypted));
+    cipher_key.zeroize();
+    let mut payload = decrypted?;
+
+    if header.compressed {
+        let mut decompressed = Vec::new();
+        let result =
+            flate2::read::GzDecoder::new(payload.as_slice()).read_to_end(&mut decompressed);
+        payload.zeroize();
+        if result.is_err() {
+            decompressed.zeroize();
+            return Err("Не удалось распаковать содержимое базы".to_string());
+        }
+        payload = decompressed;
+    }
+
+    let result = read_payload(&payload);
+    payload.zeroize();
+    result
+}
+
+// Ключ шифра и ключ HMAC блоков из составного ключа и соли базы
+fn derive_keys(
+    header: &Header,
+    password: &str,
+    key_file: Option<&[u8]>,
+) -> Result<([u8; 32], [u8; 64]), String> {
+    let mut composite = composite_key(password, key_file)?;
+    let transformed = transform_key(&composite, &header.kdf);
+    composite.zeroize();
+    let mut transformed = transformed?;
+
+    let mut seeded = header.master_seed.clone();
+    seeded.extend_from_slice(&transformed);
+    let cipher_key = Sha256::digest(&seeded).into();
+    seeded.push(0x01);
+    let hmac_key = Sha512::digest(&seeded).into();
+    seeded.zeroize();
+    transformed.zeroize();
+    Ok((cipher_key, hmac_key))
+}
+
+fn read_header(reader: &mut Reader) -> Result<Header, String> {
+    let mut cipher = None;
+    let mut compressed = false;
+    let mut master_seed = None;
+    let mut iv = None;
+    let mut kdf = None;
+
+    loop {
+        let id = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let value = reader.take(size)?;
+        match id {
+            HEADER_END => break,
+            HEADER_CIPHER => {
+                cipher = Some(match value {
+                    v if v == CIPHER_AES256 => Cipher::Aes256,
+                    v if v == CIPHER_CHACHA20 => Cipher::ChaCha20,
+                    v if v == CIPHER_TWOFISH => {
+                        return Err("Шифр Twofish не поддерживается. Смените шифр на AES-256 \
+                             или ChaCha20 в настройках базы KeePass"
+                            .to_string());
+                    }
+                    _ => return Err("Неизвестный шифр базы".to_string()),
+                });
+            }
+            HEADER_COMPRESSION => compressed = value.first().is_some_and(|flag| *flag != 0),
+            HEADER_MASTER_SEED => master_seed = Some(value.to_vec()),
+            HEADER_IV => iv = Some(value.to_vec()),
+            HEADER_KDF => kdf = Some(parse_kdf(value)?),
+            _ => {}
+        }
+    }
+
+    let missing = || "В заголовке базы не хватает полей".to_string();
+    let header = Header {
+        cipher: cipher.ok_or_else(missing)?,
+        compressed,
+        master_seed: master_seed.ok_or_else(missing)?,
+        iv: iv.ok_or_else(missing)?,
+        kdf: kdf.ok_or_else(missing)?,
+    };
+    let iv_len = match header.cipher {
+        Cipher::Aes256 => 16,
+        Cipher::ChaCha20 => 12,
+    };
+    if header.master_seed.len() != 32 || header.iv.len() != iv_len {
+        return Err("Заголовок базы повреждён".to_string());
+    }
+    Ok(header)
+}
+
+// Словарь параметров (VariantDictionary): тип, имя и значение каждого поля
+fn parse_variant_dictionary(data: &[u8]) -> Result<HashMap<String, Vec<u8>>, String> {
+    let mut reader = Reader::new(data);
+    let version = reader.u16()?;
+    if version >> 8 != 1 {
+        return Err("Неизвестная версия параметров ключа".to_string());
+    }
+    let mut values = HashMap::new();
+    loop {
+        let kind = reader.u8()?;
+        if kind == 0 {
+            break;
+        }
+        let name_len = reader.u32()? as usize;
+        let name = String::from_utf8_lossy(reader.take(name_len)?).into_owned();
+        let value_len = reader.u32()? as usize;
+        values.insert(name, reader.take(value_len)?.to_vec());
+    }
+    Ok(values)
+}
+
+fn parse_kdf(data: &[u8]) -> Result<Kdf, String> {
+    let params = parse_variant_dictionary(data)?;
+    let bytes = |name: &str| {
+        params
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Нет параметра ключа «{}»", name))
+    };
+    let number = |name: &str| -> Result<u64, String> {
+        let value = bytes(name)?;
+        match value.len() {
+            4 => Ok(u32::from_le_bytes(value.try_into().unwrap()) as u64),
+            8 => Ok(u64::from_le_bytes(value.try_into().unwrap())),
+            _ => Err(format!("Неверный параметр ключа «{}»", name)),
+        }
+    };
+
+    let uuid = bytes("$UUID")?;
+    if uuid == KDF_AES_KDBX3 || uuid == KDF_AES_KDBX4 {
+        return Ok(Kdf::Aes {
+            seed: bytes("S")?,
+            rounds: number("R")?,
+        });
+    }
+    let algorithm = if uuid == KDF_ARGON2D {
+        argon2::Algorithm::Argon2d
+    } else if uuid == KDF_ARGON2ID {
+        argon2::Algorithm::Argon2id
+    } else {
+        return Err("Неизвестная функция вывода ключа".to_string());
+    };
+    Ok(Kdf::Argon2 {
+        algorithm,
+        salt: bytes("S")?,
+        memory: number("M")?,
+        iterations: number("I")?,
+        parallelism: number("P")? as u32,
+        version: number("V")? as u32,
+    })
+}
+
+// Составной ключ: хеш пароля и ключ из ключевого файла.
+// Пустой пароль вместе с ключевым файлом означает «без пароля»
+fn composite_key(password: &str, key_file: Option<&[u8]>) -> Result<[u8; 32], String> {
+    let mut hasher = Sha256::new();
+    if !password.is_empty() || key_file.is_none() {
+        hasher.update(Sha256::digest(password.as_bytes()));
+    }
+    if let Some(data) = key_file {
+        let mut key = key_file_key(data)?;
+        hasher.update(&key);
+        key.zeroize();
+    }
+    Ok(hasher.finalize().into())
+}
+
+// Ключ из ключевого файла: XML (версии 1.0 и 2.0), 32 байта, 64 шестнадцатеричных
+// символа или хеш SHA-256 содержимого любого другого файла
+fn key_file_key(data: &[u8]) -> Result<Vec<u8>, String> {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let trimmed = text.trim();
+        if trimmed.starts_with('<') && trimmed.contains("<KeyFile") {
+            return xml_key_file_key(trimmed);
+        }
+        if trimmed.len() == 64
+            && let Some(key) = decode_hex(trimmed)
+        {
+            return Ok(key);
+        }
+    }
+    if data.len() == 32 {
+        return Ok(data.to_vec());
+    }
+    Ok(Sha256::digest(data).to_vec())
+}
+
+fn xml_key_file_key(text: &str) -> Result<Vec<u8>, String> {
+    let invalid = || "Неверный формат ключевого файла".to_string();
+    let document = Document::parse(text).map_err(|_| invalid())?;
+    let root = document.root_element();
+    let version = child(root, "Meta")
+        .and_then(|meta| child(meta, "Version"))
+        .and_then(|version| version.text())
+        .unwrap_or("1.0");
+    let data = child(root, "Key")
+        .and_then(|key| child(key, "Data"))
+        .ok_or_else(invalid)?;
+    let value = data.text().unwrap_or_default();
+
+    if version.trim().starts_with('2') {
+        let hex: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+        let key = decode_hex(&hex)
+            .filter(|key| key.len() == 32)
+            .ok_or_else(invalid)?;
+        // Первые 4 байта хеша ключа защищают от опечаток при ручном вводе
+        if let Some(hash) = data.attribute("Hash")
+            && decode_hex(hash.trim()).as_deref() != Some(&Sha256::digest(&key)[..4])
+        {
+            return Err("Ключевой файл повреждён: не совпадает контрольная сумма".to_string());
+        }
+        Ok(key)
+    } else {
+        general_purpose::STANDARD
+            .decode(value.trim())
+            .map_err(|_| invalid())
+    }
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Вывод ключа: долгое преобразование, которое замедляет перебор паролей
+fn transform_key(composite: &[u8; 32], kdf: &Kdf) -> Result<[u8; 32], String> {
+    match kdf {
+        Kdf::Aes { seed, rounds } => {
+            let cipher = Aes256::new_from_slice(seed).map_err(|_| "Неверные параметры AES-KDF")?;
+            let mut key = *composite;
+            for _ in 0..*rounds {
+                for block in key.chunks_exact_mut(16) {
+                    cipher.encrypt_block(block.into());
+                }
+            }
+            let transformed = Sha256::digest(key).into();
+            key.zeroize();
+            Ok(transformed)
+        }
+        Kdf::Argon2 {
+            algorithm,
+            salt,
+            memory,
+            iterations,
+            parallelism,
+            version,
+        } => {
+            let version = if *version == 0x10 {
+                argon2::Version::V0x10
+            } else {
+                argon2::Version::V0x13
+            };
+            let params = argon2::Params::new(
+                (memory / 1024) as u32,
+                *iterations as u32,
+                *parallelism,
+                Some(32),
+            )
+            .map_err(|e| format!("Неверные параметры Argon2: {}", e))?;
+            let mut key = [0u8; 32];
+            argon2::Argon2::new(*algorithm, version, params)
+                .hash_password_into(composite, salt, &mut key)
+                .map_err(|e| format!("Ошибка Argon2: {}", e))?;
+            Ok(key)
+        }
+    }
+}
+
+// Ключ HMAC блока зависит от его номера; заголовок подписывается номером u64::MAX
+fn block_hmac(hmac_key: &[u8; 64], index: u64) -> Hmac<Sha256> {
+    let mut hasher = Sha512::new();
+    hasher.update(index.to_le_bytes());
+    hasher.update(hmac_key);
+    let mut key = hasher.finalize();
+    let mac = <Hmac<Sha256> as Mac>::new_from_slice(&key).expect("HMAC принимает ключ любой длины");
+    key.as_mut_slice().zeroize();
+    mac
+}
+
+// Блоки: HMAC, размер и данные; последний блок пустой
+fn read_blocks(reader: &mut Reader, hmac_key: &[u8; 64]) -> Result<Vec<u8>, String> {
+    let mut encrypted = Vec::new();
+    let mut index = 0u64;
+    loop {
+        let block_mac = reader.take(32)?;
+        let size = reader.u32()?;
+        let data = reader.take(size as usize)?;
+        let mut mac = block_hmac(hmac_key, index);
+        mac.update(&index.to_le_bytes());
+        mac.update(&size.to_le_bytes());
+        mac.update(data);
+        if mac.verify_slice(block_mac).is_err() {
+            return Err(format!("Блок данных {} повреждён", index));
+        }
+        if size == 0 {
+            return Ok(encrypted);
+        }
+        encrypted.extend_from_slice(data);
+        index += 1;
+    }
+}
+
+fn decrypt(header: &Header, key: &[u8; 32], encrypted: &[u8]) -> Result<Vec<u8>, String> {
+    match header.cipher {
+        Cipher::Aes256 => cbc::Decryptor::<Aes256>::new_from_slices(key, &header.iv)
+            .map_err(|_| "Неверные параметры шифра")?
+            .decrypt_padded_vec_mut::<Pkcs7>(encrypted)
+            .map_err(|_| "Не удалось расшифровать базу".to_string()),
+        Cipher::ChaCha20 => {
+            let mut data = encrypted.to_vec();
+            chacha20::ChaCha20::new_from_slices(key, &header.iv)
+                .map_err(|_| "Неверные параметры шифра")?
+                .apply_keystream(&mut data);
+            Ok(data)
+        }
+    }
+}
+
+// Шифр защищённых значений (паролей и скрытых полей) внутри XML
+enum InnerStream {
+    Salsa20(salsa20::Salsa20),
+    ChaCha20(chacha20::ChaCha20),
+}
+
+impl InnerStream {
+    fn new(id: u32, key: &[u8]) -> Result<Option<Self>, String> {
+        match id {
+            0 => Ok(None),
+            STREAM_SALSA20 => {
+                let key = Sha256::digest(key);
+                Ok(Some(Self::Salsa20(
+                    salsa20::Salsa20::new_from_slices(&key, &SALSA20_NONCE)
+                        .map_err(|_| "Неверный ключ защиты значений")?,
+                )))
+            }
+            STREAM_CHACHA20 => {
+                let hash = Sha512::digest(key);
+                Ok(Some(Self::ChaCha20(
+                    chacha20::ChaCha20::new_from_slices(&hash[..32], &hash[32..44])
+                        .map_err(|_| "Неверный ключ защиты значений")?,
+                )))
+            }
+            _ => Err("Неизвестный шифр защищённых значений".to_string()),
+        }
+    }
+
+    fn apply(&mut self, data: &mut [u8]) {
+        match self {
+            Self::Salsa20(cipher) => cipher.apply_keystream(data),
+            Self::ChaCha20(cipher) => cipher.apply_keystream(data),
+        }
+    }
+}
+
+// Внутренний заголовок, затем XML
+fn read_payload(payload: &[u8]) -> Result<(Vec<Entry>, Vec<String>), String> {
+    let mut reader = Reader::new(payload);
+    let mut stream_id = 0;
+    let mut stream_key = Vec::new();
+    let mut binaries = Vec::new();
+    loop {
+        let id = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let value = reader.take(size)?;
+        match id {
+            INNER_END => break,
+            INNER_STREAM_ID if value.len() == 4 => {
+                stream_id = u32::from_le_bytes(value.try_into().unwrap())
+            }
+            INNER_STREAM_KEY => stream_key = value.to_vec(),
+            // Первый байт — флаги вложения, дальше содержимое
+            INNER_BINARY => binaries.push(value.get(1..).unwrap_or_default().to_vec()),
+            _ => {}
+        }
+    }
+    let stream = InnerStream::new(stream_id, &stream_key);
+    stream_key.zeroize();
+
+    let xml = std::str::from_utf8(reader.rest()).map_err(|_| "Содержимое базы повреждено");
+    let result = stream.and_then(|stream| entries_from_xml(xml?, &binaries, stream));
+    binaries.zeroize();
+    result
+}
+
+// Записи из XML KeePass. Защищённые значения расшифровываются потоковым шифром
+// строго в порядке документа, поэтому сначала проходим весь документ
+fn entries_from_xml(
+    xml: &str,
+    binaries: &[Vec<u8>],
+    mut stream: Option<InnerStream>,
+) -> Result<(Vec<Entry>, Vec<String>), String> {
+    let document = Document::parse(xml).map_err(|e| format!("Неверный XML KeePass: {}", e))?;
+    let mut protected = HashMap::new();
+    if let Some(stream) = stream.as_mut() {
+        for node in document.descendants() {
+            if node.has_tag_name("Value") && node.attribute("Protected") == Some("True") {
+                let mut data = general_purpose::STANDARD
+                    .decode(node.text().unwrap_or_default().trim())
+                    .map_err(|_| "Защищённое значение повреждено")?;
+                stream.apply(&mut data);
+                let value = String::from_utf8_lossy(&data).into_owned();
+                data.zeroize();
+                protected.insert(node.id(), value);
+            }
+        }
+    }
+
+    let root = document.root_element();
+    let meta = child(root, "Meta");
+    // В XML-экспорте вложения лежат в Meta, а не во внутреннем заголовке
+    let meta_binaries;
+    let binaries = match meta.and_then(|meta| child(meta, "Binaries")) {
+        Some(node) if binaries.is_empty() => {
+            meta_binaries = read_meta_binaries(node)?;
+            meta_binaries.as_slice()
+        }
+        _ => binaries,
+    };
+    let recycle_bin = meta
+        .filter(|meta| child_text(*meta, "RecycleBinEnabled") != Some("False"))
+        .and_then(|meta| child_text(meta, "RecycleBinUUID"));
+    let top = child(root, "Root")
+        .and_then(|node| child(node, "Group"))
+        .ok_or("В базе нет групп")?;
+
+    let mut context = Context {
+        protected,
+        binaries,
+        recycle_bin,
+        entries: Vec::new(),
+        warnings: Vec::new(),
+    };
+    // Корневая группа — это сама база, её название в путь не входит
+    context.walk_group(top, "");
+    context.protected.values_mut().for_each(Zeroize::zeroize);
+    Ok((context.entries, context.warnings))
+}
+
+// Вложения из Meta: <Binary ID="0" Compressed="True">base64</Binary>.
+// Номера идут подряд с нуля, поэтому больше числа вложений они быть не могут
+fn read_meta_binaries(node: Node) -> Result<Vec<Vec<u8>>, String> {
+    let mut binaries = vec![Vec::new(); children(node, "Binary").count()];
+    for binary in children(node, "Binary") {
+        let Some(id) = binary
+            .attribute("ID")
+            .and_then(|id| id.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        if id >= binaries.len() {
+            return Err(format!("Неверный номер вложения: {}", id));
+        }
+        let mut data = general_purpose::STANDARD
+            .decode(binary.text().unwrap_or_default().trim())
+            .map_err(|_| "Вложение повреждено")?;
+        if binary.attribute("Compressed") == Some("True") {
+            let mut decompressed = Vec::new();
+            let result =
+                flate2::read::GzDecoder::new(data.as_slice()).read_to_end(&mut decompressed);
+            data.zeroize();
+            result.map_err(|_| "Не удалось распаковать вложение")?;
+            data = decompressed;
+        }
+        binaries[id] = data;
+    }
+    Ok(binaries)
+}
+
+// Записи из незашифрованного XML KeePass 2.x
+pub fn read_xml(xml: &str) -> Result<(Vec<Entry>, Vec<String>), String> {
+    entries_from_xml(xml.trim_start_matches('\u{FEFF}'), &[], None)
+}
+
+// Незашифрованный XML KeePass 2.x: пароли и вложения открытым текстом
+pub fn write_xml(entries: &[Entry], name: &str) -> String {
+    entries_to_xml(entries, name, None)
+}
+
+struct Context<'a, 'input> {
+    protected: HashMap<NodeId, String>,
+    binaries: &'a [Vec<u8>],
+    recycle_bin: Option<&'input str>,
+    entries: Vec<Entry>,
+    warnings: Vec<String>,
+}
+
+impl<'input> Context<'_, 'input> {
+    fn walk_group(&mut self, group: Node<'_, 'input>, folder: &str) {
+        for node in group.children().filter(Node::is_element) {
+            match node.tag_name().name() {
+                "Entry" => {
+                    let entry = self.entry(node, folder, true);
+                    self.entries.push(entry);
+                }
+                "Group" => {
+                    // Корзину KeePass не переносим
+                    if self.recycle_bin.is_some() && child_text(node, "UUID") == self.recycle_bin {
+                        continue;
+                    }
+                    let name = child_text(node, "Name")
+                        .unwrap_or_default()
+                        .replace('/', "∕");
+                    let path = vault::normalize_folder(&format!("{}/{}", folder, name));
+                    self.walk_group(node, &path);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn value(&self, node: Node) -> String {
+        match self.protected.get(&node.id()) {
+            Some(value) => value.clone(),
+            None => node.text().unwrap_or_default().to_string(),
+        }
+    }
+
+    fn entry(&mut self, node: Node, folder: &str, with_history: bool) -> Entry {
+        let mut entry = Entry {
+            folder: folder.to_string(),
+            ..Entry::default()
+        };
+        let mut strings = Vec::new();
+        for string in children(node, "String") {
+            let key = child_text(string, "Key").unwrap_or_default().to_string();
+            let value = child(string, "Value").map(|value| (self.value(value), value));
+            if let Some((value, value_node)) = value {
+                let protected = value_node.attribute("Protected") == Some("True")
+                    || value_node.attribute("ProtectInMemory") == Some("True");
+                strings.push((key, value, protected));
+            }
+        }
+
+        let mut totp_seed = None;
+        let mut totp_settings = None;
+        for (key, value, protected) in strings {
+            match key.as_str() {
+                TITLE => entry.service = value,
+                USER_NAME => entry.login = value,
+                PASSWORD => entry.password = value,
+                URL => entry.url = value,
+                NOTES => entry.notes = value,
+                OTP => totp_seed = Some((value, true)),
+                TOTP_SEED if totp_seed.is_none() => totp_seed = Some((value, false)),
+                TOTP_SETTINGS => totp_settings = Some(value),
+                _ if value.is_empty() => {}
+                _ => entry.fields.push(CustomField {
+                    name: key,
+                    value,
+                    protected,
+                }),
+            }
+        }
+        if let Some((mut seed, is_uri)) = totp_seed {
+            match totp_config(&seed, is_uri, totp_settings.as_deref()) {
+                Ok(config) => entry.otp = Some(config),
+                Err(e) => {
+                    if with_history {
+                        self.warnings.push(format!(
+                            "«{}»: 2FA не распознан ({}), секрет сохранён в доп. поле",
+                            entry.service, e
+                        ));
+                    }
+                    entry.fields.push(CustomField {
+                        name: if is_uri { OTP } else { TOTP_SEED }.to_string(),
+                        value: std::mem::take(&mut seed),
+                        protected: true,
+                    });
+                }
+            }
+            seed.zeroize();
+        }
+
+        for binary in children(node, "Binary") {
+            let name = child_text(binary, "Key").unwrap_or_default().to_string();
+            let data = child(binary, "Value")
+                .and_then(|value| value.attribute("Ref"))
+                .and_then(|reference| reference.parse::<usize>().ok())
+                .and_then(|reference| self.binaries.get(reference));
+            match data {
+                Some(data) => entry.attachments.push(Attachment {
+                    name,
+                    data: data.clone(),
+                }),
+                None if with_history => self.warnings.push(format!(
+                    "«{}»: вложение «{}» не найдено в базе",
+                    entry.service, name
+                )),
+                None => {}
+            }
+        }
+
+        if let Some(tags) = child_text(node, "Tags") {
+            entry.tags = vault::parse_tags(&tags.replace(';', ","));
+        }
+
+        if let Some(times) = child(node, "Times") {
+            entry.created_at = child_text(times, "CreationTime").and_then(parse_time);
+            entry.modified_at = child_text(times, "LastModificationTime").and_then(parse_time);
+            entry.last_used_at = child_text(times, "LastAccessTime").and_then(parse_time);
+            entry.use_count = child_text(times, "UsageCount")
+                .and_then(|count| count.trim().parse().ok())
+                .unwrap_or(0);
+            if child_text(times, "Expires") == Some("True") {
+                entry.expiry = child_text(times, "ExpiryTime")
+                    .and_then(parse_time)
+                    .map(Expiry::Date);
+            }
+        }
+        entry.password_changed_at = entry.created_at;
+
+        if with_history && let Some(history) = child(node, "History") {
+            let versions: Vec<Entry> = children(history, "Entry")
+                .map(|version| self.entry(version, folder, false))
+                .collect();
+            apply_history(&mut entry, versions);
+        }
+        entry
+    }
+}
+
+// Прежние версии записи (от старых к новым) → прежние пароли.
+// Пароль сменён в момент изменения следующей версии
+fn apply_history(entry: &mut Entry, mut versions: Vec<Entry>) {
+    let changes: Vec<(String, Option<SystemTime>)> = versions
+        .iter()
+        .map(|version| (version.password.clone(), version.modified_at))
+        .chain(std::iter::once((entry.password.clone(), entry.modified_at)))
+        .collect();
+    for pair in changes.windows(2) {
+        let (old, _) = &pair[0];
+        let (new, changed_at) = &pair[1];
+        if old != new {
+            if !old.is_empty() {
+                entry.password_history.push(OldPassword {
+                    password: old.clone(),
+                    replaced_at: *changed_at,
+                });
+            }
+            entry.password_changed_at = *changed_at;
+        }
+    }
+    if let Some(first) = versions.first() {
+        entry.created_at = entry.created_at.or(first.created_at);
+    }
+    let excess = entry
+        .password_history
+        .len()
+        .saturating_sub(vault::PASSWORD_HISTORY_LIMIT);
+    entry
+        .password_history
+        .drain(..excess)
+        .for_each(|mut old| old.zeroize());
+    for (mut password, _) in changes {
+        password.zeroize();
+    }
+    versions.zeroize();
+}
+
+// 2FA: otpauth:// из KeePassXC или пара «TOTP Seed» + «TOTP Settings» (период;цифры)
+fn totp_config(seed: &str, is_uri: bool, settings: Option<&str>) -> Result<OtpConfig, String> {
+    let mut config = OtpConfig::parse(seed)?;
+    if !is_uri && let Some(settings) = settings {
+        let mut parts = settings.split(';').map(str::trim);
+        if let Some(period) = parts.next().and_then(|period| period.parse().ok()) {
+            config.period = period;
+        }
+        match parts.next() {
+            Some("S") => return Err("коды Steam не поддерживаются".to_string()),
+            Some(digits) => {
+                if let Ok(digits) = digits.parse() {
+                    config.digits = digits;
+                }
+            }
+            None => {}
+        }
+    }
+    config.validate()?;
+    Ok(config)
+}
+
+// Время: base64 от числа секунд с 0001-01-01 (KDBX 4) или строка ISO 8601 (XML, KDBX 3)
+fn parse_time(text: &str) -> Option<SystemTime> {
+    if text.contains('-') {
+        return time::parse_iso_time(text);
+    }
+    let bytes = general_purpose::STANDARD.decode(text.trim()).ok()?;
+    let seconds = i64::from_le_bytes(bytes.try_into().ok()?) - SECONDS_BEFORE_UNIX_EPOCH;
+    if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+    }
+}
+
+// Время в формате KDBX 4
+fn format_time(time: SystemTime) -> String {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    general_purpose::STANDARD.encode((seconds + SECONDS_BEFORE_UNIX_EPOCH).to_le_bytes())
+}
+
+// Запись хранилища в новую базу KDBX 4 с паролем: Argon2id, AES-256, gzip
+pub fn write(entries: &[Entry], name: &str, password: &str) -> Result<Vec<u8>, String> {
+    let kdf = Kdf::Argon2 {
+        algorithm: argon2::Algorithm::Argon2id,
+        salt: random_bytes(32),
+        memory: ARGON2_MEMORY,
+        iterations: ARGON2_ITERATIONS,
+        parallelism: ARGON2_PARALLELISM,
+        version: 0x13,
+    };
+    write_with_kdf(entries, name, password, kdf)
+}
+
+fn write_with_kdf(
+    entries: &[Entry],
+    name: &str,
+    password: &str,
+    kdf: Kdf,
+) -> Result<Vec<u8>, String> {
+    let header = Header {
+        cipher: Cipher::Aes256,
+        compressed: true,
+        master_seed: random_bytes(32),
+        iv: random_bytes(16),
+        kdf,
+    };
+
+    let mut data = SIGNATURE.to_vec();
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&MAJOR_VERSION.to_le_bytes());
+    write_field(&mut data, HEADER_CIPHER, &CIPHER_AES256);
+    write_field(&mut data, HEADER_COMPRESSION, &1u32.to_le_bytes());
+    write_field(&mut data, HEADER_MASTER_SEED, &header.master_seed);
+    write_field(&mut data, HEADER_IV, &header.iv);
+    write_field(&mut data, HEADER_KDF, &kdf_parameters(&header.kdf));
+    write_field(&mut data, HEADER_END, b"\r\n\r\n");
+    let header_len = data.len();
+    let header_hash = Sha256::digest(&data);
+    data.extend_from_slice(&header_hash);
+
+    let (mut cipher_key, mut hmac_key) = derive_keys(&header, password, None)?;
+    let mut mac = block_hmac(&hmac_key, u64::MAX);
+    mac.update(&data[..header_len]);
+    data.extend_from_slice(&mac.finalize().into_bytes());
+
+    // Внутренний заголовок: ключ защиты значений и вложения, затем XML
+    let mut stream_key = random_bytes(64);
+    let stream = InnerStream::new(STREAM_CHACHA20, &stream_key)?;
+    let mut payload = Vec::new();
+    write_field(
+        &mut payload,
+        INNER_STREAM_ID,
+        &STREAM_CHACHA20.to_le_bytes(),
+    );
+    write_field(&mut payload, INNER_STREAM_KEY, &stream_key);
+    stream_key.zeroize();
+    for attachment in entries.iter().flat_map(|entry| &entry.attachments) {
+        let mut binary = vec![0u8];
+        binary.extend_from_slice(&attachment.data);
+        write_field(&mut payload, INNER_BINARY, &binary);
+        binary.zeroize();
+    }
+    write_field(&mut payload, INNER_END, &[]);
+    let mut xml = entries_to_xml(entries, name, stream);
+    payload.extend_from_slice(xml.as_bytes());
+    xml.zeroize();
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let compressed = encoder.write_all(&payload).and_then(|_| encoder.finish());
+    payload.zeroize();
+    let mut compressed = compressed.map_err(|e| format!("Не удалось сжать базу: {}", e))?;
+
+    let encrypted = cbc::Encryptor::<Aes256>::new_from_slices(&cipher_key, &header.iv)
+        .map_err(|_| "Неверные параметры шифра")?
+        .encrypt_padded_vec_mut::<Pkcs7>(&compressed);
+    compressed.zeroize();
+    cipher_key.zeroize();
+
+    let mut index = 0u64;
+    for block in encrypted.chunks(BLOCK_SIZE) {
+        write_block(&mut data, &hmac_key, index, block);
+        index += 1;
+    }
+    write_block(&mut data, &hmac_key, index, &[]);
+    hmac_key.zeroize();
+    Ok(data)
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rand::rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+// Поле заголовка: номер, размер и значение
+fn write_field(data: &mut Vec<u8>, id: u8, value: &[u8]) {
+    data.push(id);
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value);
+}
+
+fn write_block(data: &mut Vec<u8>, hmac_key: &[u8; 64], index: u64, block: &[u8]) {
+    let size = block.len() as u32;
+    let mut mac = block_hmac(hmac_key, index);
+    mac.update(&index.to_le_bytes());
+    mac.update(&size.to_le_bytes());
+    mac.update(block);
+    data.extend_from_slice(&mac.finalize().into_bytes());
+    data.extend_from_slice(&size.to_le_bytes());
+    data.extend_from_slice(block);
+}
+
+fn kdf_parameters(kdf: &Kdf) -> Vec<u8> {
+    let mut params = 0x0100u16.to_le_bytes().to_vec();
+    let mut item = |kind: u8, name: &str, value: &[u8]| {
+        params.push(kind);
+        params.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        params.extend_from_slice(name.as_bytes());
+        params.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        params.extend_from_slice(value);
+    };
+    match kdf {
+        Kdf::Aes { seed, rounds } => {
+            item(VARIANT_BYTES, "$UUID", &KDF_AES_KDBX4);
+            item(VARIANT_UINT64, "R", &rounds.to_le_bytes());
+            item(VARIANT_BYTES, "S", seed);
+        }
+        Kdf::Argon2 {
+            algorithm,
+            salt,
+            memory,
+            iterations,
+            parallelism,
+            version,
+        } => {
+            let uuid = match algorithm {
+                argon2::Algorithm::Argon2id => KDF_ARGON2ID,
+                _ => KDF_ARGON2D,
+            };
+            item(VARIANT_BYTES, "$UUID", &uuid);
+            item(VARIANT_BYTES, "S", salt);
+            item(VARIANT_UINT32, "P", &parallelism.to_le_bytes());
+            item(VARIANT_UINT64, "M", &memory.to_le_bytes());
+            item(VARIANT_UINT64, "I", &iterations.to_le_bytes());
+            item(VARIANT_UINT32, "V", &version.to_le_bytes());
+        }
+    }
+    params.push(0);
+    params
+}
+
+// Папки хранилища → дерево групп
+#[derive(Default)]
+struct GroupTree<'a> {
+    entries: Vec<(usize, &'a Entry)>,
+    groups: BTreeMap<&'a str, GroupTree<'a>>,
+}
+
+// XML KeePass из записей. С потоковым шифром защищённые значения шифруются
+// по мере записи — в том же порядке, в котором их расшифрует читатель
+fn entries_to_xml(entries: &[Entry], name: &str, stream: Option<InnerStream>) -> String {
+    let mut tree = GroupTree::default();
+    for (index, entry) in entries.iter().enumerate() {
+        let mut group = &mut tree;
+        for part in entry.folder.split('/').filter(|part| !part.is_empty()) {
+            group = group.groups.entry(part).or_default();
+        }
+        group.entries.push((index, entry));
+    }
+    // Вложения пронумерованы по порядку записей
+    let first_binaries: Vec<usize> = entries
+        .iter()
+        .scan(0, |next, entry| {
+            let first = *next;
+            *next += entry.attachments.len();
+            Some(first)
+        })
+        .collect();
+
+    let now = SystemTime::now();
+    let mut writer = XmlWriter {
+        xml: String::from("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n"),
+        depth: 0,
+        stream,
+        now,
+    };
+    writer.start("KeePassFile");
+    writer.start("Meta");
+    writer.element("Generator", "Shroombrella");
+    writer.element("DatabaseName", name);
+    writer.element("DatabaseNameChanged", &format_time(now));
+    writer.element("MasterKeyChanged", &format_time(now));
+    writer.element("RecycleBinEnabled", "False");
+    writer.element(
+        "HistoryMaxItems",
+        &(vault::PASSWORD_HISTORY_LIMIT + 1).to_string(),
+    );
+    writer.element("HistoryMaxSize", "6291456");
+    if writer.stream.is_none() {
+        writer.binaries(entries);
+    }
+    writer.end("Meta");
+    writer.start("Root");
+    writer.group(name, &tree, &first_binaries);
+    writer.element("DeletedObjects", "");
+    writer.end("Root");
+    writer.end("KeePassFile");
+    writer.xml
+}
+
+struct XmlWriter {
+    xml: String,
+    depth: usize,
+    stream: Option<InnerStream>,
+    now: SystemTime,
+}
+
+impl XmlWriter {
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.xml.push('\t');
+        }
+    }
+
+    fn start(&mut self, tag: &str) {
+        self.indent();
+        self.xml.push_str(&format!("<{}>\n", tag));
+        self.depth += 1;
+    }
+
+    fn end(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.indent();
+        self.xml.push_str(&format!("</{}>\n", tag));
+    }
+
+    fn element(&mut self, tag: &str, text: &str) {
+        self.indent();
+        if text.is_empty() {
+            self.xml.push_str(&format!("<{} />\n", tag));
+        } else {
+            self.xml.push_str(&format!("<{}>", tag));
+            escape_into(&mut self.xml, text);
+            self.xml.push_str(&format!("</{}>\n", tag));
+        }
+    }
+
+    // В KDBX время хранится в base64, в XML-экспорте — строкой ISO 8601
+    fn time(&mut self, tag: &str, time: Option<SystemTime>) {
+        let time = time.unwrap_or(self.now);
+        let text = match self.stream {
+            Some(_) => format_time(time),
+            None => time::format_iso_time(time),
+        };
+        self.element(tag, &text);
+    }
+
+    fn binaries(&mut self, entries: &[Entry]) {
+        let attachments = entries.iter().flat_map(|entry| &entry.attachments);
+        if attachments.clone().next().is_none() {
+            return;
+        }
+        self.start("Binaries");
+        for (id, attachment) in attachments.enumerate() {
+            self.indent();
+            self.xml.push_str(&format!(
+                "<Binary ID=\"{}\">{}</Binary>\n",
+                id,
+                general_purpose::STANDARD.encode(&attachment.data)
+            ));
+        }
+        self.end("Binaries");
+    }
+
+    fn string(&mut self, key: &str, value: &str, protected: bool) {
+        self.start("String");
+        self.element("Key", key);
+        self.indent();
+        match (self.stream.as_mut(), protected) {
+            (Some(stream), true) => {
+                let mut data = value.as_bytes().to_vec();
+                stream.apply(&mut data);
+                self.xml.push_str("<Value Protected=\"True\">");
+                self.xml.push_str(&general_purpose::STANDARD.encode(&data));
+                self.xml.push_str("</Value>\n");
+                data.zeroize();
+            }
+            (None, true) => {
+                self.xml.push_str("<Value ProtectInMemory=\"True\">");
+                escape_into(&mut self.xml, value);
+                self.xml.push_str("</Value>\n");
+            }
+            _ => {
+                self.xml.push_str("<Value>");
+                escape_into(&mut self.xml, value);
+                self.xml.push_str("</Value>\n");
+            }
+        }
+        self.end("String");
+    }
+
+    fn group(&mut self, name: &str, tree: &GroupTree, first_binaries: &[usize]) {
+        self.start("Group");
+        self.element("UUID", &general_purpose::STANDARD.encode(random_bytes(16)));
+        self.element("Name", name);
+        self.element("IconID", "48");
+        self.start("Times");
+        for tag in ["LastModificationTime", "CreationTime", "LastAccessTime"] {
+            self.time(tag, None);
+        }
+        self.time("ExpiryTime", None);
+        self.element("Expires", "False");
+        self.element("UsageCount", "0");
+        self.time("LocationChanged", None);
+        self.end("Times");
+        self.element("IsExpanded", "True");
+        for (index, entry) in &tree.entries {
+            self.entry(entry, first_binaries[*index]);
+        }
+        for (name, group) in &tree.groups {
+            self.group(name, group, first_binaries);
+        }
+        self.end("Group");
+    }
+
+    fn entry(&mut self, entry: &Entry, first_binary: usize) {
+        let uuid = general_purpose::STANDARD.encode(random_bytes(16));
+        self.start("Entry");
+        self.entry_fields(entry, &uuid, &entry.password, entry.modified_at);
+        for (i, attachment) in entry.attachments.iter().enumerate() {
+            self.start("Binary");
+            self.element("Key", &attachment.name);
+            self.indent();
+            self.xml
+                .push_str(&format!("<Value Ref=\"{}\" />\n", first_binary + i));
+            self.end("Binary");
+        }
+
+        // Прежние пароли — версии записи в истории KeePass. Версия действовала
+        // с момента смены предыдущего пароля; у всех версий UUID самой записи
+        let mut versions = Vec::new();
+        let mut since = entry.created_at;
+        for old in &entry.password_history {
+            versions.push((old.password.as_str(), since));
+            since = old.replaced_at;
+        }
+        if !versions.is_empty() && since.map(format_time) != entry.modified_at.map(format_time) {
+            versions.push((entry.password.as_str(), since));
+        }
+        if !versions.is_empty() {
+            self.start("History");
+            for (password, modified_at) in versions {
+                self.start("Entry");
+                self.entry_fields(entry, &uuid, password, modified_at);
+                self.end("Entry");
+            }
+            self.end("History");
+        }
+        self.end("Entry");
+    }
+
+    fn entry_fields(
+        &mut self,
+        entry: &Entry,
+        uuid: &str,
+        password: &str,
+        modified_at: Option<SystemTime>,
+    ) {
+        self.element("UUID", uuid);
+        self.element("IconID", "0");
+        self.start("Times");
+        self.time("LastModificationTime", modified_at);
+        self.time("CreationTime", entry.created_at);
+        self.time("LastAccessTime", entry.last_used_at.or(modified_at));
+        let expires_at = entry.expires_at();
+        self.time("ExpiryTime", expires_at);
+        self.element(
+            "Expires",
+            if expires_at.is_some() {
+                "True"
+            } else {
+                "False"
+            },
+        );
+        self.element("UsageCount", &entry.use_count.to_string());
+        self.time("LocationChanged", modified_at);
+        self.end("Times");
+        if !entry.tags.is_empty() {
+            self.element("Tags", &entry.tags.join(";"));
+        }
+
+        self.string(TITLE, &entry.service, false);
+        self.string(USER_NAME, &entry.login, false);
+        self.string(PASSWORD, password, true);
+        self.string(URL, &entry.url, false);
+        self.string(NOTES, &entry.notes, false);
+        let mut used = vec![
+            TITLE,
+            USER_NAME,
+            PASSWORD,
+            URL,
+            NOTES,
+            TOTP_SEED,
+            TOTP_SETTINGS,
+        ];
+        if let Some(otp) = &entry.otp {
+            let mut uri = otp.to_uri();
+            self.string(OTP, &uri, true);
+            uri.zeroize();
+            used.push(OTP);
+        }
+        // Названия полей в KeePass уникальны и не совпадают со стандартными
+        let mut names: Vec<String> = used.into_iter().map(str::to_string).collect();
+        for field in &entry.fields {
+            let mut name = field.name.clone();
+            let mut copy = 1;
+            while names.contains(&name) {
+                copy += 1;
+                name = format!("{} ({})", field.name, copy);
+            }
+            self.string(&name, &field.value, field.protected);
+            names.push(name);
+        }
+    }
+}
+
+// Экранирование для XML; недопустимые в XML 1.0 управляющие символы выбрасываются
+fn escape_into(xml: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => xml.push_str("&amp;"),
+            '<' => xml.push_str("&lt;"),
+            '>' => xml.push_str("&gt;"),
+            '"' => xml.push_str("&quot;"),
+            '\'' => xml.push_str("&apos;"),
+            '\t' | '\n' | '\r' => xml.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => xml.push(c),
+        }
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn child_text<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|child| child.text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::totp::OtpAlgorithm;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        let path = format!(
+            "{}/tests/fixtures/kdbx/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e))
+    }
+
+    fn open(name: &str, password: &str, key_file: Option<&str>) -> Vec<Entry> {
+        let key_file = key_file.map(fixture);
+        let (entries, warnings) = read(&fixture(name), password, key_file.as_deref())
+            .unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert!(warnings.is_empty(), "{}: {:?}", name, warnings);
+        entries
+    }
+
+    fn find<'a>(entries: &'a [Entry], service: &str) -> &'a Entry {
+        entries
+            .iter()
+            .find(|entry| entry.service == service)
+            .unwrap_or_else(|| panic!("нет записи «{}»", service))
+    }
+
+    #[test]
+    fn aes_kdf_with_aes_cipher() {
+        let entries = open("aes_kdf.kdbx", "demopass", None);
+        let entry = find(&entries, "ASDF");
+        assert_eq!(entry.login, "ghj");
+        assert_eq!(entry.password, "klmno");
+        assert_eq!(entry.url, "https://example.com");
+        assert_eq!(entry.tags, ["keepass-rs", "test"]);
+        assert!(entry.created_at.is_some());
+    }
+
+    #[test]
+    fn argon2d_with_aes_and_chacha20() {
+        for name in ["argon2d_aes.kdbx", "argon2d_chacha20.kdbx"] {
+            let entries = open(name, "demopass", None);
+            assert!(!entries.is_empty(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn argon2id_with_password_and_key_file() {
+        let entries = open("argon2id_aes.kdbx", "P@ssw0rd", None);
+        assert_eq!(find(&entries, "Bar").password, "BarPassword3");
+
+        let entries = open(
+            "argon2id_chacha20_key_file.kdbx",
+            "P@ssw0rd",
+            Some("secret.key"),
+        );
+        assert_eq!(find(&entries, "Bar").password, "BarPassword3");
+    }
+
+    #[test]
+    fn key_file_only() {
+        let entries = open("key_file.kdbx", "", Some("key_file.key"));
+        assert!(!entries.is_empty());
+    }
+
+    #[test]
+    fn wrong_credentials() {
+        let error = read(&fixture("aes_kdf.kdbx"), "wrong", None).err();
+        assert_eq!(error.as_deref(), Some("Неверный пароль или ключевой файл"));
+
+        let key_file = fixture("secret.key");
+        let error = read(&fixture("argon2id_aes.kdbx"), "P@ssw0rd", Some(&key_file)).err();
+        assert_eq!(error.as_deref(), Some("Неверный пароль или ключевой файл"));
+    }
+
+    #[test]
+    fn unsupported_files() {
+        let error = read(&fixture("argon2d_twofish.kdbx"), "demopass", None)
+            .err()
+            .unwrap();
+        assert!(error.contains("Twofish"), "{}", error);
+        assert!(read(b"not a database", "", None).is_err());
+    }
+
+    #[test]
+    fn totp_fields() {
+        let entries = open("totp.kdbx", "test", None);
+        let otp = find(&entries, "this entry has totp").otp.as_ref().unwrap();
+        assert_eq!(otp.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(otp.digits, 6);
+        assert_eq!(otp.period, 30);
+
+        let entries = open("totp_sha512.kdbx", "test", None);
+        let otp = entries.iter().find_map(|entry| entry.otp.as_ref()).unwrap();
+        assert_eq!(otp.algorithm, OtpAlgorithm::Sha512);
+    }
+
+    #[test]
+    fn recycle_bin_is_skipped() {
+        let entries = open("recycle_bin.kdbx", "demopass", None);
+        assert!(
+            entries
+                .iter()
+                .all(|entry| !entry.folder.contains("Recycle Bin"))
+        );
+    }
+
+    #[test]
+    fn xml_groups_fields_attachments_and_history() {
+        let time = |seconds: i64| {
+            general_purpose::STANDARD.encode((seconds + SECONDS_BEFORE_UNIX_EPOCH).to_le_bytes())
+        };
+        let xml = format!(
+            r#"<KeePassFile><Meta><RecycleBinEnabled>True</RecycleBinEnabled>
+            <RecycleBinUUID>bin</RecycleBinUUID></Meta><Root><Group><Name>База</Name>
+            <Group><UUID>work</UUID><Name>Работа</Name><Group><UUID>ops</UUID><Name>ops</Name>
+            <Entry><String><Key>Title</Key><Value>Сервер</Value></String>
+            <String><Key>Password</Key><Value>new</Value></String>
+            <String><Key>PIN</Key><Value ProtectInMemory="True">1234</Value></String>
+            <String><Key>TOTP Seed</Key><Value>JBSWY3DPEHPK3PXP</Value></String>
+            <String><Key>TOTP Settings</Key><Value>60;8</Value></String>
+            <Binary><Key>id_rsa</Key><Value Ref="0"/></Binary>
+            <Times><LastModificationTime>{}</LastModificationTime></Times>
+            <History><Entry><String><Key>Password</Key><Value>old</Value></String>
+            <Times><LastModificationTime>{}</LastModificationTime></Times></Entry></History>
+            </Entry></Group></Group>
+            <Group><UUID>bin</UUID><Name>Корзина</Name><Entry><String><Key>Title</Key>
+            <Value>Удалённая</Value></String></Entry></Group></Group></Root></KeePassFile>"#,
+            time(2_000),
+            time(1_000)
+        );
+        let (entries, warnings) = entries_from_xml(&xml, &[b"key".to_vec()], None).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(entries.len(), 1);
+
+        let entry = &entries[0];
+        assert_eq!(entry.folder, "Работа/ops");
+        assert_eq!(entry.password, "new");
+        assert_eq!(entry.fields.len(), 1);
+        assert_eq!(entry.fields[0].name, "PIN");
+        assert!(entry.fields[0].protected);
+        let otp = entry.otp.as_ref().unwrap();
+        assert_eq!((otp.period, otp.digits), (60, 8));
+        assert_eq!(entry.attachments[0].name, "id_rsa");
+        assert_eq!(entry.attachments[0].data, b"key");
+        assert_eq!(entry.password_history.len(), 1);
+        assert_eq!(entry.password_history[0].password, "old");
+        assert_eq!(
+            entry.password_history[0].replaced_at,
+            Some(UNIX_EPOCH + Duration::from_secs(2_000))
+        );
+    }
+
+    fn at(seconds: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    // Запись со всеми переносимыми полями и простая запись в корне
+    fn sample_entries() -> [Entry; 2] {
+        let entry = Entry {
+            service: "Сервер <prod> & \"тест\"".to_string(),
+            login: "admin".to_string(),
+            password: "n3w-p@ss".to_string(),
+            otp: Some(OtpConfig::parse("JBSWY3DPEHPK3PXP").unwrap()),
+            url: "https://example.com/login?a=1&b=2".to_string(),
+            notes: "строка 1\nстрока 2".to_string(),
+            tags: vec!["ops".to_string(), "prod".to_string()],
+            folder: "Работа/ops".to_string(),
+            created_at: at(1_000),
+            modified_at: at(5_000),
+            last_used_at: at(6_000),
+            use_count: 3,
+            expiry: at(9_000_000).map(Expiry::Date),
+            attachments: vec![Attachment {
+                name: "id_rsa".to_string(),
+                data: vec![0, 1, 2, 255],
+            }],
+            fields: vec![
+                CustomField {
+                    name: "PIN".to_string(),
+                    value: "1234".to_string(),
+                    protected: true,
+                },
+                CustomField {
+                    name: "Title".to_string(),
+                    value: "не заголовок".to_string(),
+                    protected: false,
+                },
+            ],
+            password_history: vec![
+                OldPassword {
+                    password: "first".to_string(),
+                    replaced_at: at(2_000),
+                },
+                OldPassword {
+                    password: "second".to_string(),
+                    replaced_at: at(3_000),
+                },
+            ],
+            ..Entry::default()
+        };
+        let plain = Entry {
+            service: "Почта".to_string(),
+            password: "mail".to_string(),
+            created_at: at(1_000),
+            modified_at: at(1_000),
+            ..Entry::default()
+        };
+        [entry, plain]
+    }
+
+    fn assert_round_trip(read: &[Entry], entry: &Entry) {
+        assert_eq!(read.len(), 2);
+        let copy = find(read, &entry.service);
+        assert_eq!(copy.login, entry.login);
+        assert_eq!(copy.password, entry.password);
+        assert_eq!(copy.otp, entry.otp);
+        assert_eq!(copy.url, entry.url);
+        assert_eq!(copy.notes, entry.notes);
+        assert_eq!(copy.tags, entry.tags);
+        assert_eq!(copy.folder, entry.folder);
+        assert_eq!(copy.created_at, entry.created_at);
+        assert_eq!(copy.modified_at, entry.modified_at);
+        assert_eq!(copy.last_used_at, entry.last_used_at);
+        assert_eq!(copy.use_count, entry.use_count);
+        assert_eq!(copy.expiry, entry.expiry);
+        assert_eq!(copy.attachments[0].name, "id_rsa");
+        assert_eq!(copy.attachments[0].data, [0, 1, 2, 255]);
+        assert_eq!(copy.fields[0], entry.fields[0]);
+        assert_eq!(copy.fields[1].name, "Title (2)");
+        assert_eq!(copy.fields[1].value, entry.fields[1].value);
+        assert_eq!(copy.password_changed_at, at(3_000));
+        let history: Vec<_> = copy
+            .password_history
+            .iter()
+            .map(|old| (old.password.as_str(), old.replaced_at))
+            .collect();
+        assert_eq!(history, [("first", at(2_000)), ("second", at(3_000))]);
+
+        let copy = find(read, "Почта");
+        assert_eq!(copy.folder, "");
+        assert_eq!(copy.password, "mail");
+        assert!(copy.password_history.is_empty());
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let kdfs = [
+            Kdf::Aes {
+                seed: random_bytes(32),
+                rounds: 100,
+            },
+            Kdf::Argon2 {
+                algorithm: argon2::Algorithm::Argon2id,
+                salt: random_bytes(32),
+                memory: 1024 * 1024,
+                iterations: 2,
+                parallelism: 2,
+                version: 0x13,
+            },
+        ];
+        for kdf in kdfs {
+            let entries = sample_entries();
+            let data = write_with_kdf(&entries, "Тест", "secret", kdf).unwrap();
+            assert!(read(&data, "wrong", None).is_err());
+            let (read, warnings) = read(&data, "secret", None).unwrap();
+            assert!(warnings.is_empty(), "{:?}", warnings);
+            assert_round_trip(&read, &entries[0]);
+        }
+    }
+
+    #[test]
+    fn xml_round_trip() {
+        let entries = sample_entries();
+        let xml = write_xml(&entries, "Тест");
+        assert!(xml.contains("<Value ProtectInMemory=\"True\">n3w-p@ss</Value>"));
+        assert!(xml.contains("<CreationTime>1970-01-01T00:16:40Z</CreationTime>"));
+        assert!(xml.contains("<Binary ID=\"0\">AAEC/w==</Binary>"));
+        let (read, warnings) = read_xml(&xml).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_round_trip(&read, &entries[0]);
+    }
+
+    #[test]
+    fn xml_compressed_binaries() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"hello").unwrap();
+        let binary = general_purpose::STANDARD.encode(encoder.finish().unwrap());
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><KeePassFile><Meta><Binaries>
+            <Binary ID="0" Compressed="True">{}</Binary></Binaries></Meta><Root><Group>
+            <Name>Root</Name><Entry><String><Key>Title</Key><Value>Файл</Value></String>
+            <Binary><Key>hello.txt</Key><Value Ref="0"/></Binary>
+            <Times><CreationTime>2024-05-01T12:30:15Z</CreationTime></Times>
+            </Entry></Group></Root></KeePassFile>"#,
+            binary
+        );
+        let (entries, _) = read_xml(&xml).unwrap();
+        assert_eq!(entries[0].attachments[0].data, b"hello");
+        assert_eq!(entries[0].created_at, at(1_714_566_615));
+    }
+
+    #[test]
+    fn xml_binary_id_out_of_range() {
+        for id in ["1", "1000000000000", "18446744073709551615"] {
+            let xml = format!(
+                r#"<KeePassFile><Meta><Binaries><Binary ID="{}">AAEC</Binary></Binaries></Meta>
+                <Root><Group><Name>Root</Name></Group></Root></KeePassFile>"#,
+                id
+            );
+            assert!(read_xml(&xml).is_err(), "ID {}", id);
+        }
+    }
+}
@@ -1,9 +1,26 @@
 #![windows_subsystem = "windows"]
 mod app;
+mod bitwarden;
+mod breach;
 mod crypto;
+mod csv;
+mod generator;
+mod health;
+mod history;
+mod import;
+mod kdbx;
+mod onepassword;
+mod passphrase;
+mod policy;
+mod qr;
+mod search;
 mod settings;
 mod storage;
+mod strength;
 mod theme;
+mod time;
+mod totp;
+mod twofactor;
 mod ui;
 mod vault;
 
@@ -0,0 +1,733 @@
+// Экспорт 1Password в формате 1PUX: zip-архив с export.data (JSON) и вложениями в files/.
+// Разбор полностью локальный: export.data → учётные записи → хранилища → записи.
+// Хранилище становится папкой, категория — меткой вида записи; разделы, 2FA, доп. поля
+// и файлы переносятся, всё остальное попадает в замечания
+use crate::time;
+use crate::totp::OtpConfig;
+use crate::vault::{
+    self, Attachment, CustomField, Entry, OldPassword, TAG_CARD, TAG_IDENTITY, TAG_NOTE,
+    TAG_SSH_KEY,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroize;
+
+const EXPORT_DATA: &str = "export.data";
+const FILES_DIR: &str = "files/";
+
+// Категории 1Password: код, название и метка вида записи
+const CATEGORIES: [(&str, &str, Option<&str>); 22] = [
+    ("001", "Вход", None),
+    ("002", "Банковская карта", Some(TAG_CARD)),
+    ("003", "Защищённая заметка", Some(TAG_NOTE)),
+    ("004", "Личность", Some(TAG_IDENTITY)),
+    ("005", "Пароль", None),
+    ("006", "Документ", Some("документ")),
+    ("100", "Лицензия ПО", Some("лицензия")),
+    ("101", "Банковский счёт", Some("банковский счёт")),
+    ("102", "База данных", Some("база данных")),
+    ("103", "Водительские права", Some("водительские права")),
+    ("104", "Разрешение на охоту и рыбалку", Some("разрешение")),
+    ("105", "Членство", Some("членство")),
+    ("106", "Паспорт", Some("паспорт")),
+    ("107", "Программа лояльности", Some("программа лояльности")),
+    ("108", "Номер соцстрахования", Some("соцстрахование")),
+    ("109", "Wi-Fi роутер", Some("wi-fi")),
+    ("110", "Сервер", Some("сервер")),
+    ("111", "Электронная почта", Some("почта")),
+    ("112", "API-ключ", Some("api")),
+    ("113", "Медицинская карта", Some("медкарта")),
+    ("114", "SSH-ключ", Some(TAG_SSH_KEY)),
+    ("115", "Криптокошелёк", Some("криптокошелёк")),
+];
+
+// Записи из архива 1Password помечаются отдельно
+const TAG_ARCHIVED: &str = "архив";
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Export {
+    accounts: Vec<Account>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Account {
+    attrs: AccountAttrs,
+    vaults: Vec<Vault>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct AccountAttrs {
+    account_name: String,
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Vault {
+    attrs: VaultAttrs,
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct VaultAttrs {
+    name: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Item {
+    fav_index: u32,
+    created_at: Option<u64>,
+    updated_at: Option<u64>,
+    state: String,
+    category_uuid: String,
+    details: Details,
+    overview: Overview,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct Details {
+    login_fields: Vec<LoginField>,
+    notes_plain: Option<String>,
+    sections: Vec<Section>,
+    password_history: Vec<HistoryItem>,
+    document_attributes: Option<FileAttributes>,
+    password: Option<String>,
+    passkey: Option<serde::de::IgnoredAny>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct LoginField {
+    value: String,
+    name: String,
+    field_type: String,
+    designation: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Section {
+    title: String,
+    fields: Vec<SectionField>,
+}
+
+// Значение поля — объект с единственным ключом-типом: {"concealed": "…"}, {"totp": "…"}…
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SectionField {
+    title: String,
+    id: String,
+    value: serde_json::Map<String, Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct HistoryItem {
+    value: String,
+    time: Option<u64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct FileAttributes {
+    file_name: String,
+    document_id: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Overview {
+    title: String,
+    url: String,
+    urls: Vec<Url>,
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Url {
+    label: String,
+    url: String,
+}
+
+// Итог разбора: записи, сводка сопоставления и замечания
+pub struct Import {
+    pub entries: Vec<Entry>,
+    pub summary: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+pub fn read(data: &[u8]) -> Result<Import, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|_| "Это не архив 1PUX: файл не является zip-архивом".to_string())?;
+    let mut text = String::new();
+    archive
+        .by_name(EXPORT_DATA)
+        .map_err(|_| format!("Это не архив 1PUX: нет {}", EXPORT_DATA))?
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Не удалось прочитать {}: {}", EXPORT_DATA, e))?;
+    let export = serde_json::from_str::<Export>(&text);
+    text.zeroize();
+    let mut export = export.map_err(|e| format!("Повреждённый {}: {}", EXPORT_DATA, e))?;
+
+    let mut context = Context {
+        archive,
+        entries: Vec::new(),
+        warnings: Vec::new(),
+        folders: BTreeMap::new(),
+        categories: BTreeMap::new(),
+        attachments: 0,
+        otp: 0,
+        archived: 0,
+    };
+    let several_accounts = export.accounts.len() > 1;
+    for account in &export.accounts {
+        let account_name = if account.attrs.account_name.is_empty() {
+            &account.attrs.name
+        } else {
+            &account.attrs.account_name
+        };
+        for vault in &account.vaults {
+            // Хранилище — одна папка, даже если в названии есть "/"
+            let name = vault.attrs.name.replace('/', "∕");
+            let folder = if several_accounts {
+                vault::normalize_folder(&format!("{}/{}", account_name.replace('/', "∕"), name))
+            } else {
+                vault::normalize_folder(&name)
+            };
+            for item in &vault.items {
+                context.item(item, &folder);
+            }
+            *context
+                .folders
+                .entry((vault.attrs.name.clone(), folder))
+                .or_default() += vault.items.len();
+        }
+    }
+    wipe(&mut export);
+    Ok(context.finish())
+}
+
+struct Context<'a> {
+    archive: zip::ZipArchive<Cursor<&'a [u8]>>,
+    entries: Vec<Entry>,
+    warnings: Vec<String>,
+
+    // Счётчики для сводки: хранилища (название и папка), категории, вложения, 2FA, архив
+    folders: BTreeMap<(String, String), usize>,
+    categories: BTreeMap<String, usize>,
+    attachments: usize,
+    otp: usize,
+    archived: usize,
+}
+
+impl Context<'_> {
+    fn item(&mut self, item: &Item, folder: &str) {
+        let details = &item.details;
+        let mut entry = Entry {
+            service: item.overview.title.clone(),
+            notes: details.notes_plain.clone().unwrap_or_default(),
+            folder: folder.to_string(),
+            favorite: item.fav_index > 0,
+            created_at: item.created_at.map(unix_time),
+            modified_at: item.updated_at.map(unix_time),
+            ..Entry::default()
+        };
+        entry.password_changed_at = entry.created_at;
+
+        *self
+            .categories
+            .entry(item.category_uuid.clone())
+            .or_default() += 1;
+        match CATEGORIES
+            .iter()
+            .find(|(code, _, _)| *code == item.category_uuid)
+        {
+            Some((_, _, Some(tag))) => entry.tags.push(tag.to_string()),
+            Some((_, _, None)) => {}
+            None => self.warnings.push(format!(
+                "«{}»: неизвестная категория {}, перенесены только поля",
+                entry.service, item.category_uuid
+            )),
+        }
+        for tag in &item.overview.tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !entry.tags.iter().any(|t| t == tag) {
+                entry.tags.push(tag.to_string());
+            }
+        }
+        if item.state == "archived" {
+            entry.tags.push(TAG_ARCHIVED.to_string());
+            self.archived += 1;
+        }
+
+        // Основной адрес и дополнительные
+        entry.url = item.overview.url.trim().to_string();
+        let mut extra_urls = 0;
+        for url in &item.overview.urls {
+            let address = url.url.trim();
+            if address.is_empty() || address == entry.url {
+                continue;
+            }
+            if entry.url.is_empty() {
+                entry.url = address.to_string();
+                continue;
+            }
+            extra_urls += 1;
+            let name = if url.label.trim().is_empty() {
+                format!("URL {}", extra_urls + 1)
+            } else {
+                url.label.trim().to_string()
+            };
+            push_field(&mut entry, name, address, false);
+        }
+
+        for field in &details.login_fields {
+            match field.designation.as_deref() {
+                Some("username") => entry.login = field.value.clone(),
+                Some("password") => entry.password = field.value.clone(),
+                _ if field.value.is_empty() || field.name.is_empty() => {}
+                _ => push_field(
+                    &mut entry,
+                    field.name.clone(),
+                    &field.value,
+                    field.field_type == "P",
+                ),
+            }
+        }
+        if entry.password.is_empty()
+            && let Some(password) = &details.password
+        {
+            entry.password = password.clone();
+        }
+
+        for section in &details.sections {
+            for field in &section.fields {
+                self.section_field(&mut entry, section, field);
+            }
+        }
+
+        if let Some(document) = &details.document_attributes {
+            self.attach(&mut entry, document);
+        }
+
+        // В 1PUX история идёт от новых к старым
+        let mut history: Vec<OldPassword> = details
+            .password_history
+            .iter()
+            .filter(|old| !old.value.is_empty())
+            .map(|old| OldPassword {
+                password: old.value.clone(),
+                replaced_at: old.time.map(unix_time),
+            })
+            .collect();
+        history.sort_by_key(|old| old.replaced_at);
+        let excess = history.len().saturating_sub(vault::PASSWORD_HISTORY_LIMIT);
+        history.drain(..excess).for_each(|mut old| old.zeroize());
+        if let Some(last) = history.last() {
+            entry.password_changed_at = entry.password_changed_at.max(last.replaced_at);
+        }
+        entry.password_history = history;
+
+        if details.passkey.is_some() {
+            self.warnings.push(format!(
+                "«{}»: ключ доступа (passkey) не переносится",
+                entry.service
+            ));
+        }
+        self.entries.push(entry);
+    }
+
+    fn section_field(&mut self, entry: &mut Entry, section: &Section, field: &SectionField) {
+        let Some((kind, value)) = field.value.iter().next() else {
+            return;
+        };
+        let title = field.title.trim();
+        let title = if title.is_empty() {
+            field.id.trim()
+        } else {
+            title
+        };
+        let title = if title.is_empty() { "Поле" } else { title };
+        let name = if section.title.trim().is_empty() {
+            title.to_string()
+        } else {
+            format!("{} · {}", section.title.trim(), title)
+        };
+
+        let text = match (kind.as_str(), value) {
+            ("file", file) => {
+                match FileAttributes::deserialize(file) {
+                    Ok(file) => self.attach(entry, &file),
+                    Err(_) => self.warnings.push(format!(
+                        "«{}»: повреждённое описание файла в поле «{}»",
+                        entry.service, name
+                    )),
+                }
+                return;
+            }
+            ("totp", Value::String(totp)) if totp.trim().is_empty() => return,
+            ("totp", Value::String(totp)) if entry.otp.is_none() => {
+                match OtpConfig::parse(totp).and_then(|config| config.validate().map(|_| config)) {
+                    Ok(config) => {
+                        entry.otp = Some(config);
+                        self.otp += 1;
+                    }
+                    Err(e) => {
+                        self.warnings.push(format!(
+                            "«{}»: 2FA не распознан ({}), секрет сохранён в доп. поле",
+                            entry.service, e
+                        ));
+                        push_field(entry, name, totp, true);
+                    }
+                }
+                return;
+            }
+            ("totp", Value::String(totp)) => {
+                self.warnings.push(format!(
+                    "«{}»: второй код 2FA «{}» сохранён в доп. поле",
+                    entry.service, name
+                ));
+                push_field(entry, name, totp, true);
+                return;
+            }
+            ("concealed" | "creditCardNumber", Value::String(secret)) => {
+                // Пароль и логин сервера, базы данных и т. п. лежат в разделе без названия
+                if field.id == "password" && entry.password.is_empty() {
+                    entry.password = secret.clone();
+                } else {
+                    push_field(entry, name, secret, true);
+                }
+                return;
+            }
+            ("sshKey", key) => {
+                let key_text = |pointer: &str| {
+                    key.pointer(pointer)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                };
+                push_field(
+                    entry,
+                    "Закрытый ключ".to_string(),
+                    key_text("/privateKey"),
+                    true,
+                );
+                push_field(
+                    entry,
+                    "Открытый ключ".to_string(),
+                    key_text("/metadata/publicKey"),
+                    false,
+                );
+                push_field(
+                    entry,
+                    "Отпечаток".to_string(),
+                    key_text("/metadata/fingerprint"),
+                    false,
+                );
+                return;
+            }
+            ("date", Value::Number(seconds)) => seconds
+                .as_i64()
+                .filter(|seconds| *seconds >= 0)
+                .map(|seconds| time::format_date(unix_time(seconds as u64))),
+            ("monthYear", Value::Number(month_year)) => month_year
+                .as_u64()
+                .map(|month_year| format!("{:02}/{}", month_year % 100, month_year / 100)),
+            ("email", Value::Object(email)) => email
+                .get("email_address")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            ("address", Value::Object(address)) => Some(
+                ["street", "zip", "city", "state", "country"]
+                    .iter()
+                    .filter_map(|part| address.get(*part).and_then(Value::as_str))
+                    .map(str::trim)
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            ("reference", _) => {
+                self.warnings.push(format!(
+                    "«{}»: ссылка на другую запись «{}» не переносится",
+                    entry.service, name
+                ));
+                return;
+            }
+            (_, Value::String(text)) => Some(text.clone()),
+            (_, Value::Number(number)) => Some(number.to_string()),
+            (_, Value::Bool(flag)) => Some(flag.to_string()),
+            _ => {
+                self.warnings.push(format!(
+                    "«{}»: поле «{}» типа «{}» не переносится",
+                    entry.service, name, kind
+                ));
+                return;
+            }
+        };
+        let Some(text) = text else {
+            return;
+        };
+        match field.id.as_str() {
+            "username" if entry.login.is_empty() => entry.login = text,
+            "url" if entry.url.is_empty() => entry.url = text,
+            _ => push_field(entry, name, &text, false),
+        }
+    }
+
+    // Файл из каталога files/ архива: "<documentId>__<имя файла>"
+    fn attach(&mut self, entry: &mut Entry, file: &FileAttributes) {
+        let prefix = format!("{}{}", FILES_DIR, file.document_id);
+        let path = self
+            .archive
+            .file_names()
+            .filter_map(Result::ok)
+            .find(|path| {
+                !file.document_id.is_empty()
+                    && path
+                        .strip_prefix(&prefix)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with("__"))
+            })
+            .map(|path| path.into_owned());
+        let data = path.and_then(|path| {
+            let mut data = Vec::new();
+            self.archive
+                .by_name(&path)
+                .ok()?
+                .read_to_end(&mut data)
+                .ok()?;
+            Some(data)
+        });
+        match data {
+            Some(data) => {
+                entry.attachments.push(Attachment {
+                    name: file.file_name.clone(),
+                    data,
+                });
+                self.attachments += 1;
+            }
+            None => self.warnings.push(format!(
+                "«{}»: файл «{}» не найден в архиве",
+                entry.service, file.file_name
+            )),
+        }
+    }
+
+    // Сводка: куда попали хранилища и категории
+    fn finish(self) -> Import {
+        let mut summary = Vec::new();
+        for ((vault, folder), count) in &self.folders {
+            summary.push(format!(
+                "🗄️ Хранилище «{}» → папка «{}»: {}",
+                vault, folder, count
+            ));
+        }
+        for (code, count) in &self.categories {
+            let line = match CATEGORIES.iter().find(|(known, _, _)| known == code) {
+                Some((_, title, Some(tag))) => {
+                    format!("🏷️ {} → записи с меткой «{}»: {}", title, tag, count)
+                }
+                Some((_, title, None)) => format!("🔑 {} → записи: {}", title, count),
+                None => format!("❔ Категория {} → записи без метки: {}", code, count),
+            };
+            summary.push(line);
+        }
+        if self.otp > 0 {
+            summary.push(format!("🔢 Коды 2FA: {}", self.otp));
+        }
+        if self.attachments > 0 {
+            summary.push(format!("📎 Файлы → вложения: {}", self.attachments));
+        }
+        if self.archived > 0 {
+            summary.push(format!(
+                "📦 Из архива 1Password → метка «{}»: {}",
+                TAG_ARCHIVED, self.archived
+            ));
+        }
+        Import {
+            entries: self.entries,
+            summary,
+            warnings: self.warnings,
+        }
+    }
+}
+
+fn push_field(entry: &mut Entry, name: String, value: &str, protected: bool) {
+    let value = value.trim();
+    if !value.is_empty() {
+        entry.fields.push(CustomField {
+            name,
+            value: value.to_string(),
+            protected,
+        });
+    }
+}
+
+fn unix_time(seconds: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+// Секреты из разобранного файла больше не нужны
+fn wipe(export: &mut Export) {
+    for item in export
+        .accounts
+        .iter_mut()
+        .flat_map(|account| account.vaults.iter_mut())
+        .flat_map(|vault| vault.items.iter_mut())
+    {
+        let details = &mut item.details;
+        details.notes_plain.zeroize();
+        details.password.zeroize();
+        for field in &mut details.login_fields {
+            field.value.zeroize();
+        }
+        for old in &mut details.password_history {
+            old.value.zeroize();
+        }
+        // Значения разделов лежат в serde_json::Value; затираем строки
+        for field in details
+            .sections
+            .iter_mut()
+            .flat_map(|section| section.fields.iter_mut())
+        {
+            field.value.values_mut().for_each(wipe_value);
+        }
+    }
+}
+
+fn wipe_value(value: &mut Value) {
+    match value {
+        Value::String(text) => text.zeroize(),
+        Value::Object(object) => object.values_mut().for_each(wipe_value),
+        Value::Array(items) => items.iter_mut().for_each(wipe_value),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Import {
+        let path = format!(
+            "{}/tests/fixtures/1password/export.1pux",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        let data = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        read(&data).unwrap()
+    }
+
+    fn find<'a>(entries: &'a [Entry], service: &str) -> &'a Entry {
+        entries
+            .iter()
+            .find(|entry| entry.service == service)
+            .unwrap_or_else(|| panic!("нет записи «{}»", service))
+    }
+
+    fn field<'a>(entry: &'a Entry, name: &str) -> &'a CustomField {
+        entry
+            .fields
+            .iter()
+            .find(|field| field.name == name)
+            .unwrap_or_else(|| panic!("«{}»: нет поля «{}»", entry.service, name))
+    }
+
+    #[test]
+    fn login_with_sections_totp_and_files() {
+        let import = fixture();
+        assert_eq!(import.entries.len(), 7);
+
+        let mail = find(&import.entries, "Corporate mail");
+        assert_eq!(mail.folder, "Private");
+        assert_eq!(mail.login, "alice@example.com");
+        assert_eq!(mail.password, "correct horse battery staple");
+        assert_eq!(mail.url, "https://mail.example.com");
+        assert_eq!(field(mail, "webmail").value, "https://webmail.example.com");
+        assert_eq!(mail.tags, ["work", "email"]);
+        assert!(mail.favorite);
+        assert_eq!(mail.otp.as_ref().unwrap().digits, 8);
+        assert!(field(mail, "Security · PIN").protected);
+        assert_eq!(field(mail, "Security · Department").value, "Ops");
+        assert_eq!(field(mail, "Security · Since").value, "2020-01-01");
+        assert_eq!(mail.attachments[0].name, "contract.pdf");
+        assert_eq!(mail.attachments[0].data, b"Signed contract");
+        let history: Vec<&str> = mail
+            .password_history
+            .iter()
+            .map(|old| old.password.as_str())
+            .collect();
+        assert_eq!(history, ["old-secret-1", "old-secret-2"]);
+        assert!(
+            import
+                .warnings
+                .iter()
+                .any(|warning| warning.contains("ссылка на другую запись")),
+            "{:?}",
+            import.warnings
+        );
+        assert_eq!(import.warnings.len(), 1, "{:?}", import.warnings);
+    }
+
+    #[test]
+    fn categories_and_vaults() {
+        let import = fixture();
+
+        let card = find(&import.entries, "Visa");
+        assert_eq!(card.tags, [TAG_CARD]);
+        assert!(field(card, "number").protected);
+        assert_eq!(field(card, "expiry date").value, "07/2029");
+
+        let note = find(&import.entries, "Wi-Fi at home");
+        assert_eq!(note.tags, [TAG_NOTE, TAG_ARCHIVED]);
+
+        let document = find(&import.entries, "Passport scan");
+        assert_eq!(document.attachments[0].data, b"Scanned passport page");
+
+        let server = find(&import.entries, "Database server");
+        assert_eq!(server.folder, "Ops∕Shared");
+        assert_eq!(server.tags, ["сервер"]);
+        assert_eq!(server.login, "root");
+        assert_eq!(server.password, "t0ps3cret");
+        assert_eq!(server.url, "ssh://db.example.com");
+        assert_eq!(
+            field(server, "Admin Console · console URL").value,
+            "https://console.example.com"
+        );
+
+        let key = find(&import.entries, "Deploy key");
+        assert!(field(key, "Закрытый ключ").protected);
+        assert!(field(key, "Открытый ключ").value.starts_with("ssh-ed25519"));
+
+        assert_eq!(
+            find(&import.entries, "Router admin").password,
+            "router-admin-pass"
+        );
+
+        for expected in [
+            "Хранилище «Private» → папка «Private»: 4",
+            "Хранилище «Ops/Shared» → папка «Ops∕Shared»: 3",
+            "Банковская карта → записи с меткой «карта»: 1",
+            "Файлы → вложения: 2",
+        ] {
+            assert!(
+                import.summary.iter().any(|line| line.contains(expected)),
+                "{}: {:?}",
+                expected,
+                import.summary
+            );
+        }
+    }
+
+    #[test]
+    fn not_a_1pux_archive() {
+        assert!(read(b"not a zip").is_err());
+    }
+}
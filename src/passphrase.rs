@@ -0,0 +1,363 @@
+use crate::csv;
+use rand::Rng;
+use rand::distr::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+// Большой словарь EFF для diceware: 7776 слов, по одному на бросок пяти костей
+const EFF_LARGE_WORDLIST: &str = include_str!("../assets/eff_large_wordlist.txt");
+
+const INSERT_SYMBOLS: &str = "!@#$%^&*-_=+?";
+
+// Минимальный размер словаря, при котором фраза имеет смысл
+const MIN_WORDS: usize = 100;
+
+// Регистр слов во фразе
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Capitalization {
+    Lower,
+    Upper,
+    Title,  // первая буква каждого слова заглавная
+    Random, // регистр каждого слова выбирается случайно
+}
+
+impl Capitalization {
+    pub const ALL: [Capitalization; 4] = [
+        Capitalization::Lower,
+        Capitalization::Upper,
+        Capitalization::Title,
+        Capitalization::Random,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Capitalization::Lower => "строчные",
+            Capitalization::Upper => "ПРОПИСНЫЕ",
+            Capitalization::Title => "С Заглавной",
+            Capitalization::Random => "сЛуЧаЙно",
+        }
+    }
+}
+
+// Параметры парольной фразы
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct PassphraseOptions {
+    pub word_count: usize,
+    pub separator: String,
+    pub capitalization: Capitalization,
+    pub insert_digit: bool,
+    pub insert_symbol: bool,
+    // Файл со своим словарём; None — встроенный словарь EFF
+    pub wordlist: Option<PathBuf>,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        Self {
+            word_count: 6,
+            separator: "-".to_string(),
+            capitalization: Capitalization::Lower,
+            insert_digit: false,
+            insert_symbol: false,
+            wordlist: None,
+        }
+    }
+}
+
+// Разбираем словарь: по слову в строке; строки вида "11111\tслово" (формат diceware)
+// тоже подходят — берётся последнее слово строки
+pub fn parse_wordlist(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_whitespace().last())
+        .map(|word| word.to_lowercase())
+        .collect();
+    // Повторы уменьшили бы реальную энтропию
+    words.sort_unstable();
+    words.dedup();
+    words
+}
+
+// Файл словаря в том виде, в каком он был прочитан
+#[derive(PartialEq)]
+struct WordlistFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+// Загружаем словарь (встроенный или из файла); разобранные словари кэшируются
+pub fn load_wordlist(path: Option<&Path>) -> Result<Arc<Vec<String>>, String> {
+    static EMBEDDED: OnceLock<Arc<Vec<String>>> = OnceLock::new();
+    static CUSTOM: Mutex<Option<(WordlistFile, Arc<Vec<String>>)>> = Mutex::new(None);
+
+    let Some(path) = path else {
+        return Ok(EMBEDDED
+            .get_or_init(|| Arc::new(parse_wordlist(EFF_LARGE_WORDLIST)))
+            .clone());
+    };
+
+    // Изменённый файл перечитываем: в ключе кэша и время изменения, и размер
+    let metadata = std::fs::metadata(path).map_err(|_| "Не удалось прочитать файл словаря")?;
+    let file = WordlistFile {
+        path: path.to_path_buf(),
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+    };
+    let mut custom = CUSTOM.lock().map_err(|_| "Ошибка доступа к словарю")?;
+    if let Some((cached, words)) = custom.as_ref()
+        && *cached == file
+    {
+        return Ok(words.clone());
+    }
+
+    // Кодировка — как у импорта CSV: BOM, UTF-8 или Windows-1251
+    let bytes = std::fs::read(path).map_err(|_| "Не удалось прочитать файл словаря")?;
+    let text = csv::decode(&bytes, csv::detect_encoding(&bytes));
+    let words = parse_wordlist(&text);
+    if words.len() < MIN_WORDS {
+        return Err(format!(
+            "В словаре слишком мало слов ({}), нужно хотя бы {}",
+            words.len(),
+            MIN_WORDS
+        ));
+    }
+
+    let words = Arc::new(words);
+    *custom = Some((file, words.clone()));
+    Ok(words)
+}
+
+impl PassphraseOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.word_count == 0 {
+            return Err("Нужно хотя бы одно слово".to_string());
+        }
+        load_wordlist(self.wordlist.as_deref()).map(|_| ())
+    }
+
+    // Энтропия: слова + случайный регистр + вставленные цифра и символ с их позициями
+    pub fn entropy_bits(&self) -> f64 {
+        let Ok(words) = load_wordlist(self.wordlist.as_deref()) else {
+            return 0.0;
+        };
+        let count = self.word_count as f64;
+        let mut bits = count * (words.len() as f64).log2();
+        if self.capitalization == Capitalization::Random {
+            bits += count;
+        }
+        if self.insert_digit {
+            bits += 10f64.log2() + count.log2();
+        }
+        if self.insert_symbol {
+            bits += (INSERT_SYMBOLS.chars().count() as f64).log2() + count.log2();
+        }
+        bits
+    }
+}
+
+// Генерируем фразу; слова выбираются равномерно криптостойким ГПСЧ
+pub fn generate(options: &PassphraseOptions) -> Result<String, String> {
+    options.validate()?;
+    let words = load_wordlist(options.wordlist.as_deref())?;
+    let mut rng = rand::rng();
+    let word_index = Uniform::new(0, words.len()).map_err(|_| "Словарь пуст")?;
+    let position = Uniform::new(0, options.word_count).map_err(|_| "Нужно хотя бы одно слово")?;
+
+    let mut chosen: Vec<String> = (0..options.word_count)
+        .map(|_| {
+            let word = &words[word_index.sample(&mut rng)];
+            match options.capitalization {
+                Capitalization::Lower => word.clone(),
+                Capitalization::Upper => word.to_uppercase(),
+                Capitalization::Title => capitalize(word),
+                Capitalization::Random => {
+                    if rng.random_bool(0.5) {
+                        word.to_uppercase()
+                    } else {
+                        word.clone()
+                    }
+                }
+            }
+        })
+        .collect();
+
+    // Цифру и символ дописываем к случайно выбранным словам
+    if options.insert_digit {
+        let digit = Uniform::new(0u32, 10).map_err(|_| "Ошибка генерации")?;
+        let digit = char::from_digit(digit.sample(&mut rng), 10).unwrap_or('0');
+        chosen[position.sample(&mut rng)].push(digit);
+    }
+    if options.insert_symbol {
+        let symbols: Vec<char> = INSERT_SYMBOLS.chars().collect();
+        let symbol = Uniform::new(0, symbols.len()).map_err(|_| "Ошибка генерации")?;
+        chosen[position.sample(&mut rng)].push(symbols[symbol.sample(&mut rng)]);
+    }
+
+    Ok(chosen.join(&options.separator))
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "shroombrella-words-{}-{}.txt",
+            std::process::id(),
+            name
+        ))
+    }
+
+    // Сто слов вида «кXY» из строчных русских букв
+    fn russian_words() -> Vec<String> {
+        let letters: Vec<char> = "абвгдежзил".chars().collect();
+        letters
+            .iter()
+            .flat_map(|a| letters.iter().map(move |b| format!("к{}{}", a, b)))
+            .collect()
+    }
+
+    // Строчные русские буквы в Windows-1251 идут подряд с 0xE0
+    fn to_cp1251(text: &str) -> Vec<u8> {
+        text.chars()
+            .map(|c| match c {
+                'а'..='я' => (c as u32 - 'а' as u32 + 0xE0) as u8,
+                _ => c as u8,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn eff_list_has_7776_unique_words() {
+        let words = load_wordlist(None).unwrap();
+        assert_eq!(words.len(), 7776);
+        assert!(
+            words
+                .iter()
+                .all(|w| !w.is_empty() && !w.contains(char::is_numeric))
+        );
+    }
+
+    #[test]
+    fn parses_custom_lists() {
+        let text =
+            "# комментарий\n11111\tabacus\n\n11112 Abdomen\n  \nabide\nabacus\r\n11113\tabide\n";
+        assert_eq!(parse_wordlist(text), ["abacus", "abdomen", "abide"]);
+    }
+
+    #[test]
+    fn phrase_shape() {
+        let options = PassphraseOptions {
+            word_count: 5,
+            separator: "+".to_string(),
+            ..PassphraseOptions::default()
+        };
+        let words = load_wordlist(None).unwrap();
+        let phrase = generate(&options).unwrap();
+        let parts: Vec<&str> = phrase.split('+').collect();
+        assert_eq!(parts.len(), 5);
+        assert!(
+            parts.iter().all(|p| words.iter().any(|w| w == p)),
+            "{}",
+            phrase
+        );
+
+        let upper = generate(&PassphraseOptions {
+            capitalization: Capitalization::Upper,
+            ..options.clone()
+        })
+        .unwrap();
+        assert_eq!(upper, upper.to_uppercase());
+
+        let title = generate(&PassphraseOptions {
+            capitalization: Capitalization::Title,
+            ..options.clone()
+        })
+        .unwrap();
+        for part in title.split('+') {
+            let mut chars = part.chars();
+            assert!(chars.next().unwrap().is_uppercase(), "{}", title);
+            assert!(chars.all(|c| !c.is_uppercase()), "{}", title);
+        }
+
+        let with_extras = generate(&PassphraseOptions {
+            insert_digit: true,
+            insert_symbol: true,
+            separator: " ".to_string(),
+            ..options.clone()
+        })
+        .unwrap();
+        assert_eq!(with_extras.split(' ').count(), 5);
+        assert_eq!(with_extras.chars().filter(char::is_ascii_digit).count(), 1);
+        assert_eq!(
+            with_extras
+                .chars()
+                .filter(|c| INSERT_SYMBOLS.contains(*c))
+                .count(),
+            1
+        );
+
+        assert!(
+            generate(&PassphraseOptions {
+                word_count: 0,
+                ..options
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn entropy_counts_words_and_extras() {
+        let options = PassphraseOptions::default();
+        let bits = options.entropy_bits();
+        assert!((bits - 6.0 * 7776f64.log2()).abs() < 1e-9);
+        let random = PassphraseOptions {
+            capitalization: Capitalization::Random,
+            insert_digit: true,
+            ..options
+        };
+        assert!(random.entropy_bits() > bits + 6.0);
+    }
+
+    #[test]
+    fn reads_cp1251_lists() {
+        let path = temp_path("cp1251");
+        std::fs::write(&path, to_cp1251(&russian_words().join("\r\n"))).unwrap();
+        let words = load_wordlist(Some(&path));
+        std::fs::remove_file(&path).unwrap();
+        let words = words.unwrap();
+        assert_eq!(words.len(), 100);
+        assert_eq!(words[0], "каа");
+    }
+
+    #[test]
+    fn reloads_changed_file() {
+        let path = temp_path("changed");
+        let mut words = russian_words();
+        std::fs::write(&path, words.join("\n")).unwrap();
+        let first = load_wordlist(Some(&path)).unwrap();
+        assert_eq!(first.len(), 100);
+
+        words.push("новое".to_string());
+        std::fs::write(&path, words.join("\n")).unwrap();
+        let second = load_wordlist(Some(&path));
+        std::fs::write(&path, "мало слов").unwrap();
+        let too_short = load_wordlist(Some(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(second.unwrap().len(), 101);
+        assert!(too_short.is_err());
+    }
+}
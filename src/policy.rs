@@ -0,0 +1,467 @@
+use rand::distr::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+
+// Спецсимволы в понимании Apple passwordrules (включая пробел)
+const SPECIAL: &str = "-~!@#$%^&*_+=`|(){}[:;\"'<>,.?] ";
+
+// Сколько раз пробуем заново, если пароль нарушил ограничение на повторы подряд
+const MAX_ATTEMPTS: usize = 1000;
+
+// Класс символов в правилах политики
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum CharRule {
+    Upper,
+    Lower,
+    Digit,
+    Special,
+    AsciiPrintable,
+    Unicode,
+    Custom(String),
+}
+
+impl CharRule {
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            CharRule::Upper => c.is_ascii_uppercase(),
+            CharRule::Lower => c.is_ascii_lowercase(),
+            CharRule::Digit => c.is_ascii_digit(),
+            CharRule::Special => SPECIAL.contains(c),
+            CharRule::AsciiPrintable => (' '..='~').contains(&c),
+            CharRule::Unicode => true,
+            CharRule::Custom(chars) => chars.contains(c),
+        }
+    }
+
+    // Символы, из которых генератор выбирает (пробел и не-ASCII не используем)
+    fn generation_chars(&self) -> Vec<char> {
+        match self {
+            CharRule::Unicode | CharRule::AsciiPrintable => ('!'..='~').collect(),
+            CharRule::Special => SPECIAL.chars().filter(|c| *c != ' ').collect(),
+            CharRule::Upper => ('A'..='Z').collect(),
+            CharRule::Lower => ('a'..='z').collect(),
+            CharRule::Digit => ('0'..='9').collect(),
+            CharRule::Custom(chars) => chars.chars().filter(|c| !c.is_whitespace()).collect(),
+        }
+    }
+
+    fn to_rules(&self) -> String {
+        match self {
+            CharRule::Upper => "upper".to_string(),
+            CharRule::Lower => "lower".to_string(),
+            CharRule::Digit => "digit".to_string(),
+            CharRule::Special => "special".to_string(),
+            CharRule::AsciiPrintable => "ascii-printable".to_string(),
+            CharRule::Unicode => "unicode".to_string(),
+            CharRule::Custom(chars) => format!("[{}]", chars),
+        }
+    }
+}
+
+// Требования сайта к паролю
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct PasswordPolicy {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    // Каждая группа: хотя бы один символ из любого её класса
+    pub required: Vec<Vec<CharRule>>,
+    pub allowed: Vec<CharRule>,
+    pub max_consecutive: Option<usize>,
+}
+
+// Политика для сайтов, чей адрес подходит под шаблон (например, "*.example.com")
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SitePolicy {
+    pub pattern: String,
+    pub policy: PasswordPolicy,
+}
+
+impl PasswordPolicy {
+    // Разбираем правила в синтаксисе Apple passwordrules:
+    // "minlength: 8; maxlength: 20; required: lower; required: upper, digit; allowed: [-_]; max-consecutive: 2"
+    pub fn parse(rules: &str) -> Result<Self, String> {
+        let mut policy = Self::default();
+
+        for rule in split_rules(rules) {
+            let rule = rule.trim();
+            if rule.is_empty() {
+                continue;
+            }
+            let (name, value) = rule
+                .split_once(':')
+                .ok_or_else(|| format!("Правило без значения: {}", rule))?;
+            let value = value.trim();
+
+            match name.trim().to_ascii_lowercase().as_str() {
+                "minlength" => policy.min_length = Some(parse_number(name, value)?),
+                "maxlength" => policy.max_length = Some(parse_number(name, value)?),
+                "max-consecutive" => {
+                    let max = parse_number(name, value)?;
+                    // Из нескольких ограничений действует самое строгое
+                    policy.max_consecutive =
+                        Some(policy.max_consecutive.map_or(max, |m| m.min(max)));
+                }
+                "required" => policy.required.push(parse_classes(value)?),
+                "allowed" => policy.allowed.extend(parse_classes(value)?),
+                // По спецификации Apple незнакомые свойства пропускаются: сайты
+                // добавляют их для других менеджеров паролей
+                _ => {}
+            }
+        }
+
+        if let (Some(min), Some(max)) = (policy.min_length, policy.max_length)
+            && min > max
+        {
+            return Err("minlength больше maxlength".to_string());
+        }
+        if policy.max_consecutive == Some(0) {
+            return Err("max-consecutive должен быть больше нуля".to_string());
+        }
+        Ok(policy)
+    }
+
+    // Обратное преобразование в строку правил
+    pub fn to_rules(&self) -> String {
+        let mut rules = Vec::new();
+        if let Some(min) = self.min_length {
+            rules.push(format!("minlength: {}", min));
+        }
+        if let Some(max) = self.max_length {
+            rules.push(format!("maxlength: {}", max));
+        }
+        for group in &self.required {
+            let classes: Vec<String> = group.iter().map(CharRule::to_rules).collect();
+            rules.push(format!("required: {}", classes.join(", ")));
+        }
+        if !self.allowed.is_empty() {
+            let classes: Vec<String> = self.allowed.iter().map(CharRule::to_rules).collect();
+            rules.push(format!("allowed: {}", classes.join(", ")));
+        }
+        if let Some(max) = self.max_consecutive {
+            rules.push(format!("max-consecutive: {}", max));
+        }
+        rules.join("; ")
+    }
+
+    // Разрешён ли символ (без явных ограничений разрешено всё)
+    fn allows(&self, c: char) -> bool {
+        if self.allowed.is_empty() && self.required.is_empty() {
+            return true;
+        }
+        self.allowed
+            .iter()
+            .chain(self.required.iter().flatten())
+            .any(|rule| rule.contains(c))
+    }
+
+    // Список нарушений политики; пустой — пароль подходит
+    pub fn check(&self, password: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        let length = password.chars().count();
+
+        if let Some(min) = self.min_length
+            && length < min
+        {
+            problems.push(format!("Пароль короче {} символов", min));
+        }
+        if let Some(max) = self.max_length
+            && length > max
+        {
+            problems.push(format!("Пароль длиннее {} символов", max));
+        }
+
+        for group in &self.required {
+            if !password
+                .chars()
+                .any(|c| group.iter().any(|rule| rule.contains(c)))
+            {
+                let classes: Vec<String> = group.iter().map(CharRule::to_rules).collect();
+                problems.push(format!("Нужен хотя бы один символ: {}", classes.join(", ")));
+            }
+        }
+
+        let mut forbidden: Vec<char> = password.chars().filter(|c| !self.allows(*c)).collect();
+        forbidden.sort_unstable();
+        forbidden.dedup();
+        if !forbidden.is_empty() {
+            problems.push(format!(
+                "Недопустимые символы: {}",
+                forbidden.into_iter().collect::<String>()
+            ));
+        }
+
+        if let Some(max) = self.max_consecutive
+            && longest_run(password) > max
+        {
+            problems.push(format!("Больше {} одинаковых символов подряд", max));
+        }
+
+        problems
+    }
+
+    // Генерируем пароль, удовлетворяющий политике; длина — желаемая, ограниченная политикой
+    pub fn generate(&self, preferred_length: usize) -> Result<String, String> {
+        let min = self.min_length.unwrap_or(1).max(self.required.len());
+        let length = preferred_length
+            .max(min)
+            .min(self.max_length.unwrap_or(usize::MAX));
+        if length < self.required.len() {
+            return Err("Политика требует больше классов, чем позволяет длина".to_string());
+        }
+
+        // Алфавит: разрешённые и обязательные классы, без повторов
+        let mut pool: Vec<char> = if self.allowed.is_empty() && self.required.is_empty() {
+            CharRule::AsciiPrintable.generation_chars()
+        } else {
+            self.allowed
+                .iter()
+                .chain(self.required.iter().flatten())
+                .flat_map(CharRule::generation_chars)
+                .collect()
+        };
+        pool.sort_unstable();
+        pool.dedup();
+
+        let groups: Vec<Vec<char>> = self
+            .required
+            .iter()
+            .map(|group| {
+                let mut chars: Vec<char> =
+                    group.iter().flat_map(CharRule::generation_chars).collect();
+                chars.sort_unstable();
+                chars.dedup();
+                chars
+            })
+            .collect();
+        if pool.is_empty() || groups.iter().any(Vec::is_empty) {
+            return Err("Политика не оставляет допустимых символов".to_string());
+        }
+
+        let mut rng = rand::rng();
+        let pool_index = Uniform::new(0, pool.len()).map_err(|_| "Пустой алфавит")?;
+        for _ in 0..MAX_ATTEMPTS {
+            let mut password: Vec<char> = groups
+                .iter()
+                .map(|chars| pick(&mut rng, chars))
+                .collect::<Result<_, _>>()?;
+            while password.len() < length {
+                password.push(pool[pool_index.sample(&mut rng)]);
+            }
+            for i in (1..password.len()).rev() {
+                let j = Uniform::new(0, i + 1)
+                    .map_err(|_| "Ошибка генерации")?
+                    .sample(&mut rng);
+                password.swap(i, j);
+            }
+
+            let password: String = password.into_iter().collect();
+            if self
+                .max_consecutive
+                .is_none_or(|max| longest_run(&password) <= max)
+            {
+                return Ok(password);
+            }
+        }
+
+        Err("Не удалось подобрать пароль по политике".to_string())
+    }
+}
+
+impl SitePolicy {
+    // Подходит ли адрес под шаблон: "example.com" совпадает с самим доменом и поддоменами,
+    // "*.example.com" — только с поддоменами
+    pub fn matches(&self, url: &str) -> bool {
+        let Some(host) = host_of(url) else {
+            return false;
+        };
+        let pattern = self.pattern.trim().to_lowercase();
+        match pattern.strip_prefix("*.") {
+            Some(domain) => host.ends_with(&format!(".{}", domain)),
+            None => host == pattern || host.ends_with(&format!(".{}", pattern)),
+        }
+    }
+}
+
+// Имя хоста из URL (схема, порт, путь и учётные данные отбрасываются)
+pub fn host_of(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.trim_end_matches('.').to_lowercase();
+    (!host.is_empty()).then_some(host)
+}
+
+// Точка с запятой внутри [...] не разделяет правила
+fn split_rules(rules: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_brackets = false;
+    for c in rules.chars() {
+        match c {
+            '[' if !in_brackets => in_brackets = true,
+            ']' if in_brackets && !current.ends_with('[') => in_brackets = false,
+            ';' if !in_brackets => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+fn parse_number(name: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Неверное число в правиле {}: {}", name.trim(), value))
+}
+
+// Список классов через запятую: "upper, digit, [-_!]"
+fn parse_classes(value: &str) -> Result<Vec<CharRule>, String> {
+    let mut classes = Vec::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c == ',' || c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '[' {
+            chars.next();
+            // "]" сразу после "[" — это символ набора, а не конец
+            let mut custom = String::new();
+            if chars.peek() == Some(&']') {
+                custom.push(']');
+                chars.next();
+            }
+            loop {
+                match chars.next() {
+                    Some(']') => break,
+                    Some(c) => custom.push(c),
+                    None => return Err("Не закрыта скобка в наборе символов".to_string()),
+                }
+            }
+            classes.push(CharRule::Custom(custom));
+            continue;
+        }
+
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == ',' || c.is_whitespace() {
+                break;
+            }
+            name.push(c);
+            chars.next();
+        }
+        classes.push(match name.to_ascii_lowercase().as_str() {
+            "upper" => CharRule::Upper,
+            "lower" => CharRule::Lower,
+            "digit" => CharRule::Digit,
+            "special" => CharRule::Special,
+            "ascii-printable" => CharRule::AsciiPrintable,
+            "unicode" => CharRule::Unicode,
+            other => return Err(format!("Неизвестный класс символов: {}", other)),
+        });
+    }
+
+    if classes.is_empty() {
+        return Err("Пустой список классов символов".to_string());
+    }
+    Ok(classes)
+}
+
+fn pick(rng: &mut impl rand::Rng, chars: &[char]) -> Result<char, String> {
+    let index = Uniform::new(0, chars.len()).map_err(|_| "Пустой набор символов")?;
+    Ok(chars[index.sample(rng)])
+}
+
+// Длина самой длинной серии одинаковых символов
+fn longest_run(password: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+    for c in password.chars() {
+        run = if previous == Some(c) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(c);
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rules() {
+        let policy = PasswordPolicy::parse(
+            "required: upper; required: digit; allowed: [-_]; max-consecutive: 2",
+        )
+        .unwrap();
+        assert_eq!(
+            policy.required,
+            [vec![CharRule::Upper], vec![CharRule::Digit]]
+        );
+        assert_eq!(policy.allowed, [CharRule::Custom("-_".to_string())]);
+        assert_eq!(policy.max_consecutive, Some(2));
+        assert_eq!(policy.min_length, None);
+        assert_eq!(
+            policy.to_rules(),
+            "required: upper; required: digit; allowed: [-_]; max-consecutive: 2"
+        );
+
+        assert!(policy.check("AB1-").is_empty());
+        assert_eq!(policy.check("ab1").len(), 2); // нет заглавной, строчные не разрешены
+        assert_eq!(policy.check("AAA1").len(), 1);
+    }
+
+    #[test]
+    fn brackets_and_groups() {
+        let policy = PasswordPolicy::parse(
+            "minlength: 8; maxlength: 20; required: lower, upper; allowed: [];:], special",
+        )
+        .unwrap();
+        assert_eq!(policy.required, [vec![CharRule::Lower, CharRule::Upper]]);
+        assert_eq!(
+            policy.allowed,
+            [CharRule::Custom("];:".to_string()), CharRule::Special]
+        );
+        // Из нескольких max-consecutive действует самое строгое
+        let policy = PasswordPolicy::parse("max-consecutive: 3; max-consecutive: 2").unwrap();
+        assert_eq!(policy.max_consecutive, Some(2));
+    }
+
+    #[test]
+    fn ignores_unknown_properties() {
+        let policy =
+            PasswordPolicy::parse("minlength: 10; passwordrules-version: 2; foo: bar").unwrap();
+        assert_eq!(policy.min_length, Some(10));
+        assert_eq!(policy.to_rules(), "minlength: 10");
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let error = PasswordPolicy::parse("minlength: 20; maxlength: 8").unwrap_err();
+        assert_eq!(error, "minlength больше maxlength");
+        assert!(PasswordPolicy::parse("max-consecutive: 0").is_err());
+        assert!(PasswordPolicy::parse("minlength: много").is_err());
+        assert!(PasswordPolicy::parse("required: emoji").is_err());
+        assert!(PasswordPolicy::parse("allowed: [abc").is_err());
+        assert!(PasswordPolicy::parse("minlength").is_err());
+    }
+
+    #[test]
+    fn generated_passwords_follow_policy() {
+        let policy = PasswordPolicy::parse(
+            "minlength: 12; required: upper; required: digit; allowed: lower, [-_]; max-consecutive: 2",
+        )
+        .unwrap();
+        for _ in 0..20 {
+            let password = policy.generate(8).unwrap();
+            assert_eq!(password.chars().count(), 12);
+            assert!(policy.check(&password).is_empty(), "{}", password);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::totp::{self, OtpAlgorithm, OtpConfig, OtpKind};
+use base64::{Engine as _, engine::general_purpose};
+use std::path::Path;
+use zeroize::Zeroize;
+
+// Распознаём все QR-коды на изображении и возвращаем их текст
+pub fn decode_qr_image(path: &Path) -> Result<Vec<String>, String> {
+    let image = image::ImageReader::open(path)
+        .map_err(|_| "Не удалось открыть изображение")?
+        .with_guessed_format()
+        .map_err(|_| "Не удалось определить формат изображения")?
+        .decode()
+        .map_err(|_| "Не удалось декодировать изображение")?
+        .into_luma8();
+
+    let (width, height) = image.dimensions();
+    let mut prepared =
+        rqrr::PreparedImage::prepare_from_greyscale(width as usize, height as usize, |x, y| {
+            image.get_pixel(x as u32, y as u32).0[0]
+        });
+
+    let contents: Vec<String> = prepared
+        .detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok().map(|(_, text)| text))
+        .collect();
+
+    if contents.is_empty() {
+        return Err("QR-код на изображении не найден".to_string());
+    }
+    Ok(contents)
+}
+
+// Разбираем содержимое QR-кода: otpauth:// или пакет otpauth-migration://
+pub fn parse_otp_payload(text: &str) -> Result<Vec<OtpConfig>, String> {
+    let text = text.trim();
+    let lower = text.to_ascii_lowercase();
+    if lower.starts_with("otpauth-migration://") {
+        parse_migration_uri(text)
+    } else if lower.starts_with("otpauth://") {
+        Ok(vec![OtpConfig::from_uri(text)?])
+    } else {
+        Err("QR-код не содержит данных 2FA".to_string())
+    }
+}
+
+// Экспорт Google Authenticator: otpauth-migration://offline?data=<base64 protobuf>
+fn parse_migration_uri(uri: &str) -> Result<Vec<OtpConfig>, String> {
+    let (_, query) = uri
+        .split_once('?')
+        .ok_or("Неверный формат otpauth-migration URI")?;
+    let data = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("data="))
+        .ok_or("В otpauth-migration URI нет параметра data")?;
+
+    // '+' из base64 может прийти неэкранированным и превратиться в пробел
+    let data = totp::percent_decode(data)?.replace(' ', "+");
+    let mut payload = general_purpose::STANDARD
+        .decode(&data)
+        .or_else(|_| general_purpose::STANDARD_NO_PAD.decode(data.trim_end_matches('=')))
+        .map_err(|_| "Неверный base64 в otpauth-migration URI")?;
+
+    let result = parse_migration_payload(&payload);
+    payload.zeroize();
+    result
+}
+
+// Схема MigrationPayload из Google Authenticator:
+//   repeated OtpParameters otp_parameters = 1;
+// OtpParameters:
+//   bytes secret = 1; string name = 2; string issuer = 3;
+//   Algorithm algorithm = 4; DigitCount digits = 5; OtpType type = 6; int64 counter = 7;
+fn parse_migration_payload(payload: &[u8]) -> Result<Vec<OtpConfig>, String> {
+    let mut configs = Vec::new();
+    let mut reader = ProtoReader::new(payload);
+
+    while let Some((field, value)) = reader.next_field()? {
+        if let (1, ProtoValue::Bytes(bytes)) = (field, value) {
+            configs.push(parse_otp_parameters(bytes)?);
+        }
+    }
+
+    if configs.is_empty() {
+        return Err("Пакет миграции не содержит кодов 2FA".to_string());
+    }
+    Ok(configs)
+}
+
+fn parse_otp_parameters(data: &[u8]) -> Result<OtpConfig, String> {
+    let mut config = OtpConfig::default();
+    let mut reader = ProtoReader::new(data);
+    let mut name = String::new();
+
+    while let Some((field, value)) = reader.next_field()? {
+        match (field, value) {
+            (1, ProtoValue::Bytes(secret)) => config.secret = totp::base32_encode(secret),
+            (2, ProtoValue::Bytes(bytes)) => name = String::from_utf8_lossy(bytes).to_string(),
+            (3, ProtoValue::Bytes(bytes)) => {
+                config.issuer = String::from_utf8_lossy(bytes).to_string()
+            }
+            (4, ProtoValue::Varint(algorithm)) => {
+                config.algorithm = match algorithm {
+                    0 | 1 => OtpAlgorithm::Sha1,
+                    2 => OtpAlgorithm::Sha256,
+                    3 => OtpAlgorithm::Sha512,
+                    _ => return Err("Неподдерживаемый алгоритм в пакете миграции".to_string()),
+                }
+            }
+            (5, ProtoValue::Varint(digits)) => config.digits = if digits == 2 { 8 } else { 6 },
+            (6, ProtoValue::Varint(kind)) => {
+                config.kind = if kind == 1 {
+                    OtpKind::Hotp
+                } else {
+                    OtpKind::Totp
+                }
+            }
+            (7, ProtoValue::Varint(counter)) => config.counter = counter,
+            _ => {}
+        }
+    }
+
+    // Имя в пакете имеет тот же вид, что и метка otpauth: "Issuer:account"
+    match name.split_once(':') {
+        Some((issuer, account)) => {
+            if config.issuer.is_empty() {
+                config.issuer = issuer.trim().to_string();
+            }
+            config.account = account.trim().to_string();
+        }
+        None => config.account = name,
+    }
+
+    config.validate()?;
+    Ok(config)
+}
+
+enum ProtoValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+// Минимальный читатель protobuf wire format — ровно столько, сколько нужно для миграции
+struct ProtoReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn read_varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.data.get(self.pos).ok_or("Пакет миграции обрезан")?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Неверное число в пакете миграции".to_string())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or("Пакет миграции обрезан")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u64, ProtoValue<'a>)>, String> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        let key = self.read_varint()?;
+        let value = match key & 0x07 {
+            0 => ProtoValue::Varint(self.read_varint()?),
+            1 => {
+                self.take(8)?;
+                ProtoValue::Fixed
+            }
+            2 => {
+                let len = self.read_varint()? as usize;
+                ProtoValue::Bytes(self.take(len)?)
+            }
+            5 => {
+                self.take(4)?;
+                ProtoValue::Fixed
+            }
+            _ => return Err("Неподдерживаемый тип поля в пакете миграции".to_string()),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+}
+
+// Строим QR-код и возвращаем ширину и модули (true — тёмный)
+pub fn encode_qr(text: &str) -> Result<(usize, Vec<bool>), String> {
+    let code = qrcode::QrCode::with_error_correction_level(text, qrcode::EcLevel::M)
+        .map_err(|_| "Слишком много данных для QR-кода")?;
+    let modules = code
+        .to_colors()
+        .into_iter()
+        .map(|color| color == qrcode::Color::Dark)
+        .collect();
+    Ok((code.width(), modules))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Пакет с двумя кодами: TOTP SHA-256 на 8 цифр и HOTP SHA-1 со счётчиком 42
+    const MIGRATION_URI: &str = "otpauth-migration://offline?data=CjUKCkhlbGxvId6tvu8SGEFDTUUgQ286am9obkBleGFtcGxlLmNvbRoHQUNNRSBDbyACKAIwAgo6ChQxMjM0NTY3ODkwMTIzNDU2Nzg5MBIRYWxpY2VAZXhhbXBsZS5vcmcaB0V4YW1wbGUgASgBMAE4KhABGAEgACh7";
+
+    #[test]
+    fn decodes_migration_uri() {
+        let configs = parse_otp_payload(MIGRATION_URI).unwrap();
+        assert_eq!(configs.len(), 2);
+
+        let totp = &configs[0];
+        assert_eq!(totp.kind, OtpKind::Totp);
+        assert_eq!(totp.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(totp.issuer, "ACME Co");
+        assert_eq!(totp.account, "john@example.com");
+        assert_eq!(totp.algorithm, OtpAlgorithm::Sha256);
+        assert_eq!(totp.digits, 8);
+
+        let hotp = &configs[1];
+        assert_eq!(hotp.kind, OtpKind::Hotp);
+        assert_eq!(hotp.secret, "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(hotp.issuer, "Example");
+        assert_eq!(hotp.account, "alice@example.org");
+        assert_eq!(hotp.algorithm, OtpAlgorithm::Sha1);
+        assert_eq!(hotp.digits, 6);
+        assert_eq!(hotp.counter, 42);
+    }
+
+    #[test]
+    fn payload_kinds() {
+        let configs =
+            parse_otp_payload(" otpauth://totp/Example:alice?secret=JBSWY3DPEHPK3PXP ").unwrap();
+        assert_eq!(configs[0].account, "alice");
+        assert!(parse_otp_payload("https://example.com").is_err());
+        assert!(parse_otp_payload("otpauth-migration://offline").is_err());
+        // Обрезанный пакет
+        assert!(parse_otp_payload("otpauth-migration://offline?data=CjUKCkhlbGxv").is_err());
+    }
+}
@@ -0,0 +1,392 @@
+use crate::vault::Entry;
+
+// Структурный фильтр из строки поиска
+#[derive(Clone, PartialEq, Debug)]
+pub enum Filter {
+    Tag(String),
+    Folder(String),
+    Weak,
+    Expired,
+    Has(Field),
+}
+
+// Поля, наличие которых можно проверить через has:
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Field {
+    Totp,
+    Url,
+    Notes,
+    Tags,
+}
+
+// Разобранный запрос: слова для нечёткого поиска и фильтры
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub filters: Vec<Filter>,
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Self {
+        let mut query = Self::default();
+
+        for token in input.split_whitespace() {
+            let filter = match token.split_once(':') {
+                Some((key, value)) => match key.to_lowercase().as_str() {
+                    "tag" if !value.is_empty() => Some(Filter::Tag(value.to_lowercase())),
+                    "folder" if !value.is_empty() => Some(Filter::Folder(value.to_lowercase())),
+                    "weak" => Some(Filter::Weak),
+                    "expired" => Some(Filter::Expired),
+                    "has" => match value.to_lowercase().as_str() {
+                        "totp" | "2fa" | "otp" => Some(Filter::Has(Field::Totp)),
+                        "url" => Some(Filter::Has(Field::Url)),
+                        "notes" => Some(Filter::Has(Field::Notes)),
+                        "tags" | "tag" => Some(Filter::Has(Field::Tags)),
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                None => None,
+            };
+
+            match filter {
+                Some(filter) => query.filters.push(filter),
+                // Неизвестные префиксы ищем как обычный текст
+                None => query.terms.push(token.to_lowercase()),
+            }
+        }
+
+        query
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.filters.is_empty()
+    }
+
+    // Нужен ли фильтру доступ к секретным полям записи
+    pub fn needs_secrets(&self) -> bool {
+        self.filters.contains(&Filter::Weak)
+    }
+}
+
+// Результат нечёткого сопоставления: оценка и позиции совпавших символов
+#[derive(Clone, Default, Debug)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    pub positions: Vec<usize>,
+}
+
+// Нечёткий поиск подпоследовательности `pattern` в `text` (pattern уже в нижнем регистре).
+// Бонусы за совпадения подряд и в начале слов, штраф за пропуски.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    if pattern.is_empty() {
+        return Some(FuzzyMatch::default());
+    }
+
+    let text_chars: Vec<char> = text.chars().collect();
+    let pattern_chars: Vec<char> = pattern.chars().collect();
+    if pattern_chars.len() > text_chars.len() {
+        return None;
+    }
+
+    // Точное вхождение подстроки всегда лучше разреженного совпадения
+    let lower: Vec<char> = text_chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    if let Some(start) = lower
+        .windows(pattern_chars.len())
+        .position(|window| window == pattern_chars.as_slice())
+    {
+        let word_start = start == 0 || !lower[start - 1].is_alphanumeric();
+        return Some(FuzzyMatch {
+            score: 100 + pattern_chars.len() as i32 * 10 + if word_start { 30 } else { 0 }
+                - start as i32,
+            positions: (start..start + pattern_chars.len()).collect(),
+        });
+    }
+
+    let mut positions = Vec::with_capacity(pattern_chars.len());
+    let mut score = 0;
+    let mut pattern_index = 0;
+    let mut previous: Option<usize> = None;
+
+    for (i, c) in lower.iter().enumerate() {
+        if pattern_index == pattern_chars.len() {
+            break;
+        }
+        if *c != pattern_chars[pattern_index] {
+            continue;
+        }
+
+        score += 10;
+        if previous.is_some_and(|p| p + 1 == i) {
+            score += 15;
+        } else if let Some(p) = previous {
+            score -= (i - p - 1).min(10) as i32;
+        }
+        if i == 0 || !lower[i - 1].is_alphanumeric() {
+            score += 20;
+        }
+
+        positions.push(i);
+        previous = Some(i);
+        pattern_index += 1;
+    }
+
+    (pattern_index == pattern_chars.len()).then_some(FuzzyMatch { score, positions })
+}
+
+// Совпадение записи с запросом и подсветка для видимых колонок
+#[derive(Clone, Default, Debug)]
+pub struct EntryMatch {
+    pub index: usize,
+    pub score: i32,
+    pub service: Vec<usize>,
+    pub login: Vec<usize>,
+}
+
+// Признаки записи, вычисляемые вне модуля поиска
+pub struct EntryFlags {
+    pub weak: bool,
+    pub expired: bool,
+}
+
+// Отбираем и ранжируем записи. `flags` вызывается только для записей,
+// прошедших остальные фильтры, и только если запрос явно этого требует.
+pub fn filter_entries(
+    entries: &[Entry],
+    query: &SearchQuery,
+    flags: impl Fn(&Entry) -> EntryFlags,
+) -> Vec<EntryMatch> {
+    let mut matches: Vec<EntryMatch> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| match_entry(index, entry, query, &flags))
+        .collect();
+
+    if !query.terms.is_empty() {
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then(a.index.cmp(&b.index)));
+    }
+    matches
+}
+
+fn match_entry(
+    index: usize,
+    entry: &Entry,
+    query: &SearchQuery,
+    flags: &impl Fn(&Entry) -> EntryFlags,
+) -> Option<EntryMatch> {
+    let mut needs_flags = false;
+    for filter in &query.filters {
+        let passed = match filter {
+            Filter::Tag(tag) => entry.tags.iter().any(|t| t.to_lowercase() == *tag),
+            Filter::Folder(folder) => {
+                let entry_folder = entry.folder.to_lowercase();
+                entry_folder == *folder || entry_folder.starts_with(&format!("{}/", folder))
+            }
+            Filter::Has(Field::Totp) => entry.otp.is_some(),
+            Filter::Has(Field::Url) => !entry.url.is_empty(),
+            Filter::Has(Field::Notes) => !entry.notes.is_empty(),
+            Filter::Has(Field::Tags) => !entry.tags.is_empty(),
+            Filter::Weak | Filter::Expired => {
+                needs_flags = true;
+                true
+            }
+        };
+        if !passed {
+            return None;
+        }
+    }
+
+    let mut result = EntryMatch {
+        index,
+        ..EntryMatch::default()
+    };
+
+    // Каждое слово должно найтись хотя бы в одном из открытых полей
+    for term in &query.terms {
+        let service = fuzzy_match(term, &entry.service);
+        let login = fuzzy_match(term, &entry.login);
+        let others = [entry.url.as_str(), entry.folder.as_str()]
+            .into_iter()
+            .chain(entry.tags.iter().map(String::as_str))
+            .filter_map(|text| fuzzy_match(term, text))
+            .map(|m| m.score)
+            .max();
+        // В длинных заметках подпоследовательность найдётся почти всегда,
+        // поэтому там ищем только точное вхождение
+        let notes = entry
+            .notes
+            .to_lowercase()
+            .contains(term.as_str())
+            .then_some(50);
+
+        // Совпадения в названии сервиса ценнее остальных
+        let best = [
+            service.as_ref().map(|m| m.score + 20),
+            login.as_ref().map(|m| m.score),
+            others,
+            notes,
+        ]
+        .into_iter()
+        .flatten()
+        .max()?;
+
+        result.score += best;
+        if let Some(m) = service {
+            result.service.extend(m.positions);
+        }
+        if let Some(m) = login {
+            result.login.extend(m.positions);
+        }
+    }
+
+    if needs_flags {
+        let entry_flags = flags(entry);
+        for filter in &query.filters {
+            match filter {
+                Filter::Weak if !entry_flags.weak => return None,
+                Filter::Expired if !entry_flags.expired => return None,
+                _ => {}
+            }
+        }
+    }
+
+    result.service.sort_unstable();
+    result.service.dedup();
+    result.login.sort_unstable();
+    result.login.dedup();
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(service: &str, login: &str) -> Entry {
+        Entry {
+            service: service.to_string(),
+            login: login.to_string(),
+            ..Entry::default()
+        }
+    }
+
+    fn no_flags(_: &Entry) -> EntryFlags {
+        EntryFlags {
+            weak: false,
+            expired: false,
+        }
+    }
+
+    #[test]
+    fn parses_filters_and_terms() {
+        let query = SearchQuery::parse(
+            "  Git TAG:Work folder:Дом/Банк has:2FA weak: expired: foo:bar tag:",
+        );
+        assert_eq!(query.terms, ["git", "foo:bar", "tag:"]);
+        assert_eq!(
+            query.filters,
+            [
+                Filter::Tag("work".to_string()),
+                Filter::Folder("дом/банк".to_string()),
+                Filter::Has(Field::Totp),
+                Filter::Weak,
+                Filter::Expired,
+            ]
+        );
+        assert!(query.needs_secrets());
+        assert!(!SearchQuery::parse("has:url").needs_secrets());
+        assert!(SearchQuery::parse("   ").is_empty());
+        // Неизвестное поле has: ищется как текст
+        assert_eq!(SearchQuery::parse("has:phone").terms, ["has:phone"]);
+    }
+
+    #[test]
+    fn fuzzy_scoring() {
+        // Подстрока в начале слова лучше подстроки в середине
+        let start = fuzzy_match("hub", "GitHub hub").unwrap();
+        assert_eq!(start.positions, [3, 4, 5]);
+        let word = fuzzy_match("git", "GitHub").unwrap();
+        let middle = fuzzy_match("thu", "GitHub").unwrap();
+        assert!(word.score > middle.score);
+
+        // Подпоследовательность: подряд идущие символы ценнее разреженных
+        let dense = fuzzy_match("gh", "git-hub").unwrap();
+        assert_eq!(dense.positions, [0, 4]);
+        let sparse = fuzzy_match("gb", "git-hub").unwrap();
+        assert!(dense.score > sparse.score);
+        // Любая подстрока лучше подпоследовательности
+        assert!(middle.score > dense.score);
+
+        assert!(fuzzy_match("xyz", "GitHub").is_none());
+        assert!(fuzzy_match("githubs", "GitHub").is_none());
+        assert_eq!(fuzzy_match("", "GitHub").unwrap().score, 0);
+        assert_eq!(
+            fuzzy_match("яндекс", "Почта Яндекс").unwrap().positions[0],
+            6
+        );
+    }
+
+    #[test]
+    fn ranks_service_above_login() {
+        let entries = [
+            entry("Почта", "gitlover"),
+            entry("GitHub", "ivan"),
+            entry("Банк", "anna"),
+        ];
+        let matches = filter_entries(&entries, &SearchQuery::parse("git"), no_flags);
+        let order: Vec<usize> = matches.iter().map(|m| m.index).collect();
+        assert_eq!(order, [1, 0]);
+        assert_eq!(matches[0].service, [0, 1, 2]);
+        assert_eq!(matches[1].login, [0, 1, 2]);
+
+        // Все слова запроса должны найтись
+        assert!(filter_entries(&entries, &SearchQuery::parse("git anna"), no_flags).is_empty());
+    }
+
+    #[test]
+    fn structured_filters() {
+        let mut work = entry("GitHub", "ivan");
+        work.tags = vec!["Work".to_string()];
+        work.folder = "Работа/Код".to_string();
+        let mut home = entry("Банк", "anna");
+        home.folder = "Работакод".to_string();
+        home.notes = "PIN в сейфе".to_string();
+        let entries = [work, home];
+
+        let indices = |query: &str| -> Vec<usize> {
+            filter_entries(&entries, &SearchQuery::parse(query), |entry| EntryFlags {
+                weak: entry.login == "anna",
+                expired: entry.login == "ivan",
+            })
+            .iter()
+            .map(|m| m.index)
+            .collect()
+        };
+        assert_eq!(indices("tag:work"), [0]);
+        // Папка совпадает целиком или как родитель
+        assert_eq!(indices("folder:работа"), [0]);
+        assert_eq!(indices("has:notes"), [1]);
+        assert_eq!(indices("pin"), [1]);
+        assert_eq!(indices("weak:"), [1]);
+        assert_eq!(indices("expired:"), [0]);
+        assert!(indices("expired: weak:").is_empty());
+    }
+
+    #[test]
+    fn flags_only_for_filtered_entries() {
+        let entries = [entry("GitHub", "ivan"), entry("Банк", "anna")];
+        let checked = std::cell::Cell::new(0);
+        let query = SearchQuery::parse("банк weak:");
+        filter_entries(&entries, &query, |_| {
+            checked.set(checked.get() + 1);
+            no_flags(&entries[0])
+        });
+        assert_eq!(checked.get(), 1);
+        // Без weak: и expired: признаки не вычисляются вовсе
+        filter_entries(&entries, &SearchQuery::parse("банк"), |_| {
+            panic!("признаки не нужны")
+        });
+    }
+}
@@ -1,3 +1,5 @@
+use crate::generator::{GeneratorOptions, GeneratorProfile};
+use crate::strength::MasterPasswordPolicy;
 use crate::theme::ThemeVisuals;
 use dirs;
 use serde::{Deserialize, Serialize};
@@ -7,6 +9,21 @@ use std::path::PathBuf;
 pub struct Settings {
     pub current_theme: String,
     pub ui_scale: f32,
+    // Через сколько дней записи удаляются из корзины (0 — никогда)
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u32,
+    // Последние параметры генератора паролей и сохранённые профили
+    #[serde(default)]
+    pub generator_options: GeneratorOptions,
+    #[serde(default)]
+    pub generator_profiles: Vec<GeneratorProfile>,
+    // Минимальные требования к мастер-паролю при создании хранилища и смене пароля
+    #[serde(default)]
+    pub master_password_policy: MasterPasswordPolicy,
+}
+
+fn default_trash_retention_days() -> u32 {
+    30
 }
 
 impl Default for Settings {
@@ -14,6 +31,10 @@ impl Default for Settings {
         Self {
             current_theme: "Dark".to_string(),
             ui_scale: 1.0,
+            trash_retention_days: default_trash_retention_days(),
+            generator_options: GeneratorOptions::default(),
+            generator_profiles: Vec::new(),
+            master_password_policy: MasterPasswordPolicy::default(),
         }
     }
 }
@@ -42,7 +42,7 @@ pub fn get_vault_path(name: &str) -> Option<PathBuf> {
 }
 
 pub fn create_encrypted_vault(
-    entries: &[Entry],
+    data: &VaultData,
     master_password: &str,
     path: &Path,
 ) -> Result<Vault, String> {
@@ -60,7 +60,7 @@ pub fn create_encrypted_vault(
     let key = derive_key(master_password, &salt);
 
     // Сериализуем записи
-    let plaintext = serde_json::to_vec(entries).map_err(|_| "Ошибка сериализации записей")?;
+    let plaintext = serde_json::to_vec(data).map_err(|_| "Ошибка сериализации записей")?;
 
     // Шифруем данные
     let (ciphertext, used_nonce) = encrypt_data(&plaintext, &key);
@@ -68,7 +68,7 @@ pub fn create_encrypted_vault(
     // Создаем файл хранилища
     let vault_file = VaultFile {
         header: VaultHeader {
-            version: 1,
+            version: 2,
             creation_date: SystemTime::now(),
             salt: general_purpose::STANDARD.encode(salt),
             nonce: general_purpose::STANDARD.encode(used_nonce),
@@ -107,10 +107,10 @@ pub fn create_new_vault(name: String, password: &str) -> Result<Vault, String> {
         // Получаем ключ
         let key = derive_key(password, &salt);
 
-        // Создаем пустой список записей
-        let empty_entries: Vec<Entry> = Vec::new();
+        // Создаем пустое хранилище
+        let empty_data = VaultData::default();
         let plaintext =
-            serde_json::to_vec(&empty_entries).map_err(|_| "Ошибка сериализации пустого списка")?;
+            serde_json::to_vec(&empty_data).map_err(|_| "Ошибка сериализации пустого списка")?;
 
         // Шифруем данные
         let (ciphertext, used_nonce) = encrypt_data(&plaintext, &key);
@@ -118,7 +118,7 @@ pub fn create_new_vault(name: String, password: &str) -> Result<Vault, String> {
         // Создаем файл хранилища
         let vault_file = VaultFile {
             header: VaultHeader {
-                version: 1,
+                version: 2,
                 creation_date: SystemTime::now(),
                 salt: general_purpose::STANDARD.encode(salt),
                 nonce: general_purpose::STANDARD.encode(used_nonce),
@@ -137,6 +137,32 @@ pub fn create_new_vault(name: String, password: &str) -> Result<Vault, String> {
     }
 }
 
+// Затираем файл нулями и удаляем его. На SSD и в файловых системах с копированием
+// при записи старые блоки могут сохраниться, поэтому это лишь снижает риск
+pub fn wipe_file(path: &Path) -> Result<(), String> {
+    use std::io::Write;
+
+    let len = fs::metadata(path)
+        .map_err(|e| format!("Файл не найден: {}", e))?
+        .len();
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("Не удалось открыть файл: {}", e))?;
+    let zeros = [0u8; 8192];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..chunk])
+            .map_err(|e| format!("Не удалось перезаписать файл: {}", e))?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()
+        .map_err(|e| format!("Не удалось перезаписать файл: {}", e))?;
+    drop(file);
+    fs::remove_file(path).map_err(|e| format!("Не удалось удалить файл: {}", e))
+}
+
 // Получение списка всех доступных хранилищ в директории
 pub fn get_available_vaults() -> Vec<std::path::PathBuf> {
     use dirs;
@@ -158,3 +184,43 @@ pub fn get_available_vaults() -> Vec<std::path::PathBuf> {
 
     vaults
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwords_survive_save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("shroombrella-test-{}.vault", std::process::id()));
+        let mut data = VaultData::default();
+        data.entries.push(Entry {
+            service: "mail".to_string(),
+            login: "alice".to_string(),
+            password: "correct horse".to_string(),
+            ..Entry::default()
+        });
+        create_encrypted_vault(&data, "master", &path).unwrap();
+
+        let loaded = load_vault_from_path(&path).and_then(|vault| vault.decrypt("master"));
+        fs::remove_file(&path).unwrap();
+        let mut loaded = loaded.unwrap();
+        assert_eq!(loaded.entries[0].login, "alice");
+        assert_eq!(loaded.entries[0].password, "correct horse");
+
+        // После открытия смена пароля снова пополняет историю
+        let entry = &mut loaded.entries[0];
+        entry.set_password("battery staple".to_string());
+        assert_eq!(entry.password_history.len(), 1);
+        assert_eq!(entry.password_history[0].password, "correct horse");
+    }
+
+    #[test]
+    fn entries_without_password_still_load() {
+        // Старые хранилища не сохраняли пароль
+        let entry: Entry =
+            serde_json::from_str(r#"{"service": "mail", "login": "alice"}"#).unwrap();
+        assert_eq!(entry.login, "alice");
+        assert!(entry.password.is_empty());
+    }
+}
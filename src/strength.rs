@@ -0,0 +1,1106 @@
+// Оценка надёжности пароля в духе zxcvbn: ищем в пароле словарные слова,
+// узоры на клавиатуре, повторы, последовательности и даты, затем подбираем
+// разбиение с минимальным числом попыток перебора.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Частотные словари zxcvbn (Dropbox, MIT): чем выше слово, тем раньше его пробуют
+const FREQUENCY_LISTS: [(&str, &str); 6] = [
+    (
+        "passwords",
+        include_str!("../assets/frequency_lists/passwords.txt"),
+    ),
+    (
+        "english_wikipedia",
+        include_str!("../assets/frequency_lists/english_wikipedia.txt"),
+    ),
+    (
+        "female_names",
+        include_str!("../assets/frequency_lists/female_names.txt"),
+    ),
+    (
+        "male_names",
+        include_str!("../assets/frequency_lists/male_names.txt"),
+    ),
+    (
+        "surnames",
+        include_str!("../assets/frequency_lists/surnames.txt"),
+    ),
+    (
+        "us_tv_and_film",
+        include_str!("../assets/frequency_lists/us_tv_and_film.txt"),
+    ),
+];
+
+// Раскладки клавиатуры по рядам (со сдвигом рядов, как на обычной клавиатуре)
+const QWERTY: [(&str, &str); 4] = [
+    ("`1234567890-=", "~!@#$%^&*()_+"),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+    ("asdfghjkl;'", "ASDFGHJKL:\""),
+    ("zxcvbnm,./", "ZXCVBNM<>?"),
+];
+const JCUKEN: [(&str, &str); 4] = [
+    ("ё1234567890-=", "Ё!\"№;%:?*()_+"),
+    ("йцукенгшщзхъ\\", "ЙЦУКЕНГШЩЗХЪ/"),
+    ("фывапролджэ", "ФЫВАПРОЛДЖЭ"),
+    ("ячсмитьбю.", "ЯЧСМИТЬБЮ,"),
+];
+// Цифровой блок: ряды без сдвига
+const KEYPAD: [&str; 4] = ["/*-", "789+", "456", "1230."];
+
+// Длиннее этого пароль анализируем только перебором (защита от квадратичной сложности)
+const MAX_ANALYZED_LENGTH: usize = 64;
+
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+const MIN_GUESSES_BEFORE_GROWING_SEQUENCE: f64 = 10_000.0;
+const MIN_SUBMATCH_GUESSES_SINGLE_CHAR: f64 = 10.0;
+const MIN_SUBMATCH_GUESSES_MULTI_CHAR: f64 = 50.0;
+const MIN_YEAR_SPACE: f64 = 20.0;
+
+// Замены букв похожими символами
+const L33T_TABLE: [(char, &str); 12] = [
+    ('a', "4@"),
+    ('b', "8"),
+    ('c', "({[<"),
+    ('e', "3"),
+    ('g', "69"),
+    ('i', "1!|"),
+    ('l', "1|7"),
+    ('o', "0"),
+    ('s', "$5"),
+    ('t', "+7"),
+    ('x', "%"),
+    ('z', "2"),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Pattern {
+    Dictionary {
+        dictionary: &'static str,
+        rank: usize,
+        reversed: bool,
+        l33t: bool,
+    },
+    Spatial {
+        graph: &'static str,
+        turns: usize,
+        shifted: usize,
+    },
+    Repeat {
+        base_token: String,
+        count: usize,
+    },
+    Sequence {
+        ascending: bool,
+    },
+    Date,
+    Year,
+    Bruteforce,
+}
+
+// Найденный фрагмент пароля (индексы символов, включительно)
+#[derive(Clone, Debug)]
+struct Match {
+    i: usize,
+    j: usize,
+    token: String,
+    pattern: Pattern,
+    guesses: f64,
+}
+
+// Результат оценки
+#[derive(Clone, Debug, Default)]
+pub struct Estimate {
+    pub guesses: f64,
+    pub guesses_log10: f64,
+    pub score: u8, // 0 — очень слабый … 4 — надёжный
+    pub warning: String,
+    pub suggestions: Vec<String>,
+}
+
+impl Estimate {
+    pub fn label(&self) -> &'static str {
+        score_label(self.score)
+    }
+
+    // Время подбора при медленном хешировании (10^4 попыток в секунду)
+    pub fn crack_time(&self) -> String {
+        const GUESSES_PER_SECOND: f64 = 1e4;
+        const MINUTE: f64 = 60.0;
+        const HOUR: f64 = MINUTE * 60.0;
+        const DAY: f64 = HOUR * 24.0;
+        const MONTH: f64 = DAY * 31.0;
+        const YEAR: f64 = MONTH * 12.0;
+        const CENTURY: f64 = YEAR * 100.0;
+
+        let seconds = self.guesses / GUESSES_PER_SECOND;
+        if seconds < 1.0 {
+            "меньше секунды".to_string()
+        } else if seconds < MINUTE {
+            format!("{:.0} с", seconds)
+        } else if seconds < HOUR {
+            format!("{:.0} мин", seconds / MINUTE)
+        } else if seconds < DAY {
+            format!("{:.0} ч", seconds / HOUR)
+        } else if seconds < MONTH {
+            format!("{:.0} дн", seconds / DAY)
+        } else if seconds < YEAR {
+            format!("{:.0} мес", seconds / MONTH)
+        } else if seconds < CENTURY {
+            format!("{:.0} лет", seconds / YEAR)
+        } else {
+            "века".to_string()
+        }
+    }
+
+    // Энтропия по числу попыток: log2(guesses)
+    pub fn entropy_bits(&self) -> f64 {
+        self.guesses_log10 * std::f64::consts::LOG2_10
+    }
+
+    pub fn is_weak(&self) -> bool {
+        is_weak_score(self.score)
+    }
+}
+
+// Минимальное требование к мастер-паролю
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum MasterPasswordPolicy {
+    MinScore(u8),    // оценка не ниже заданной (0…4)
+    MinEntropy(u32), // не меньше заданного числа бит (log2 числа попыток)
+}
+
+impl Default for MasterPasswordPolicy {
+    fn default() -> Self {
+        MasterPasswordPolicy::MinScore(2)
+    }
+}
+
+impl MasterPasswordPolicy {
+    pub fn title(&self) -> String {
+        match self {
+            MasterPasswordPolicy::MinScore(score) => {
+                format!("оценка не ниже «{}»", score_label(*score))
+            }
+            MasterPasswordPolicy::MinEntropy(bits) => format!("не меньше {} бит", bits),
+        }
+    }
+
+    pub fn check(&self, estimate: &Estimate) -> Result<(), String> {
+        let satisfied = match self {
+            MasterPasswordPolicy::MinScore(score) => estimate.score >= *score,
+            MasterPasswordPolicy::MinEntropy(bits) => estimate.entropy_bits() >= *bits as f64,
+        };
+        if satisfied {
+            Ok(())
+        } else {
+            Err(format!(
+                "Мастер-пароль слишком слабый: требуется {}",
+                self.title()
+            ))
+        }
+    }
+}
+
+// Слабым считаем пароль, который подбирается быстрее 10^10 попыток
+pub fn is_weak_score(score: u8) -> bool {
+    score < 3
+}
+
+pub fn score_label(score: u8) -> &'static str {
+    match score {
+        0 => "Очень слабый",
+        1 => "Слабый",
+        2 => "Средний",
+        3 => "Хороший",
+        _ => "Надёжный",
+    }
+}
+
+// Оцениваем пароль; `user_inputs` — слова, связанные с записью (сервис, логин)
+pub fn estimate(password: &str, user_inputs: &[&str]) -> Estimate {
+    let chars: Vec<char> = password.chars().collect();
+    if chars.is_empty() {
+        let mut estimate = Estimate::default();
+        feedback(&mut estimate, &[]);
+        return estimate;
+    }
+
+    let analyzed = &chars[..chars.len().min(MAX_ANALYZED_LENGTH)];
+    let user_dictionary = user_dictionary(user_inputs);
+    let matches = omnimatch(analyzed, &user_dictionary);
+    let (guesses, mut sequence) = most_guessable_sequence(analyzed, matches);
+
+    // Остаток слишком длинного пароля считаем случайными символами
+    let mut guesses = guesses;
+    if chars.len() > analyzed.len() {
+        let rest = chars.len() - analyzed.len();
+        guesses *= BRUTEFORCE_CARDINALITY.powi(rest as i32);
+        sequence.push(Match {
+            i: analyzed.len(),
+            j: chars.len() - 1,
+            token: chars[analyzed.len()..].iter().collect(),
+            pattern: Pattern::Bruteforce,
+            guesses: BRUTEFORCE_CARDINALITY.powi(rest as i32),
+        });
+    }
+
+    let guesses_log10 = guesses.log10();
+    let mut estimate = Estimate {
+        guesses,
+        guesses_log10,
+        score: score_from_guesses(guesses),
+        ..Estimate::default()
+    };
+    feedback(&mut estimate, &sequence);
+    estimate
+}
+
+fn score_from_guesses(guesses: f64) -> u8 {
+    const DELTA: f64 = 5.0;
+    if guesses < 1e3 + DELTA {
+        0
+    } else if guesses < 1e6 + DELTA {
+        1
+    } else if guesses < 1e8 + DELTA {
+        2
+    } else if guesses < 1e10 + DELTA {
+        3
+    } else {
+        4
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Поиск шаблонов
+// ---------------------------------------------------------------------------
+
+type RankedDictionary = HashMap<String, usize>;
+
+fn dictionaries() -> &'static Vec<(&'static str, RankedDictionary)> {
+    static DICTIONARIES: OnceLock<Vec<(&'static str, RankedDictionary)>> = OnceLock::new();
+    DICTIONARIES.get_or_init(|| {
+        FREQUENCY_LISTS
+            .iter()
+            .map(|(name, list)| {
+                let mut ranked = HashMap::new();
+                for (rank, word) in list.lines().filter(|w| !w.is_empty()).enumerate() {
+                    ranked.entry(word.to_string()).or_insert(rank + 1);
+                }
+                (*name, ranked)
+            })
+            .collect()
+    })
+}
+
+fn user_dictionary(user_inputs: &[&str]) -> RankedDictionary {
+    let mut ranked = HashMap::new();
+    let words = user_inputs
+        .iter()
+        .flat_map(|input| input.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty());
+    for (rank, word) in words.enumerate() {
+        ranked.entry(word.to_lowercase()).or_insert(rank + 1);
+    }
+    ranked
+}
+
+fn omnimatch(chars: &[char], user_dictionary: &RankedDictionary) -> Vec<Match> {
+    let mut matches = Vec::new();
+    dictionary_match(chars, user_dictionary, &mut matches);
+    spatial_match(chars, &mut matches);
+    repeat_match(chars, user_dictionary, &mut matches);
+    sequence_match(chars, &mut matches);
+    date_match(chars, &mut matches);
+    matches.sort_by_key(|m| (m.i, m.j));
+    matches
+}
+
+fn lowercase(chars: &[char]) -> Vec<char> {
+    chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect()
+}
+
+// Все вхождения слов из словарей, в том числе задом наперёд и с заменами символов
+fn dictionary_match(chars: &[char], user_dictionary: &RankedDictionary, matches: &mut Vec<Match>) {
+    let lower = lowercase(chars);
+    let n = chars.len();
+    let all: Vec<(&'static str, &RankedDictionary)> = dictionaries()
+        .iter()
+        .map(|(name, dictionary)| (*name, dictionary))
+        .chain(std::iter::once(("user_inputs", user_dictionary)))
+        .collect();
+
+    let mut push = |i: usize, j: usize, word: &str, reversed: bool, l33t: bool| {
+        for (name, dictionary) in &all {
+            if let Some(&rank) = dictionary.get(word) {
+                let token: String = chars[i..=j].iter().collect();
+                let guesses = dictionary_guesses(&token, rank, reversed, l33t, word);
+                matches.push(Match {
+                    i,
+                    j,
+                    token,
+                    pattern: Pattern::Dictionary {
+                        dictionary: name,
+                        rank,
+                        reversed,
+                        l33t,
+                    },
+                    guesses,
+                });
+            }
+        }
+    };
+
+    for i in 0..n {
+        for j in i..n {
+            let word: String = lower[i..=j].iter().collect();
+            push(i, j, &word, false, false);
+
+            if j > i {
+                let reversed: String = lower[i..=j].iter().rev().collect();
+                if reversed != word {
+                    push(i, j, &reversed, true, false);
+                }
+            }
+
+            // Заменённые символы пробуем в двух вариантах (1 → i или l и т. п.)
+            for primary in [true, false] {
+                if let Some(unsubbed) = unl33t(&lower[i..=j], primary)
+                    && unsubbed != word
+                    && j > i
+                {
+                    push(i, j, &unsubbed, false, true);
+                }
+            }
+        }
+    }
+}
+
+fn unl33t(chars: &[char], primary: bool) -> Option<String> {
+    let mut changed = false;
+    let word = chars
+        .iter()
+        .map(|&c| {
+            let mut letters = L33T_TABLE
+                .iter()
+                .filter(|(_, subs)| subs.contains(c))
+                .map(|(letter, _)| *letter);
+            let letter = if primary {
+                letters.next()
+            } else {
+                letters.next_back()
+            };
+            match letter {
+                Some(letter) => {
+                    changed = true;
+                    letter
+                }
+                None => c,
+            }
+        })
+        .collect();
+    changed.then_some(word)
+}
+
+fn spatial_match(chars: &[char], matches: &mut Vec<Match>) {
+    for (name, graph) in graphs() {
+        let mut i = 0;
+        while i + 2 < chars.len() {
+            let mut j = i;
+            let mut turns = 0;
+            let mut last_direction = None;
+            let mut shifted = usize::from(graph.is_shifted(chars[i]));
+
+            while j + 1 < chars.len() {
+                let Some(direction) = graph.direction(chars[j], chars[j + 1]) else {
+                    break;
+                };
+                if last_direction != Some(direction) {
+                    turns += 1;
+                    last_direction = Some(direction);
+                }
+                if graph.is_shifted(chars[j + 1]) {
+                    shifted += 1;
+                }
+                j += 1;
+            }
+
+            if j - i + 1 >= 3 {
+                let token: String = chars[i..=j].iter().collect();
+                let guesses = graph.guesses(j - i + 1, turns, shifted);
+                matches.push(Match {
+                    i,
+                    j,
+                    token,
+                    pattern: Pattern::Spatial {
+                        graph: name,
+                        turns,
+                        shifted,
+                    },
+                    guesses,
+                });
+            }
+            i = j.max(i + 1);
+        }
+    }
+}
+
+// Повторы одного фрагмента подряд: "aaaa", "abcabcabc"
+fn repeat_match(chars: &[char], user_dictionary: &RankedDictionary, matches: &mut Vec<Match>) {
+    let n = chars.len();
+    let mut i = 0;
+    while i < n {
+        let mut best: Option<(usize, usize)> = None; // длина основы, число повторов
+        for base_len in 1..=(n - i) / 2 {
+            let base = &chars[i..i + base_len];
+            let mut count = 1;
+            while i + (count + 1) * base_len <= n
+                && &chars[i + count * base_len..i + (count + 1) * base_len] == base
+            {
+                count += 1;
+            }
+            if count > 1 && best.is_none_or(|(len, c)| base_len * count > len * c) {
+                best = Some((base_len, count));
+            }
+        }
+
+        match best {
+            Some((base_len, count)) => {
+                let j = i + base_len * count - 1;
+                let base_chars = &chars[i..i + base_len];
+                let base_token: String = base_chars.iter().collect();
+                // Основу оцениваем рекурсивно: "abcabc" не сложнее "abc"
+                let base_matches = omnimatch(base_chars, user_dictionary);
+                let (base_guesses, _) = most_guessable_sequence(base_chars, base_matches);
+                matches.push(Match {
+                    i,
+                    j,
+                    token: chars[i..=j].iter().collect(),
+                    pattern: Pattern::Repeat { base_token, count },
+                    guesses: base_guesses * count as f64,
+                });
+                i = j + 1;
+            }
+            None => i += 1,
+        }
+    }
+}
+
+// Последовательности с постоянным шагом: "abcd", "9753", "ЭЮЯ"
+fn sequence_match(chars: &[char], matches: &mut Vec<Match>) {
+    if chars.len() < 3 {
+        return;
+    }
+    let delta_at = |k: usize| chars[k + 1] as i64 - chars[k] as i64;
+
+    let mut i = 0;
+    while i + 2 < chars.len() {
+        let delta = delta_at(i);
+        let mut j = i + 1;
+        while j + 1 < chars.len() && delta_at(j) == delta {
+            j += 1;
+        }
+
+        if j - i + 1 >= 3 && delta != 0 && delta.abs() <= 5 {
+            let token: String = chars[i..=j].iter().collect();
+            let first = chars[i];
+            let mut base = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            if delta < 0 {
+                base *= 2.0;
+            }
+            matches.push(Match {
+                i,
+                j,
+                token,
+                pattern: Pattern::Sequence {
+                    ascending: delta > 0,
+                },
+                guesses: base * (j - i + 1) as f64,
+            });
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+fn current_year() -> i64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0);
+    1970 + (days as f64 / 365.2425) as i64
+}
+
+// Годы и даты: "1987", "25.12.1990", "311299"
+fn date_match(chars: &[char], matches: &mut Vec<Match>) {
+    let n = chars.len();
+    let reference = current_year();
+    let year_guesses = |year: i64| ((year - reference).abs() as f64).max(MIN_YEAR_SPACE);
+
+    for i in 0..n {
+        for j in i + 3..n.min(i + 10) {
+            let token: String = chars[i..=j].iter().collect();
+            let digits_only = token.chars().all(|c| c.is_ascii_digit());
+
+            if digits_only && token.len() == 4 {
+                let year: i64 = token.parse().unwrap_or(0);
+                if (1900..=2099).contains(&year) {
+                    matches.push(Match {
+                        i,
+                        j,
+                        token: token.clone(),
+                        pattern: Pattern::Year,
+                        guesses: year_guesses(year),
+                    });
+                }
+            }
+
+            let Some((year, separated)) = parse_date(&token) else {
+                continue;
+            };
+            let mut guesses = year_guesses(year) * 365.0;
+            if separated {
+                guesses *= 4.0;
+            }
+            matches.push(Match {
+                i,
+                j,
+                token,
+                pattern: Pattern::Date,
+                guesses,
+            });
+        }
+    }
+}
+
+// Разбираем дату в виде ДДММГГ(ГГ), ГГГГММДД или с разделителями; возвращаем год
+fn parse_date(token: &str) -> Option<(i64, bool)> {
+    let separators = [' ', '-', '/', '\\', '_', '.'];
+    let parts: Vec<&str> = token.split(separators).collect();
+    let (numbers, separated): (Vec<i64>, bool) = if parts.len() == 3 {
+        if parts.iter().any(|p| p.is_empty() || p.len() > 4)
+            || !parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit()))
+        {
+            return None;
+        }
+        (parts.iter().filter_map(|p| p.parse().ok()).collect(), true)
+    } else if token.chars().all(|c| c.is_ascii_digit()) && (token.len() == 6 || token.len() == 8) {
+        let split = if token.len() == 6 {
+            vec![&token[0..2], &token[2..4], &token[4..6]]
+        } else if token.starts_with("19") || token.starts_with("20") {
+            vec![&token[0..4], &token[4..6], &token[6..8]]
+        } else {
+            vec![&token[0..2], &token[2..4], &token[4..8]]
+        };
+        (split.iter().filter_map(|p| p.parse().ok()).collect(), false)
+    } else {
+        return None;
+    };
+    if numbers.len() != 3 {
+        return None;
+    }
+
+    let valid_day_month = |a: i64, b: i64| {
+        ((1..=31).contains(&a) && (1..=12).contains(&b))
+            || ((1..=12).contains(&a) && (1..=31).contains(&b))
+    };
+    let normalize_year = |year: i64| match year {
+        0..=49 => 2000 + year,
+        50..=99 => 1900 + year,
+        _ => year,
+    };
+
+    let (first, second, third) = (numbers[0], numbers[1], numbers[2]);
+    if (1000..=2099).contains(&first) && valid_day_month(second, third) {
+        Some((first, separated))
+    } else if valid_day_month(first, second) && (third < 100 || (1000..=2099).contains(&third)) {
+        Some((normalize_year(third), separated))
+    } else {
+        None
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Клавиатурные графы
+// ---------------------------------------------------------------------------
+
+struct KeyboardGraph {
+    positions: HashMap<char, (i32, i32, bool)>, // ряд, колонка, с Shift
+    slanted: bool,
+    keys: usize,
+    average_degree: f64,
+}
+
+impl KeyboardGraph {
+    fn new(rows: &[(&str, &str)], slanted: bool) -> Self {
+        let mut positions = HashMap::new();
+        for (r, (plain, shifted)) in rows.iter().enumerate() {
+            for (c, ch) in plain.chars().enumerate() {
+                positions.insert(ch, (r as i32, c as i32, false));
+            }
+            for (c, ch) in shifted.chars().enumerate() {
+                positions.entry(ch).or_insert((r as i32, c as i32, true));
+            }
+        }
+
+        let mut graph = Self {
+            positions,
+            slanted,
+            keys: 0,
+            average_degree: 0.0,
+        };
+        let keys: Vec<(i32, i32)> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(r, (plain, _))| {
+                (0..plain.chars().count()).map(move |c| (r as i32, c as i32))
+            })
+            .collect();
+        let degree: usize = keys
+            .iter()
+            .map(|&(r, c)| {
+                graph
+                    .offsets()
+                    .iter()
+                    .filter(|(dr, dc)| keys.contains(&(r + dr, c + dc)))
+                    .count()
+            })
+            .sum();
+        graph.keys = keys.len();
+        graph.average_degree = degree as f64 / keys.len() as f64;
+        graph
+    }
+
+    // Соседние клавиши: у обычной клавиатуры ряды сдвинуты, у цифрового блока — сетка
+    fn offsets(&self) -> &'static [(i32, i32)] {
+        if self.slanted {
+            &[(0, -1), (0, 1), (-1, 0), (-1, 1), (1, -1), (1, 0)]
+        } else {
+            &[
+                (0, -1),
+                (0, 1),
+                (-1, 0),
+                (1, 0),
+                (-1, -1),
+                (-1, 1),
+                (1, -1),
+                (1, 1),
+            ]
+        }
+    }
+
+    fn is_shifted(&self, c: char) -> bool {
+        self.positions.get(&c).is_some_and(|p| p.2)
+    }
+
+    fn direction(&self, from: char, to: char) -> Option<usize> {
+        let (r1, c1, _) = self.positions.get(&from)?;
+        let (r2, c2, _) = self.positions.get(&to)?;
+        self.offsets()
+            .iter()
+            .position(|&(dr, dc)| r1 + dr == *r2 && c1 + dc == *c2)
+    }
+
+    fn guesses(&self, length: usize, turns: usize, shifted: usize) -> f64 {
+        let mut guesses = 0.0;
+        for i in 2..=length {
+            for j in 1..=turns.min(i - 1) {
+                guesses +=
+                    binomial(i - 1, j - 1) * self.keys as f64 * self.average_degree.powi(j as i32);
+            }
+        }
+        if shifted > 0 {
+            let unshifted = length - shifted;
+            if unshifted == 0 {
+                guesses *= 2.0;
+            } else {
+                let variations: f64 = (1..=shifted.min(unshifted))
+                    .map(|i| binomial(shifted + unshifted, i))
+                    .sum();
+                guesses *= variations;
+            }
+        }
+        guesses
+    }
+}
+
+fn graphs() -> &'static Vec<(&'static str, KeyboardGraph)> {
+    static GRAPHS: OnceLock<Vec<(&'static str, KeyboardGraph)>> = OnceLock::new();
+    GRAPHS.get_or_init(|| {
+        let keypad: Vec<(&str, &str)> = KEYPAD.iter().map(|row| (*row, "")).collect();
+        vec![
+            ("qwerty", KeyboardGraph::new(&QWERTY, true)),
+            ("jcuken", KeyboardGraph::new(&JCUKEN, true)),
+            ("keypad", KeyboardGraph::new(&keypad, false)),
+        ]
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Оценка числа попыток
+// ---------------------------------------------------------------------------
+
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    let k = k.min(n - k);
+    (1..=k).fold(1.0, |acc, i| acc * (n + 1 - i) as f64 / i as f64)
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).fold(1.0, |acc, i| acc * i as f64)
+}
+
+fn dictionary_guesses(token: &str, rank: usize, reversed: bool, l33t: bool, word: &str) -> f64 {
+    let mut guesses = rank as f64 * uppercase_variations(token);
+    if l33t {
+        guesses *= l33t_variations(token, word);
+    }
+    if reversed {
+        guesses *= 2.0;
+    }
+    guesses
+}
+
+// Сколько вариантов регистра нужно перебрать для слова
+fn uppercase_variations(token: &str) -> f64 {
+    let upper = token.chars().filter(|c| c.is_uppercase()).count();
+    let lower = token.chars().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_upper = token.chars().next().is_some_and(char::is_uppercase);
+    let last_upper = token.chars().last().is_some_and(char::is_uppercase);
+    if lower == 0 || (upper == 1 && (first_upper || last_upper)) {
+        return 2.0;
+    }
+    (1..=upper.min(lower))
+        .map(|i| binomial(upper + lower, i))
+        .sum()
+}
+
+fn l33t_variations(token: &str, word: &str) -> f64 {
+    let substituted = token
+        .chars()
+        .zip(word.chars())
+        .filter(|(t, w)| t.to_lowercase().next() != Some(*w))
+        .count();
+    let unsubstituted = token.chars().count() - substituted;
+    if substituted == 0 {
+        return 1.0;
+    }
+    if unsubstituted == 0 {
+        return 2.0;
+    }
+    let variations: f64 = (1..=substituted.min(unsubstituted))
+        .map(|i| binomial(substituted + unsubstituted, i))
+        .sum();
+    variations.max(2.0)
+}
+
+fn bruteforce_match(chars: &[char], i: usize, j: usize) -> Match {
+    let length = j - i + 1;
+    let mut guesses = BRUTEFORCE_CARDINALITY.powi(length as i32);
+    let min = if length == 1 {
+        MIN_SUBMATCH_GUESSES_SINGLE_CHAR
+    } else {
+        MIN_SUBMATCH_GUESSES_MULTI_CHAR
+    };
+    guesses = guesses.max(min + 1.0);
+    Match {
+        i,
+        j,
+        token: chars[i..=j].iter().collect(),
+        pattern: Pattern::Bruteforce,
+        guesses,
+    }
+}
+
+// Поиск разбиения пароля на фрагменты с минимальным итоговым числом попыток
+// (динамическое программирование, как в zxcvbn)
+fn most_guessable_sequence(chars: &[char], matches: Vec<Match>) -> (f64, Vec<Match>) {
+    let n = chars.len();
+    if n == 0 {
+        return (1.0, Vec::new());
+    }
+
+    // Нижняя граница: фрагмент не может быть проще отдельного символа
+    let matches: Vec<Match> = matches
+        .into_iter()
+        .map(|mut m| {
+            let min = if m.j == m.i {
+                MIN_SUBMATCH_GUESSES_SINGLE_CHAR
+            } else {
+                MIN_SUBMATCH_GUESSES_MULTI_CHAR
+            };
+            if m.j - m.i + 1 < n {
+                m.guesses = m.guesses.max(min);
+            }
+            m.guesses = m.guesses.max(1.0);
+            m
+        })
+        .collect();
+
+    let mut by_end: Vec<Vec<&Match>> = vec![Vec::new(); n];
+    for m in &matches {
+        by_end[m.j].push(m);
+    }
+
+    // optimal[k][l] — лучшее разбиение префикса 0..=k из l фрагментов
+    struct Step {
+        matched: Match,
+        pi: f64,
+        g: f64,
+    }
+    let mut optimal: Vec<HashMap<usize, Step>> = (0..n).map(|_| HashMap::new()).collect();
+
+    fn update(optimal: &mut [HashMap<usize, Step>], m: Match, l: usize) {
+        let k = m.j;
+        let mut pi = m.guesses;
+        if l > 1 {
+            pi *= optimal[m.i - 1][&(l - 1)].pi;
+        }
+        let g = factorial(l) * pi + MIN_GUESSES_BEFORE_GROWING_SEQUENCE.powi(l as i32 - 1);
+        // Более длинное разбиение не нужно, если короче уже не хуже
+        if optimal[k]
+            .iter()
+            .any(|(&other_l, step)| other_l <= l && step.g <= g)
+        {
+            return;
+        }
+        optimal[k].insert(l, Step { matched: m, pi, g });
+    }
+
+    for (k, ending) in by_end.iter().enumerate() {
+        for m in ending {
+            if m.i > 0 {
+                let lengths: Vec<usize> = optimal[m.i - 1].keys().copied().collect();
+                for l in lengths {
+                    update(&mut optimal, (*m).clone(), l + 1);
+                }
+            } else {
+                update(&mut optimal, (*m).clone(), 1);
+            }
+        }
+
+        // Перебор: фрагмент i..=k случайными символами
+        update(&mut optimal, bruteforce_match(chars, 0, k), 1);
+        for i in 1..=k {
+            let previous: Vec<(usize, bool)> = optimal[i - 1]
+                .iter()
+                .map(|(&l, step)| (l, step.matched.pattern == Pattern::Bruteforce))
+                .collect();
+            for (l, previous_is_bruteforce) in previous {
+                // Два перебора подряд равносильны одному
+                if !previous_is_bruteforce {
+                    update(&mut optimal, bruteforce_match(chars, i, k), l + 1);
+                }
+            }
+        }
+    }
+
+    // Восстанавливаем последовательность с конца
+    let Some((&best_l, best)) = optimal[n - 1].iter().min_by(|a, b| a.1.g.total_cmp(&b.1.g)) else {
+        return (1.0, Vec::new());
+    };
+    let guesses = best.g;
+
+    let mut sequence = Vec::new();
+    let mut k = n as isize - 1;
+    let mut l = best_l;
+    while k >= 0 && l > 0 {
+        let step = &optimal[k as usize][&l];
+        sequence.push(step.matched.clone());
+        k = step.matched.i as isize - 1;
+        l -= 1;
+    }
+    sequence.reverse();
+    (guesses, sequence)
+}
+
+// ---------------------------------------------------------------------------
+// Подсказки
+// ---------------------------------------------------------------------------
+
+fn feedback(estimate: &mut Estimate, sequence: &[Match]) {
+    if sequence.is_empty() {
+        estimate.suggestions = vec![
+            "Используйте несколько слов, избегайте распространённых фраз".to_string(),
+            "Символы, цифры и заглавные буквы не обязательны".to_string(),
+        ];
+        return;
+    }
+    if estimate.score > 2 {
+        return;
+    }
+
+    let sole_match = sequence.len() == 1;
+    let Some(longest) = sequence.iter().max_by_key(|m| m.token.chars().count()) else {
+        return;
+    };
+
+    let mut suggestions = vec!["Добавьте ещё одно-два слова, лучше необычных".to_string()];
+    let warning = match &longest.pattern {
+        Pattern::Dictionary {
+            dictionary,
+            rank,
+            reversed,
+            l33t,
+        } => {
+            let warning = match *dictionary {
+                "passwords" if sole_match && !l33t && !reversed => {
+                    if *rank <= 10 {
+                        "Это один из 10 самых популярных паролей"
+                    } else if *rank <= 100 {
+                        "Это один из 100 самых популярных паролей"
+                    } else {
+                        "Это очень распространённый пароль"
+                    }
+                }
+                "passwords" => "Пароль похож на распространённый",
+                "english_wikipedia" if sole_match => "Одно слово легко подобрать",
+                "surnames" | "male_names" | "female_names" => {
+                    if sole_match {
+                        "Имена и фамилии легко подобрать"
+                    } else {
+                        "Распространённые имена и фамилии легко подобрать"
+                    }
+                }
+                "user_inputs" => "Пароль содержит название сервиса или логин",
+                _ => "",
+            };
+
+            let token = &longest.token;
+            let upper = token.chars().filter(|c| c.is_uppercase()).count();
+            if token.chars().next().is_some_and(char::is_uppercase) && upper == 1 {
+                suggestions.push("Заглавная первая буква почти не помогает".to_string());
+            } else if upper > 0 && token.chars().all(|c| !c.is_lowercase()) {
+                suggestions.push(
+                    "Все заглавные подобрать почти так же легко, как все строчные".to_string(),
+                );
+            }
+            if *reversed && token.chars().count() >= 4 {
+                suggestions.push("Слова задом наперёд не усложняют подбор".to_string());
+            }
+            if *l33t {
+                suggestions.push("Замены вроде «@» вместо «a» почти не помогают".to_string());
+            }
+            warning
+        }
+        Pattern::Spatial { turns, .. } => {
+            suggestions
+                .push("Используйте более длинный узор с большим числом поворотов".to_string());
+            if *turns == 1 {
+                "Ряды клавиш подряд легко подобрать"
+            } else {
+                "Короткие узоры на клавиатуре легко подобрать"
+            }
+        }
+        Pattern::Repeat { base_token, .. } => {
+            suggestions.push("Избегайте повторяющихся слов и символов".to_string());
+            if base_token.chars().count() == 1 {
+                "Повторы вроде «aaa» легко подобрать"
+            } else {
+                "Повторы вроде «abcabcabc» лишь немного сложнее «abc»"
+            }
+        }
+        Pattern::Sequence { .. } => {
+            suggestions.push("Избегайте последовательностей".to_string());
+            "Последовательности вроде «abc» или «6543» легко подобрать"
+        }
+        Pattern::Year => {
+            suggestions.push("Избегайте недавних годов".to_string());
+            suggestions.push("Избегайте годов, связанных с вами".to_string());
+            "Недавние годы легко подобрать"
+        }
+        Pattern::Date => {
+            suggestions.push("Избегайте дат и годов, связанных с вами".to_string());
+            "Даты часто легко подобрать"
+        }
+        Pattern::Bruteforce => "",
+    };
+
+    estimate.warning = warning.to_string();
+    estimate.suggestions = suggestions;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Шаблоны, из которых складывается самая вероятная разбивка пароля
+    fn patterns(password: &str) -> Vec<Pattern> {
+        let chars: Vec<char> = password.chars().collect();
+        let matches = omnimatch(&chars, &user_dictionary(&[]));
+        let (_, sequence) = most_guessable_sequence(&chars, matches);
+        sequence.into_iter().map(|m| m.pattern).collect()
+    }
+
+    #[test]
+    fn common_passwords_score_zero() {
+        for password in ["password", "qwerty", "123456", "Password1"] {
+            let estimate = estimate(password, &[]);
+            assert_eq!(estimate.score, 0, "{}", password);
+            assert!(estimate.is_weak());
+            assert!(!estimate.warning.is_empty(), "{}", password);
+        }
+        assert_eq!(estimate("", &[]).score, 0);
+    }
+
+    #[test]
+    fn passphrase_is_strong() {
+        let estimate = estimate("correcthorsebatterystaple", &[]);
+        assert!(estimate.score >= 3, "{:?}", estimate);
+        assert!(!estimate.is_weak());
+    }
+
+    #[test]
+    fn detects_patterns() {
+        assert!(matches!(patterns("1991-05-04")[..], [Pattern::Date]));
+        assert!(matches!(
+            patterns("aaaaaa")[..],
+            [Pattern::Repeat { count: 6, .. }]
+        ));
+        assert!(matches!(
+            patterns("abcdef")[..],
+            [Pattern::Sequence { ascending: true }]
+        ));
+        assert!(matches!(
+            patterns("zyxwvu")[..],
+            [Pattern::Sequence { ascending: false }]
+        ));
+        assert!(matches!(
+            patterns("zxcvfrew")[..],
+            [Pattern::Spatial { turns: 3, .. }]
+        ));
+    }
+
+    #[test]
+    fn user_inputs_lower_the_score() {
+        let password = "Shroombrella-Ivanov";
+        let plain = estimate(password, &[]);
+        let personal = estimate(password, &["ivanov@example.com", "Shroombrella", "Ivanov"]);
+        assert!(personal.guesses < plain.guesses);
+        assert!(personal.score < plain.score, "{:?} {:?}", personal, plain);
+    }
+
+    #[test]
+    fn long_passwords_are_truncated() {
+        let password = "x".repeat(MAX_ANALYZED_LENGTH + 10);
+        let estimate = estimate(&password, &[]);
+        assert!(estimate.guesses.is_finite());
+    }
+}
@@ -0,0 +1,130 @@
+// Даты и время без внешних зависимостей: григорианский календарь в UTC.
+// Нужны и интерфейсу, и импорту/экспорту других форматов
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Форматируем момент времени как "ГГГГ-ММ-ДД ЧЧ:ММ" (UTC)
+pub fn format_time(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let minutes = secs % 86_400 / 60;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+// Дата без времени: "ГГГГ-ММ-ДД" (UTC)
+pub fn format_date(time: SystemTime) -> String {
+    format_time(time)[..10].to_string()
+}
+
+// Разбираем дату "ГГГГ-ММ-ДД" (полночь UTC)
+pub fn parse_date(input: &str) -> Option<SystemTime> {
+    let mut parts = input.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1970..=9999).contains(&year) || !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // Отсеиваем несуществующие даты вроде 31 апреля
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    Some(UNIX_EPOCH + Duration::from_secs(days as u64 * 86_400))
+}
+
+// Перевод даты григорианского календаря в количество дней от 1970-01-01
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Перевод количества дней от 1970-01-01 в дату григорианского календаря
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Время ISO 8601 "2024-05-01T12:30:00Z" (KeePass XML, Bitwarden); дробные секунды
+// отбрасываются, смещения пояса в этих форматах нет
+pub fn parse_iso_time(text: &str) -> Option<SystemTime> {
+    let (date, time) = text.trim().trim_end_matches('Z').split_once('T')?;
+    let midnight = parse_date(date)?;
+    let mut parts = time.split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next().unwrap_or("0").parse().ok()?;
+    Some(midnight + Duration::from_secs(hours * 3600 + minutes * 60 + seconds as u64))
+}
+
+// Обратное преобразование: всегда в UTC с суффиксом Z
+pub fn format_iso_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        format_date(time),
+        seconds % 86_400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse_date("2024-02-29"), Some(at(1_709_164_800)));
+        assert_eq!(format_date(at(1_709_164_800)), "2024-02-29");
+        assert_eq!(format_time(at(1_714_566_615)), "2024-05-01 12:30");
+        for invalid in [
+            "2023-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "1969-12-31",
+            "вчера",
+        ] {
+            assert_eq!(parse_date(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn iso_times() {
+        let time = at(1_714_566_615);
+        assert_eq!(format_iso_time(time), "2024-05-01T12:30:15Z");
+        assert_eq!(parse_iso_time("2024-05-01T12:30:15Z"), Some(time));
+        // Дробные секунды Bitwarden отбрасываются
+        assert_eq!(parse_iso_time("2024-05-01T12:30:15.123Z"), Some(time));
+        assert_eq!(parse_iso_time("2024-05-01"), None);
+    }
+}
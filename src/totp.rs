@@ -0,0 +1,445 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroize;
+
+// Алгоритм HMAC для генерации одноразовых кодов
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OtpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OtpAlgorithm::Sha1 => "SHA1",
+            OtpAlgorithm::Sha256 => "SHA256",
+            OtpAlgorithm::Sha512 => "SHA512",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_uppercase().replace('-', "").as_str() {
+            "SHA1" => Ok(OtpAlgorithm::Sha1),
+            "SHA256" => Ok(OtpAlgorithm::Sha256),
+            "SHA512" => Ok(OtpAlgorithm::Sha512),
+            _ => Err(format!("Неподдерживаемый алгоритм: {}", name)),
+        }
+    }
+}
+
+// Тип одноразового кода: по времени (RFC 6238) или по счётчику (RFC 4226)
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OtpKind {
+    #[default]
+    Totp,
+    Hotp,
+}
+
+// Параметры 2FA, хранящиеся вместе с записью
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct OtpConfig {
+    pub kind: OtpKind,
+    pub secret: String, // base32
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    pub counter: u64,
+    pub issuer: String,
+    pub account: String,
+}
+
+impl Default for OtpConfig {
+    fn default() -> Self {
+        Self {
+            kind: OtpKind::Totp,
+            secret: String::new(),
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+            counter: 0,
+            issuer: String::new(),
+            account: String::new(),
+        }
+    }
+}
+
+impl Zeroize for OtpConfig {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+        self.counter.zeroize();
+        self.issuer.zeroize();
+        self.account.zeroize();
+    }
+}
+
+impl OtpConfig {
+    // Разбираем ввод пользователя: otpauth:// URI или голый base32-секрет
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        if input.to_ascii_lowercase().starts_with("otpauth://") {
+            Self::from_uri(input)
+        } else {
+            let secret = normalize_secret(input);
+            base32_decode(&secret)?;
+            Ok(Self {
+                secret,
+                ..Self::default()
+            })
+        }
+    }
+
+    // Разбираем URI формата otpauth://TYPE/LABEL?PARAMETERS
+    pub fn from_uri(uri: &str) -> Result<Self, String> {
+        let rest = uri
+            .get(..10)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("otpauth://"))
+            .map(|_| &uri[10..])
+            .ok_or("URI должен начинаться с otpauth://")?;
+
+        let (kind_str, rest) = rest.split_once('/').ok_or("Неверный формат otpauth URI")?;
+        let kind = match kind_str.to_ascii_lowercase().as_str() {
+            "totp" => OtpKind::Totp,
+            "hotp" => OtpKind::Hotp,
+            _ => return Err(format!("Неизвестный тип кода: {}", kind_str)),
+        };
+
+        let (label, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let label = percent_decode(label)?;
+
+        let mut config = Self {
+            kind,
+            ..Self::default()
+        };
+
+        // Метка имеет вид "Issuer:account" или просто "account"
+        match label.split_once(':') {
+            Some((issuer, account)) => {
+                config.issuer = issuer.trim().to_string();
+                config.account = account.trim().to_string();
+            }
+            None => config.account = label.trim().to_string(),
+        }
+
+        let mut has_counter = false;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = percent_decode(value)?;
+            match key.to_ascii_lowercase().as_str() {
+                "secret" => config.secret = normalize_secret(&value),
+                "issuer" => config.issuer = value,
+                "algorithm" => config.algorithm = OtpAlgorithm::from_name(&value)?,
+                "digits" => {
+                    config.digits = value
+                        .parse()
+                        .map_err(|_| "Неверное количество цифр в otpauth URI")?
+                }
+                "period" => {
+                    config.period = value.parse().map_err(|_| "Неверный период в otpauth URI")?
+                }
+                "counter" => {
+                    config.counter = value
+                        .parse()
+                        .map_err(|_| "Неверный счётчик в otpauth URI")?;
+                    has_counter = true;
+                }
+                _ => {}
+            }
+        }
+
+        if config.kind == OtpKind::Hotp && !has_counter {
+            return Err("Для HOTP в URI обязателен параметр counter".to_string());
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Собираем otpauth:// URI обратно (для экспорта и QR-кодов)
+    pub fn to_uri(&self) -> String {
+        let kind = match self.kind {
+            OtpKind::Totp => "totp",
+            OtpKind::Hotp => "hotp",
+        };
+        let label = if self.issuer.is_empty() {
+            percent_encode(&self.account)
+        } else {
+            format!(
+                "{}:{}",
+                percent_encode(&self.issuer),
+                percent_encode(&self.account)
+            )
+        };
+
+        let mut uri = format!(
+            "otpauth://{}/{}?secret={}&algorithm={}&digits={}",
+            kind,
+            label,
+            self.secret,
+            self.algorithm.name(),
+            self.digits
+        );
+        match self.kind {
+            OtpKind::Totp => uri.push_str(&format!("&period={}", self.period)),
+            OtpKind::Hotp => uri.push_str(&format!("&counter={}", self.counter)),
+        }
+        if !self.issuer.is_empty() {
+            uri.push_str(&format!("&issuer={}", percent_encode(&self.issuer)));
+        }
+        uri
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.secret.is_empty() {
+            return Err("Секрет 2FA не может быть пустым".to_string());
+        }
+        base32_decode(&self.secret)?;
+        if !(6..=10).contains(&self.digits) {
+            return Err("Количество цифр должно быть от 6 до 10".to_string());
+        }
+        if self.kind == OtpKind::Totp && self.period == 0 {
+            return Err("Период TOTP должен быть больше нуля".to_string());
+        }
+        Ok(())
+    }
+
+    // Код для заданного момента времени (TOTP) или текущего счётчика (HOTP)
+    pub fn code_at(&self, unix_time: u64) -> Result<String, String> {
+        let mut key = base32_decode(&self.secret)?;
+        let code = match self.kind {
+            OtpKind::Totp => totp(&key, unix_time, self.period, self.digits, self.algorithm),
+            OtpKind::Hotp => hotp(&key, self.counter, self.digits, self.algorithm),
+        };
+        key.zeroize();
+        Ok(code)
+    }
+
+    // Сколько секунд осталось до смены TOTP-кода
+    pub fn seconds_remaining(&self, unix_time: u64) -> u64 {
+        if self.period == 0 {
+            return 0;
+        }
+        self.period - unix_time % self.period
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// RFC 4226: HMAC-based One-Time Password
+pub fn hotp(key: &[u8], counter: u64, digits: u32, algorithm: OtpAlgorithm) -> String {
+    let message = counter.to_be_bytes();
+    let mut digest = match algorithm {
+        OtpAlgorithm::Sha1 => hmac_digest::<Hmac<sha1::Sha1>>(key, &message),
+        OtpAlgorithm::Sha256 => hmac_digest::<Hmac<sha2::Sha256>>(key, &message),
+        OtpAlgorithm::Sha512 => hmac_digest::<Hmac<sha2::Sha512>>(key, &message),
+    };
+
+    // Динамическое усечение (раздел 5.3)
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = ((digest[offset] as u32 & 0x7f) << 24)
+        | ((digest[offset + 1] as u32) << 16)
+        | ((digest[offset + 2] as u32) << 8)
+        | (digest[offset + 3] as u32);
+    digest.zeroize();
+
+    let code = binary as u64 % 10u64.pow(digits);
+    format!("{:0width$}", code, width = digits as usize)
+}
+
+// RFC 6238: Time-based One-Time Password
+pub fn totp(
+    key: &[u8],
+    unix_time: u64,
+    period: u64,
+    digits: u32,
+    algorithm: OtpAlgorithm,
+) -> String {
+    hotp(key, unix_time / period.max(1), digits, algorithm)
+}
+
+fn hmac_digest<M: Mac + hmac::digest::KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC принимает ключ любой длины");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Убираем пробелы, дефисы и выравнивание, приводим к верхнему регистру
+fn normalize_secret(secret: &str) -> String {
+    secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub fn base32_decode(input: &str) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in normalize_secret(input).bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or("Секрет 2FA содержит недопустимые символы (ожидается base32)")?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+
+    if output.is_empty() {
+        return Err("Секрет 2FA не может быть пустым".to_string());
+    }
+    Ok(output)
+}
+
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut output = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    output
+}
+
+pub fn percent_decode(input: &str) -> Result<String, String> {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = input
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or("Неверное экранирование в otpauth URI")?;
+                output.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                output.push(b' ');
+                i += 1;
+            }
+            b => {
+                output.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(output).map_err(|_| "otpauth URI содержит неверный UTF-8".to_string())
+}
+
+fn percent_encode(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            output.push(byte as char);
+        } else {
+            output.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ключи из RFC 6238, приложение B
+    const SEED_SHA1: &[u8] = b"12345678901234567890";
+    const SEED_SHA256: &[u8] = b"12345678901234567890123456789012";
+    const SEED_SHA512: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    #[test]
+    fn hotp_rfc4226_vectors() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(
+                hotp(SEED_SHA1, counter as u64, 6, OtpAlgorithm::Sha1),
+                *code
+            );
+        }
+    }
+
+    #[test]
+    fn totp_rfc6238_vectors() {
+        let vectors: [(u64, &str, &str, &str); 6] = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        for (time, sha1, sha256, sha512) in vectors {
+            assert_eq!(totp(SEED_SHA1, time, 30, 8, OtpAlgorithm::Sha1), sha1);
+            assert_eq!(totp(SEED_SHA256, time, 30, 8, OtpAlgorithm::Sha256), sha256);
+            assert_eq!(totp(SEED_SHA512, time, 30, 8, OtpAlgorithm::Sha512), sha512);
+        }
+    }
+
+    #[test]
+    fn base32_decoding() {
+        assert_eq!(
+            base32_decode("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap(),
+            SEED_SHA1
+        );
+        assert_eq!(base32_decode("gezd gnbv-gy3t").unwrap(), b"1234567");
+    }
+
+    #[test]
+    fn base32_encoding() {
+        assert_eq!(base32_encode(SEED_SHA1), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        // Неполная группа дополняется нулевыми битами, без "="
+        assert_eq!(base32_encode(b"1234567"), "GEZDGNBVGY3Q");
+        assert_eq!(base32_encode(b""), "");
+        for len in 1..=10 {
+            let data = &SEED_SHA512[..len];
+            assert_eq!(base32_decode(&base32_encode(data)).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn parse_otpauth_uri() {
+        let config = OtpConfig::parse(
+            "otpauth://totp/ACME%20Co:john@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME%20Co&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(config.kind, OtpKind::Totp);
+        assert_eq!(config.issuer, "ACME Co");
+        assert_eq!(config.account, "john@example.com");
+        assert_eq!(config.algorithm, OtpAlgorithm::Sha256);
+        assert_eq!(config.digits, 8);
+        assert_eq!(config.period, 60);
+        assert_eq!(OtpConfig::from_uri(&config.to_uri()).unwrap(), config);
+
+        assert!(OtpConfig::parse("otpauth://hotp/x?secret=GEZDGNBV").is_err());
+        assert!(OtpConfig::parse("not base32!").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+// Справочник сервисов с двухфакторной аутентификацией (домен → способы входа).
+// Формат — tfa.json из API v3 каталога 2fa.directory: массив пар
+// [название, {"domain": …, "additional-domains": […], "tfa": […]}].
+// Встроена составленная вручную выборка популярных сервисов (см. assets/twofactor/README.md);
+// полный снимок каталога можно загрузить из файла
+use crate::policy;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const BUNDLED: &str = include_str!("../assets/twofactor/tfa.json");
+
+#[derive(Deserialize)]
+struct RawService {
+    domain: String,
+    #[serde(default, rename = "additional-domains")]
+    additional_domains: Vec<String>,
+    #[serde(default)]
+    tfa: Vec<String>,
+}
+
+// Сервис из справочника
+#[derive(Clone, Debug)]
+pub struct Service {
+    pub name: String,
+    pub methods: Vec<String>, // идентификаторы 2fa.directory: totp, sms, u2f…
+}
+
+impl Service {
+    // Можно ли хранить второй фактор прямо в записи
+    pub fn supports_totp(&self) -> bool {
+        self.methods.iter().any(|method| method == "totp")
+    }
+
+    pub fn methods_title(&self) -> String {
+        self.methods
+            .iter()
+            .map(|method| method_title(method))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn method_title(method: &str) -> &str {
+    match method {
+        "totp" => "TOTP",
+        "sms" => "SMS",
+        "call" => "звонок",
+        "email" => "почта",
+        "u2f" => "ключ U2F/FIDO2",
+        "custom-software" => "своё приложение",
+        "custom-hardware" => "свой токен",
+        other => other,
+    }
+}
+
+pub struct Directory {
+    // Домен → индекс сервиса
+    domains: HashMap<String, usize>,
+    services: Vec<Service>,
+    pub bundled: bool,
+}
+
+impl Directory {
+    // Встроенный снимок
+    pub fn bundled() -> Self {
+        let mut directory = Self::parse(BUNDLED).unwrap_or_else(|_| Self {
+            domains: HashMap::new(),
+            services: Vec::new(),
+            bundled: true,
+        });
+        directory.bundled = true;
+        directory
+    }
+
+    // Загруженный пользователем снимок, если он есть, иначе встроенный
+    pub fn load_default() -> Self {
+        Self::default_path()
+            .filter(|path| path.exists())
+            .and_then(|path| Self::load(&path).ok())
+            .unwrap_or_else(Self::bundled)
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("shroombrella").join("tfa.json"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Не удалось прочитать справочник 2FA: {}", e))?;
+        Self::parse(&json)
+    }
+
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let raw: Vec<(String, RawService)> = serde_json::from_str(json)
+            .map_err(|_| "Неверный формат справочника 2FA (ожидается tfa.json из API v3)")?;
+
+        let mut directory = Self {
+            domains: HashMap::new(),
+            services: Vec::new(),
+            bundled: false,
+        };
+        for (name, service) in raw {
+            // Сервисы без второго фактора в справочнике не нужны
+            if service.tfa.is_empty() {
+                continue;
+            }
+            let index = directory.services.len();
+            for domain in std::iter::once(&service.domain).chain(&service.additional_domains) {
+                directory
+                    .domains
+                    .insert(domain.trim().to_lowercase(), index);
+            }
+            directory.services.push(Service {
+                name,
+                methods: service.tfa,
+            });
+        }
+        if directory.services.is_empty() {
+            return Err("Справочник 2FA пуст".to_string());
+        }
+        Ok(directory)
+    }
+
+    pub fn len(&self) -> usize {
+        self.services.len()
+    }
+
+    // Сервис по адресу: ищем хост и все его родительские домены
+    // (accounts.google.com → google.com)
+    pub fn lookup(&self, url: &str) -> Option<&Service> {
+        let host = policy::host_of(url)?;
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let mut domain = host;
+        loop {
+            if let Some(&index) = self.domains.get(domain) {
+                return self.services.get(index);
+            }
+            domain = domain.split_once('.')?.1;
+            // Зону верхнего уровня саму по себе не проверяем
+            if !domain.contains('.') {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"[
+        ["Google", {"domain": "google.com", "additional-domains": ["youtube.com"], "tfa": ["totp", "sms"]}],
+        ["Example Mail", {"domain": "mail.example.co.uk", "tfa": ["email"]}],
+        ["No 2FA", {"domain": "plain.example", "tfa": []}]
+    ]"#;
+
+    #[test]
+    fn lookup_walks_up_subdomains() {
+        let directory = Directory::parse(SAMPLE).unwrap();
+        assert_eq!(directory.len(), 2);
+
+        let google = directory
+            .lookup("https://accounts.google.com/signin")
+            .unwrap();
+        assert_eq!(google.name, "Google");
+        assert!(google.supports_totp());
+        assert_eq!(google.methods_title(), "TOTP, SMS");
+        assert_eq!(directory.lookup("www.youtube.com").unwrap().name, "Google");
+        assert_eq!(
+            directory
+                .lookup("https://a.b.mail.example.co.uk:8443")
+                .unwrap()
+                .name,
+            "Example Mail"
+        );
+
+        // Родитель зарегистрированного домена и сама зона не совпадают
+        assert!(directory.lookup("https://example.co.uk").is_none());
+        assert!(directory.lookup("https://notgoogle.com").is_none());
+        assert!(directory.lookup("https://plain.example").is_none());
+        assert!(directory.lookup("").is_none());
+    }
+
+    #[test]
+    fn bundled_sample_parses() {
+        let directory = Directory::bundled();
+        assert!(directory.bundled);
+        assert!(directory.len() > 0);
+        assert!(directory.lookup("https://github.com").is_some());
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        assert!(Directory::parse(r#"{"Google": {"domain": "google.com"}}"#).is_err());
+        assert!(Directory::parse("[]").is_err());
+    }
+}
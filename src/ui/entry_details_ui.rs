@@ -0,0 +1,337 @@
+use crate::totp::{self, OtpKind};
+use crate::ui::widgets;
+use crate::vault::{Entry, ExpiryStatus};
+use eframe::egui;
+use std::time::SystemTime;
+
+// Действие в панели записи, которое выполняет менеджер паролей
+pub enum DetailAction {
+    Used, // скопирован пароль или код — обновляем время использования
+    Edit,
+    ToggleFavorite,
+    TogglePin,
+    ShowQr,
+    AdvanceHotp,
+    RotatePassword,
+    AddAttachment,
+    SaveAttachment(usize),
+    RemoveAttachment(usize),
+    Close,
+}
+
+// Панель с полями выбранной записи
+#[derive(Default)]
+pub struct EntryDetails {
+    shown_index: Option<usize>,
+    reveal_password: bool,
+}
+
+impl EntryDetails {
+    pub fn ui(&mut self, ui: &mut egui::Ui, index: usize, entry: &Entry) -> Option<DetailAction> {
+        // Для другой записи пароль снова скрываем
+        if self.shown_index != Some(index) {
+            self.shown_index = Some(index);
+            self.reveal_password = false;
+        }
+
+        let mut action = None;
+
+        ui.horizontal(|ui| {
+            ui.heading(&entry.service);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("✖").on_hover_text("Закрыть").clicked() {
+                    action = Some(DetailAction::Close);
+                }
+                if ui.button("✏️").on_hover_text("Редактировать").clicked() {
+                    action = Some(DetailAction::Edit);
+                }
+                if ui
+                    .add(egui::Button::new("📌").selected(entry.pinned))
+                    .on_hover_text("Закрепить вверху")
+                    .clicked()
+                {
+                    action = Some(DetailAction::TogglePin);
+                }
+                let star = if entry.favorite { "★" } else { "☆" };
+                if ui.button(star).on_hover_text("Избранное").clicked() {
+                    action = Some(DetailAction::ToggleFavorite);
+                }
+            });
+        });
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_salt("entry_details_scroll")
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                egui::Grid::new("entry_details_grid")
+                    .num_columns(3)
+                    .spacing([8.0, 6.0])
+                    .show(ui, |ui| {
+                        copy_row(ui, "👤 Логин:", &entry.login);
+
+                        // Пароль скрыт, пока не нажата кнопка показа
+                        ui.label("🔑 Пароль:");
+                        if self.reveal_password {
+                            ui.monospace(&entry.password);
+                        } else {
+                            ui.label("••••••••");
+                        }
+                        ui.horizontal(|ui| {
+                            let eye = if self.reveal_password { "🙈" } else { "👁" };
+                            if ui
+                                .small_button(eye)
+                                .on_hover_text("Показать/скрыть")
+                                .clicked()
+                            {
+                                self.reveal_password = !self.reveal_password;
+                            }
+                            if ui.small_button("📋").on_hover_text("Копировать").clicked()
+                            {
+                                ui.ctx().copy_text(entry.password.clone());
+                                action = Some(DetailAction::Used);
+                            }
+                            if ui
+                                .small_button("🔄")
+                                .on_hover_text("Сменить пароль (прежний сохранится в истории)")
+                                .clicked()
+                            {
+                                action = Some(DetailAction::RotatePassword);
+                            }
+                        });
+                        ui.end_row();
+
+                        if let Some(expires_at) = entry.expires_at() {
+                            ui.label("⏳ Сменить до:");
+                            let date = widgets::format_date(expires_at);
+                            match entry.expiry_status(SystemTime::now()) {
+                                Some(ExpiryStatus::Expired) => ui.colored_label(
+                                    ui.visuals().error_fg_color,
+                                    format!("{} (срок истёк)", date),
+                                ),
+                                Some(ExpiryStatus::Soon) => ui.colored_label(
+                                    ui.visuals().warn_fg_color,
+                                    format!("{} (скоро)", date),
+                                ),
+                                None => ui.label(date),
+                            };
+                            ui.label("");
+                            ui.end_row();
+                        }
+
+                        if let Some(config) = &entry.otp {
+                            ui.label("🔢 2FA:");
+                            match config.code_at(totp::unix_now()) {
+                                Ok(code) => {
+                                    ui.monospace(egui::RichText::new(&code).strong());
+                                    ui.horizontal(|ui| {
+                                        if ui
+                                            .small_button("📋")
+                                            .on_hover_text("Копировать")
+                                            .clicked()
+                                        {
+                                            ui.ctx().copy_text(code);
+                                            action = Some(DetailAction::Used);
+                                        }
+                                        if ui
+                                            .small_button("📱")
+                                            .on_hover_text("Показать QR-код для телефона")
+                                            .clicked()
+                                        {
+                                            action = Some(DetailAction::ShowQr);
+                                        }
+                                        match config.kind {
+                                            OtpKind::Totp => {
+                                                let remaining =
+                                                    config.seconds_remaining(totp::unix_now());
+                                                widgets::countdown_ring(
+                                                    ui,
+                                                    remaining as f32 / config.period as f32,
+                                                )
+                                                .on_hover_text(format!("{} с", remaining));
+                                                ui.ctx().request_repaint_after(
+                                                    std::time::Duration::from_secs(1),
+                                                );
+                                            }
+                                            OtpKind::Hotp => {
+                                                if ui
+                                                    .small_button("⏭")
+                                                    .on_hover_text("Следующий код")
+                                                    .clicked()
+                                                {
+                                                    action = Some(DetailAction::AdvanceHotp);
+                                                }
+                                            }
+                                        }
+                                    });
+                                }
+                                Err(e) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, e);
+                                    ui.label("");
+                                }
+                            }
+                            ui.end_row();
+                        }
+
+                        if !entry.url.is_empty() {
+                            ui.label("🔗 URL:");
+                            ui.add(egui::Label::new(&entry.url).truncate());
+                            ui.horizontal(|ui| {
+                                if ui.small_button("📋").on_hover_text("Копировать").clicked()
+                                {
+                                    ui.ctx().copy_text(entry.url.clone());
+                                }
+                                if ui
+                                    .small_button("🌐")
+                                    .on_hover_text("Открыть в браузере")
+                                    .clicked()
+                                {
+                                    ui.ctx()
+                                        .open_url(egui::OpenUrl::new_tab(browser_url(&entry.url)));
+                                }
+                            });
+                            ui.end_row();
+                        }
+
+                        if !entry.folder.is_empty() {
+                            copy_row(ui, "📁 Папка:", &entry.folder);
+                        }
+                        if !entry.tags.is_empty() {
+                            copy_row(ui, "🏷️ Теги:", &entry.tags.join(", "));
+                        }
+
+                        // Скрытые поля открываются вместе с паролем
+                        for field in &entry.fields {
+                            ui.label(format!("{}:", field.name));
+                            if field.protected && !self.reveal_password {
+                                ui.label("••••••••");
+                            } else {
+                                ui.add(egui::Label::new(&field.value).truncate());
+                            }
+                            if ui.small_button("📋").on_hover_text("Копировать").clicked()
+                            {
+                                ui.ctx().copy_text(field.value.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                if !entry.notes.is_empty() {
+                    ui.add_space(6.0);
+                    ui.horizontal(|ui| {
+                        ui.label("📝 Заметки:");
+                        if ui.small_button("📋").on_hover_text("Копировать").clicked() {
+                            ui.ctx().copy_text(entry.notes.clone());
+                        }
+                    });
+                    ui.add(egui::Label::new(&entry.notes).wrap());
+                }
+
+                // Прежние пароли показываются вместе с текущим
+                if !entry.password_history.is_empty() {
+                    ui.add_space(6.0);
+                    egui::CollapsingHeader::new(format!(
+                        "🕑 Прежние пароли ({})",
+                        entry.password_history.len()
+                    ))
+                    .id_salt("entry_password_history")
+                    .show(ui, |ui| {
+                        for old in entry.password_history.iter().rev() {
+                            ui.horizontal(|ui| {
+                                if self.reveal_password {
+                                    ui.monospace(&old.password);
+                                } else {
+                                    ui.label("••••••••");
+                                }
+                                ui.weak(
+                                    old.replaced_at
+                                        .map(widgets::format_time)
+                                        .unwrap_or_else(|| "—".to_string()),
+                                );
+                                if ui.small_button("📋").on_hover_text("Копировать").clicked()
+                                {
+                                    ui.ctx().copy_text(old.password.clone());
+                                }
+                            });
+                        }
+                    });
+                }
+
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    ui.label(format!("📎 Вложения ({})", entry.attachments.len()));
+                    if ui
+                        .small_button("➕")
+                        .on_hover_text("Добавить файл")
+                        .clicked()
+                    {
+                        action = Some(DetailAction::AddAttachment);
+                    }
+                });
+                for (i, attachment) in entry.attachments.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "{} ({})",
+                            attachment.name,
+                            format_size(attachment.data.len())
+                        ));
+                        if ui
+                            .small_button("💾")
+                            .on_hover_text("Сохранить как…")
+                            .clicked()
+                        {
+                            action = Some(DetailAction::SaveAttachment(i));
+                        }
+                        if ui.small_button("🗑️").on_hover_text("Удалить").clicked() {
+                            action = Some(DetailAction::RemoveAttachment(i));
+                        }
+                    });
+                }
+
+                ui.add_space(6.0);
+                ui.separator();
+                let time_or_dash = |time: Option<SystemTime>| {
+                    time.map(widgets::format_time)
+                        .unwrap_or_else(|| "—".to_string())
+                };
+                ui.weak(format!("Создано: {}", time_or_dash(entry.created_at)));
+                ui.weak(format!("Изменено: {}", time_or_dash(entry.modified_at)));
+                ui.weak(format!(
+                    "Использовано: {} (раз: {})",
+                    time_or_dash(entry.last_used_at),
+                    entry.use_count
+                ));
+            });
+
+        action
+    }
+}
+
+// Строка таблицы: подпись, значение и кнопка копирования
+fn copy_row(ui: &mut egui::Ui, label: &str, value: &str) {
+    ui.label(label);
+    ui.add(egui::Label::new(value).truncate());
+    if ui.small_button("📋").on_hover_text("Копировать").clicked() {
+        ui.ctx().copy_text(value.to_string());
+    }
+    ui.end_row();
+}
+
+// Адрес без схемы браузер не откроет
+fn browser_url(url: &str) -> String {
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} Б", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} КБ", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} МБ", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
@@ -0,0 +1,361 @@
+use crate::csv::{self, ExportFormat, Field};
+use crate::kdbx;
+use crate::storage;
+use crate::strength;
+use crate::ui::widgets;
+use crate::vault::Entry;
+use eframe::egui;
+use std::path::PathBuf;
+use std::time::Duration;
+use zeroize::Zeroize;
+
+// Формат экспорта открытым текстом
+#[derive(Clone, Copy, PartialEq)]
+pub enum PlainFormat {
+    Csv,
+    KeePassXml,
+}
+
+// Окно экспорта записей открытым текстом: CSV или XML KeePass 2.x
+pub struct PlainExport {
+    pub show: bool,
+    target: PlainFormat,
+    format: ExportFormat,
+    fields: Vec<Field>,
+    password: String,
+    acknowledged: bool,
+
+    // Затереть и удалить файл через заданное время
+    wipe: bool,
+    wipe_minutes: u32,
+
+    message: String,
+}
+
+impl PlainExport {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            target: PlainFormat::Csv,
+            format: ExportFormat::Generic,
+            fields: Field::ALL
+                .into_iter()
+                .filter(|field| *field != Field::Skip)
+                .collect(),
+            password: String::new(),
+            acknowledged: false,
+            wipe: true,
+            wipe_minutes: 10,
+            message: String::new(),
+        }
+    }
+
+    pub fn open(&mut self, target: PlainFormat) {
+        self.zeroize();
+        self.target = target;
+        self.show = true;
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        entries: &[Entry],
+        vault_name: &str,
+        master_password: &str,
+    ) {
+        let mut open = self.show;
+        let title = match self.target {
+            PlainFormat::Csv => "📤 Экспорт в CSV",
+            PlainFormat::KeePassXml => "📤 Экспорт в KeePass XML",
+        };
+        egui::Window::new(title)
+            .id(egui::Id::new("plain_export"))
+            .open(&mut open)
+            .resizable(false)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    egui::RichText::new(
+                        "⚠ Пароли будут записаны в файл ОТКРЫТЫМ ТЕКСТОМ.\n\
+                         Любой, у кого окажется этот файл, увидит все пароли.\n\
+                         Удалите его сразу после переноса данных.",
+                    )
+                    .color(ui.visuals().error_fg_color)
+                    .strong()
+                    .size(16.0),
+                );
+                ui.separator();
+
+                if self.target == PlainFormat::Csv {
+                    self.csv_options_ui(ui);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.wipe, "Затереть файл через");
+                    ui.add_enabled(
+                        self.wipe,
+                        egui::DragValue::new(&mut self.wipe_minutes)
+                            .range(1..=1440)
+                            .suffix(" мин."),
+                    );
+                })
+                .response
+                .on_hover_text(
+                    "Файл перезаписывается нулями и удаляется, если приложение ещё открыто. \
+                     На SSD копии данных всё равно могут остаться",
+                );
+                ui.separator();
+
+                ui.label("Мастер-пароль для подтверждения:");
+                ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                ui.checkbox(
+                    &mut self.acknowledged,
+                    "Я понимаю, что файл не будет зашифрован",
+                );
+
+                if !self.message.is_empty() {
+                    ui.label(&self.message);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let ready = self.acknowledged && !self.password.is_empty();
+                    if ui
+                        .add_enabled(ready, egui::Button::new("📤 Экспортировать…"))
+                        .clicked()
+                    {
+                        self.export(entries, vault_name, master_password);
+                    }
+                    if ui.button("❌ Закрыть").clicked() {
+                        self.show = false;
+                    }
+                });
+            });
+        self.show = open && self.show;
+        if !self.show {
+            self.zeroize();
+        }
+    }
+
+    // Формат CSV и набор столбцов
+    fn csv_options_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Формат:");
+            egui::ComboBox::from_id_salt("export_format")
+                .selected_text(self.format.title())
+                .show_ui(ui, |ui| {
+                    for format in ExportFormat::ALL {
+                        ui.selectable_value(&mut self.format, format, format.title());
+                    }
+                });
+        });
+
+        ui.label("Поля:");
+        ui.horizontal_wrapped(|ui| {
+            for field in Field::ALL {
+                if field == Field::Skip || !self.format.supports(field) {
+                    continue;
+                }
+                let mut checked = self.fields.contains(&field);
+                if ui.checkbox(&mut checked, field.title()).changed() {
+                    if checked {
+                        self.fields.push(field);
+                    } else {
+                        self.fields.retain(|f| *f != field);
+                    }
+                }
+            }
+        });
+    }
+
+    fn export(&mut self, entries: &[Entry], vault_name: &str, master_password: &str) {
+        if self.password != master_password {
+            self.message = "❌ Неверный мастер-пароль".to_string();
+            return;
+        }
+        self.password.zeroize();
+
+        let (filter, extension) = match self.target {
+            PlainFormat::Csv => ("CSV", "csv"),
+            PlainFormat::KeePassXml => ("KeePass XML", "xml"),
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(filter, &[extension])
+            .set_file_name(format!("shroombrella-export.{}", extension))
+            .save_file()
+        else {
+            return;
+        };
+
+        let mut contents = match self.target {
+            PlainFormat::Csv => csv::export(entries, self.format, &self.fields),
+            PlainFormat::KeePassXml => kdbx::write_xml(entries, vault_name),
+        };
+        let result = std::fs::write(&path, &contents);
+        contents.zeroize();
+        self.message = match result {
+            Ok(()) => {
+                if self.wipe {
+                    schedule_wipe(
+                        path.clone(),
+                        Duration::from_secs(self.wipe_minutes as u64 * 60),
+                    );
+                    format!(
+                        "✅ Экспортировано записей: {} в {}. Файл будет затёрт через {} мин.",
+                        entries.len(),
+                        path.display(),
+                        self.wipe_minutes
+                    )
+                } else {
+                    format!(
+                        "✅ Экспортировано записей: {} в {}. Не забудьте удалить файл!",
+                        entries.len(),
+                        path.display()
+                    )
+                }
+            }
+            Err(e) => format!("❌ Ошибка записи файла: {}", e),
+        };
+        self.acknowledged = false;
+    }
+}
+
+// Затираем файл в фоне по истечении времени
+fn schedule_wipe(path: PathBuf, delay: Duration) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        if path.exists() {
+            let _ = storage::wipe_file(&path);
+        }
+    });
+}
+
+impl Zeroize for PlainExport {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+        self.acknowledged = false;
+        self.message.clear();
+    }
+}
+
+// Окно экспорта в базу KeePass (.kdbx) под отдельным паролем
+pub struct KdbxExport {
+    pub show: bool,
+    password: String,
+    repeat: String,
+    master_password: String,
+    message: String,
+}
+
+impl KdbxExport {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            password: String::new(),
+            repeat: String::new(),
+            master_password: String::new(),
+            message: String::new(),
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        entries: &[Entry],
+        vault_name: &str,
+        master_password: &str,
+    ) {
+        let mut open = self.show;
+        egui::Window::new("🗄️ Экспорт в KeePass")
+            .open(&mut open)
+            .resizable(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Записи будут сохранены в зашифрованную базу KDBX 4, \
+                     которую открывают KeePassXC и KeePass. Папки станут группами, \
+                     прежние пароли — историей записей.",
+                );
+                ui.weak("Избранное, закрепление и политики паролей в KeePass не переносятся");
+                ui.separator();
+
+                ui.label("Пароль новой базы:");
+                ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                if !self.password.is_empty() {
+                    widgets::strength_meter(ui, &strength::estimate(&self.password, &[]));
+                }
+                ui.label("Повторите пароль:");
+                ui.add(egui::TextEdit::singleline(&mut self.repeat).password(true));
+                let matches = self.password == self.repeat;
+                if !self.repeat.is_empty() && !matches {
+                    ui.colored_label(ui.visuals().error_fg_color, "Пароли не совпадают");
+                }
+
+                ui.label("Мастер-пароль для подтверждения:");
+                ui.add(egui::TextEdit::singleline(&mut self.master_password).password(true));
+
+                if !self.message.is_empty() {
+                    ui.label(&self.message);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let ready =
+                        !self.password.is_empty() && matches && !self.master_password.is_empty();
+                    if ui
+                        .add_enabled(ready, egui::Button::new("💾 Сохранить…"))
+                        .clicked()
+                    {
+                        self.export(entries, vault_name, master_password);
+                    }
+                    if ui.button("❌ Закрыть").clicked() {
+                        self.show = false;
+                    }
+                });
+            });
+        self.show = open && self.show;
+        if !self.show {
+            self.zeroize();
+        }
+    }
+
+    fn export(&mut self, entries: &[Entry], vault_name: &str, master_password: &str) {
+        if self.master_password != master_password {
+            self.message = "❌ Неверный мастер-пароль".to_string();
+            return;
+        }
+        self.master_password.zeroize();
+
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("KeePass", &["kdbx"])
+            .set_file_name(format!("{}.kdbx", vault_name))
+            .save_file()
+        else {
+            return;
+        };
+
+        self.message = match kdbx::write(entries, vault_name, &self.password)
+            .and_then(|data| std::fs::write(&path, data).map_err(|e| e.to_string()))
+        {
+            Ok(()) => {
+                self.password.zeroize();
+                self.repeat.zeroize();
+                format!(
+                    "✅ Экспортировано записей: {} в {}",
+                    entries.len(),
+                    path.display()
+                )
+            }
+            Err(e) => format!("❌ Ошибка экспорта: {}", e),
+        };
+    }
+}
+
+impl Zeroize for KdbxExport {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+        self.repeat.zeroize();
+        self.master_password.zeroize();
+        self.message.clear();
+    }
+}
@@ -0,0 +1,332 @@
+use crate::generator::{self, GeneratorMode, GeneratorOptions, GeneratorProfile};
+use crate::passphrase::{Capitalization, PassphraseOptions};
+use eframe::egui;
+use zeroize::Zeroize;
+
+// Окно генератора паролей с настройками и профилями
+pub struct PasswordGenerator {
+    pub show: bool,
+    pub options: GeneratorOptions,
+    pub profiles: Vec<GeneratorProfile>,
+    profile_name: String,
+    preview: String,
+    error_message: String,
+
+    // Пароль, который пользователь решил использовать
+    result: Option<String>,
+    // Параметры или профили изменились и их нужно сохранить в настройках
+    settings_changed: bool,
+    // Параметры меняются при каждом движении ползунка, поэтому сохраняем их при закрытии окна
+    options_dirty: bool,
+}
+
+impl PasswordGenerator {
+    pub fn new(options: GeneratorOptions, profiles: Vec<GeneratorProfile>) -> Self {
+        Self {
+            show: false,
+            options,
+            profiles,
+            profile_name: String::new(),
+            preview: String::new(),
+            error_message: String::new(),
+            result: None,
+            settings_changed: false,
+            options_dirty: false,
+        }
+    }
+
+    // Пароль по текущим параметрам
+    pub fn generate(&self) -> Result<String, String> {
+        generator::generate(&self.options)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut show = self.show;
+        egui::Window::new("🎲 Генератор паролей")
+            .open(&mut show)
+            .resizable(false)
+            .default_width(380.0)
+            .show(ctx, |ui| {
+                self.ui(ui);
+            });
+        self.show = show && self.show;
+        if !self.show {
+            self.preview.zeroize();
+            if self.options_dirty {
+                self.options_dirty = false;
+                self.settings_changed = true;
+            }
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let before = self.options.clone();
+
+        // Профили
+        ui.horizontal(|ui| {
+            ui.label("📂 Профиль:");
+            egui::ComboBox::from_id_salt("generator_profile")
+                .selected_text("Выбрать…")
+                .show_ui(ui, |ui| {
+                    let mut delete = None;
+                    for (i, profile) in self.profiles.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.selectable_label(false, &profile.name).clicked() {
+                                self.options = profile.options.clone();
+                            }
+                            if ui
+                                .small_button("🗑")
+                                .on_hover_text("Удалить профиль")
+                                .clicked()
+                            {
+                                delete = Some(i);
+                            }
+                        });
+                    }
+                    if let Some(i) = delete {
+                        self.profiles.remove(i);
+                        self.settings_changed = true;
+                    }
+                    if self.profiles.is_empty() {
+                        ui.label("Нет сохранённых профилей");
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.profile_name)
+                    .hint_text("Название профиля")
+                    .desired_width(200.0),
+            );
+            let name = self.profile_name.trim().to_string();
+            if ui
+                .add_enabled(!name.is_empty(), egui::Button::new("💾 Сохранить профиль"))
+                .clicked()
+            {
+                let profile = GeneratorProfile {
+                    name: name.clone(),
+                    options: self.options.clone(),
+                };
+                match self.profiles.iter_mut().find(|p| p.name == name) {
+                    Some(existing) => *existing = profile,
+                    None => self.profiles.push(profile),
+                }
+                self.profile_name.clear();
+                self.settings_changed = true;
+            }
+        });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.options.mode, GeneratorMode::Password, "🔑 Пароль");
+            ui.selectable_value(
+                &mut self.options.mode,
+                GeneratorMode::Passphrase,
+                "📖 Парольная фраза",
+            );
+        });
+
+        match self.options.mode {
+            GeneratorMode::Password => {
+                ui.add(egui::Slider::new(&mut self.options.length, 4..=128).text("Длина"));
+
+                egui::Grid::new("generator_classes")
+                    .num_columns(2)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        let o = &mut self.options;
+                        class_row(ui, "A-Z", &mut o.upper, &mut o.min_upper);
+                        class_row(ui, "a-z", &mut o.lower, &mut o.min_lower);
+                        class_row(ui, "0-9", &mut o.digits, &mut o.min_digits);
+                        class_row(ui, "Символы", &mut o.symbols, &mut o.min_symbols);
+                    });
+
+                ui.horizontal(|ui| {
+                    ui.label("Набор символов:");
+                    ui.add_enabled(
+                        self.options.symbols,
+                        egui::TextEdit::singleline(&mut self.options.symbol_set)
+                            .desired_width(200.0),
+                    );
+                    if ui
+                        .small_button("↺")
+                        .on_hover_text("Набор по умолчанию")
+                        .clicked()
+                    {
+                        self.options.symbol_set = generator::DEFAULT_SYMBOLS.to_string();
+                    }
+                });
+
+                ui.checkbox(
+                    &mut self.options.exclude_look_alikes,
+                    "Исключить похожие символы (0/O, 1/l/I…)",
+                );
+                ui.checkbox(&mut self.options.no_repeat, "Без повторяющихся символов");
+                ui.checkbox(
+                    &mut self.options.no_consecutive,
+                    "Без одинаковых символов подряд",
+                );
+            }
+            GeneratorMode::Passphrase => passphrase_options_ui(ui, &mut self.options.passphrase),
+        }
+
+        ui.separator();
+
+        // Оценка энтропии обновляется при каждом изменении параметров
+        match self.options.validate() {
+            Ok(()) => {
+                let bits = self.options.entropy_bits();
+                let color = if bits < 60.0 {
+                    ui.visuals().error_fg_color
+                } else if bits < 90.0 {
+                    ui.visuals().warn_fg_color
+                } else {
+                    egui::Color32::from_rgb(80, 180, 80)
+                };
+                ui.colored_label(color, format!("Энтропия: ≈{:.0} бит", bits));
+            }
+            Err(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+        }
+
+        if self.options != before {
+            self.options_dirty = true;
+            self.preview.zeroize();
+        }
+        if self.preview.is_empty() {
+            self.regenerate();
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.preview.as_str())
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(280.0),
+            );
+            if ui.button("🔄").on_hover_text("Ещё вариант").clicked() {
+                self.regenerate();
+            }
+        });
+
+        if !self.error_message.is_empty() {
+            ui.colored_label(ui.visuals().error_fg_color, &self.error_message);
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !self.preview.is_empty(),
+                    egui::Button::new("✅ Использовать"),
+                )
+                .clicked()
+            {
+                self.result = Some(std::mem::take(&mut self.preview));
+                self.show = false;
+            }
+            if ui.button("📋 Копировать").clicked() {
+                ui.ctx().copy_text(self.preview.clone());
+            }
+        });
+    }
+
+    fn regenerate(&mut self) {
+        self.preview.zeroize();
+        match self.generate() {
+            Ok(password) => {
+                self.preview = password;
+                self.error_message.clear();
+            }
+            Err(e) => self.error_message = e,
+        }
+    }
+
+    // Забираем выбранный пароль
+    pub fn take_result(&mut self) -> Option<String> {
+        self.result.take()
+    }
+
+    // Забираем изменения для сохранения в настройках
+    pub fn take_settings_changes(&mut self) -> Option<(GeneratorOptions, Vec<GeneratorProfile>)> {
+        if !self.settings_changed {
+            return None;
+        }
+        self.settings_changed = false;
+        Some((self.options.clone(), self.profiles.clone()))
+    }
+}
+
+impl Zeroize for PasswordGenerator {
+    fn zeroize(&mut self) {
+        self.preview.zeroize();
+        self.result.zeroize();
+    }
+}
+
+// Включение набора символов и его минимальное количество
+fn class_row(ui: &mut egui::Ui, label: &str, enabled: &mut bool, min: &mut usize) {
+    ui.checkbox(enabled, label);
+    ui.add_enabled(
+        *enabled,
+        egui::DragValue::new(min).range(0..=32).prefix("мин. "),
+    );
+    ui.end_row();
+}
+
+// Параметры парольной фразы (используются и при создании хранилища)
+pub fn passphrase_options_ui(ui: &mut egui::Ui, options: &mut PassphraseOptions) {
+    ui.add(egui::Slider::new(&mut options.word_count, 3..=16).text("Слов"));
+
+    ui.horizontal(|ui| {
+        ui.label("Разделитель:");
+        ui.add(egui::TextEdit::singleline(&mut options.separator).desired_width(40.0));
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Регистр:");
+        egui::ComboBox::from_id_salt("passphrase_capitalization")
+            .selected_text(options.capitalization.title())
+            .show_ui(ui, |ui| {
+                for capitalization in Capitalization::ALL {
+                    ui.selectable_value(
+                        &mut options.capitalization,
+                        capitalization,
+                        capitalization.title(),
+                    );
+                }
+            });
+    });
+
+    ui.checkbox(&mut options.insert_digit, "Добавить цифру");
+    ui.checkbox(&mut options.insert_symbol, "Добавить символ");
+
+    ui.horizontal(|ui| {
+        ui.label("Словарь:");
+        let name = options
+            .wordlist
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "EFF (встроенный)".to_string());
+        ui.label(name);
+        if ui
+            .small_button("📂")
+            .on_hover_text("Свой словарь: текстовый файл, по слову в строке")
+            .clicked()
+            && let Some(path) = rfd::FileDialog::new()
+                .add_filter("Словарь", &["txt"])
+                .pick_file()
+        {
+            options.wordlist = Some(path);
+        }
+        if options.wordlist.is_some()
+            && ui
+                .small_button("↺")
+                .on_hover_text("Встроенный словарь")
+                .clicked()
+        {
+            options.wordlist = None;
+        }
+    });
+}
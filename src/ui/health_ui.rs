@@ -0,0 +1,348 @@
+use crate::breach::{self, BreachFilter};
+use crate::health::{self, HealthReport, IssueKind};
+use crate::twofactor::Directory;
+use crate::ui::widgets;
+use crate::vault::Entry;
+use eframe::egui;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// Построение фильтра утечек в фоновом потоке
+struct FilterBuild {
+    progress: Arc<AtomicU64>,
+    total: u64,
+    handle: JoinHandle<Result<BreachFilter, String>>,
+}
+
+// Окно «Здоровье хранилища»: сводка проблем со ссылками на записи
+pub struct VaultHealth {
+    pub show: bool,
+    // Пароль считается старым, если не менялся дольше (0 — не проверять)
+    pub max_age_months: u32,
+    // Отчёт и ревизия записей, по которой он посчитан
+    report: Option<(u64, HealthReport)>,
+    message: String,
+    build: Option<FilterBuild>,
+    // Справочник 2FA, загруженный из файла и ещё не переданный менеджеру
+    updated_directory: Option<Directory>,
+}
+
+impl VaultHealth {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            max_age_months: 12,
+            report: None,
+            message: String::new(),
+            build: None,
+            updated_directory: None,
+        }
+    }
+
+    // Отчёт устарел: записи изменились или ещё не анализировались
+    pub fn needs_update(&self, revision: u64) -> bool {
+        !matches!(&self.report, Some((analyzed, _)) if *analyzed == revision)
+    }
+
+    pub fn set_report(&mut self, report: HealthReport, revision: u64) {
+        self.report = Some((revision, report));
+    }
+
+    pub fn invalidate(&mut self) {
+        self.report = None;
+    }
+
+    // Готовый фильтр утечек (или ошибка построения), если фоновое построение завершилось
+    pub fn take_built_filter(
+        &mut self,
+        ctx: &egui::Context,
+    ) -> Option<Result<BreachFilter, String>> {
+        let build = self.build.as_ref()?;
+        if !build.handle.is_finished() {
+            ctx.request_repaint_after(Duration::from_millis(250));
+            return None;
+        }
+        let build = self.build.take()?;
+        Some(
+            build
+                .handle
+                .join()
+                .unwrap_or_else(|_| Err("Построение фильтра прервано".to_string())),
+        )
+    }
+
+    // Выбираем дамп HIBP и запускаем построение фильтра
+    fn start_build(&mut self) {
+        let Some(output) = BreachFilter::default_path() else {
+            self.message = "❌ Не удалось определить путь к фильтру".to_string();
+            return;
+        };
+        let Some(dump) = rfd::FileDialog::new()
+            .add_filter("Дамп HIBP", &["txt"])
+            .pick_file()
+        else {
+            return;
+        };
+        // Дамп читается дважды: подсчёт хешей и кодирование
+        let total = std::fs::metadata(&dump).map(|m| m.len() * 2).unwrap_or(0);
+        let progress = Arc::new(AtomicU64::new(0));
+        let thread_progress = progress.clone();
+        let handle = std::thread::spawn(move || breach::build(&dump, &output, &thread_progress));
+        self.build = Some(FilterBuild {
+            progress,
+            total,
+            handle,
+        });
+        self.message.clear();
+    }
+
+    pub fn take_updated_directory(&mut self) -> Option<Directory> {
+        self.updated_directory.take()
+    }
+
+    // Загружаем снимок справочника 2FA и копируем его в папку настроек
+    fn update_directory(&mut self) {
+        let Some(source) = rfd::FileDialog::new()
+            .add_filter("tfa.json", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        let directory = match Directory::load(&source) {
+            Ok(directory) => directory,
+            Err(e) => {
+                self.message = format!("❌ {}", e);
+                return;
+            }
+        };
+        if let Some(path) = Directory::default_path() {
+            let copied = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::copy(&source, &path));
+            if let Err(e) = copied {
+                self.message = format!("❌ Не удалось сохранить справочник 2FA: {}", e);
+                return;
+            }
+        }
+        self.message = format!("✅ Справочник 2FA обновлён: сервисов {}", directory.len());
+        self.updated_directory = Some(directory);
+    }
+
+    fn two_factor_ui(&mut self, ui: &mut egui::Ui, directory: &Directory) {
+        ui.label("🔢 Справочник сервисов с 2FA (2fa.directory):");
+        ui.horizontal(|ui| {
+            let source = if directory.bundled {
+                "встроенная выборка, составлена вручную"
+            } else {
+                "загружен из файла"
+            };
+            ui.weak(format!("{}, сервисов: {}", source, directory.len()));
+            if ui
+                .button("📂 Обновить из файла…")
+                .on_hover_text("tfa.json из API v3 каталога 2fa.directory")
+                .clicked()
+            {
+                self.update_directory();
+            }
+        });
+    }
+
+    fn breach_filter_ui(&mut self, ui: &mut egui::Ui, filter: Option<&BreachFilter>) {
+        ui.label("🚨 Проверка по базе утечек Have I Been Pwned (офлайн):");
+        match filter {
+            Some(filter) => {
+                ui.weak(format!(
+                    "Фильтр: {} хешей {}, построен {}",
+                    filter.count(),
+                    filter.kind().title(),
+                    widgets::format_time(filter.built_at())
+                ));
+            }
+            None => {
+                ui.weak(
+                    "Фильтр не построен. Скачайте дамп паролей HIBP (SHA-1 или NTLM, \
+                     упорядоченный по хешу) и постройте из него фильтр.",
+                );
+            }
+        }
+
+        match &self.build {
+            Some(build) => {
+                let done = build.progress.load(Ordering::Relaxed);
+                let fraction = if build.total == 0 {
+                    0.0
+                } else {
+                    done as f32 / build.total as f32
+                };
+                ui.add(
+                    egui::ProgressBar::new(fraction)
+                        .show_percentage()
+                        .text(format!("Построение фильтра… {:.0}%", fraction * 100.0)),
+                );
+            }
+            None => {
+                if ui
+                    .button("🔄 Перестроить из дампа…")
+                    .on_hover_text("Дамп читается с диска потоково; пароли никуда не отправляются")
+                    .clicked()
+                {
+                    self.start_build();
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.show = false;
+        self.report = None;
+        self.message.clear();
+    }
+
+    // Показываем окно; возвращает индекс записи, которую нужно открыть
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        entries: &[Entry],
+        filter: Option<&BreachFilter>,
+        directory: &Directory,
+    ) -> Option<usize> {
+        let mut open = self.show;
+        let mut open_entry = None;
+
+        egui::Window::new("🩺 Здоровье хранилища")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                self.breach_filter_ui(ui, filter);
+                ui.separator();
+                self.two_factor_ui(ui, directory);
+                ui.separator();
+
+                let Some((_, report)) = &self.report else {
+                    return;
+                };
+
+                let color = if report.score >= 80 {
+                    egui::Color32::from_rgb(80, 180, 80)
+                } else if report.score >= 50 {
+                    ui.visuals().warn_fg_color
+                } else {
+                    ui.visuals().error_fg_color
+                };
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!("{}/100", report.score))
+                            .heading()
+                            .color(color),
+                    );
+                    ui.label(format!(
+                        "Записей: {}, проблем: {}",
+                        report.entry_count,
+                        report.findings.len()
+                    ));
+                });
+
+                let mut max_age = self.max_age_months;
+                ui.horizontal(|ui| {
+                    ui.label("Старым считать пароль, не менявшийся");
+                    ui.add(
+                        egui::DragValue::new(&mut max_age)
+                            .range(0..=120)
+                            .suffix(" мес."),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("💾 Экспорт в HTML")
+                        .on_hover_text("Пароли в отчёт не попадают")
+                        .clicked()
+                    {
+                        self.message = export(report, entries, "html");
+                    }
+                    if ui
+                        .button("💾 Экспорт в CSV")
+                        .on_hover_text("Пароли в отчёт не попадают")
+                        .clicked()
+                    {
+                        self.message = export(report, entries, "csv");
+                    }
+                });
+                if !self.message.is_empty() {
+                    ui.label(&self.message);
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .max_height(420.0)
+                    .show(ui, |ui| {
+                        for kind in IssueKind::ALL {
+                            let count = report.count(kind);
+                            if count == 0 {
+                                ui.label(format!("✅ {}: нет", kind.title()));
+                                continue;
+                            }
+                            egui::CollapsingHeader::new(format!("⚠ {} ({})", kind.title(), count))
+                                .id_salt(("health", kind))
+                                .show(ui, |ui| {
+                                    egui::Grid::new(("health_grid", kind))
+                                        .striped(true)
+                                        .num_columns(4)
+                                        .show(ui, |ui| {
+                                            for finding in
+                                                report.findings.iter().filter(|f| f.kind == kind)
+                                            {
+                                                let entry = &entries[finding.index];
+                                                ui.label(&entry.service);
+                                                ui.label(&entry.login);
+                                                ui.weak(&finding.details);
+                                                if ui
+                                                    .small_button("➡")
+                                                    .on_hover_text("Открыть запись")
+                                                    .clicked()
+                                                {
+                                                    open_entry = Some(finding.index);
+                                                }
+                                                ui.end_row();
+                                            }
+                                        });
+                                });
+                        }
+                    });
+
+                if max_age != self.max_age_months {
+                    self.max_age_months = max_age;
+                    self.report = None;
+                    ui.ctx().request_repaint();
+                }
+            });
+
+        self.show = open && self.show;
+        open_entry
+    }
+}
+
+// Сохраняем отчёт в файл выбранного формата
+fn export(report: &HealthReport, entries: &[Entry], format: &str) -> String {
+    let Some(path) = rfd::FileDialog::new()
+        .add_filter(format.to_uppercase(), &[format])
+        .set_file_name(format!("vault-health.{}", format))
+        .save_file()
+    else {
+        return String::new();
+    };
+
+    let contents = if format == "csv" {
+        health::to_csv(report, entries)
+    } else {
+        health::to_html(report, entries)
+    };
+    match std::fs::write(&path, contents) {
+        Ok(()) => format!("✅ Отчёт сохранён: {}", path.display()),
+        Err(e) => format!("❌ Ошибка сохранения отчёта: {}", e),
+    }
+}
@@ -0,0 +1,732 @@
+use crate::bitwarden;
+use crate::csv::{self, Encoding, Field};
+use crate::import::{self, Duplicates, Plan};
+use crate::kdbx;
+use crate::onepassword;
+use crate::vault::Entry;
+use eframe::egui;
+use zeroize::Zeroize;
+
+// Сколько строк показывать в предпросмотре
+const PREVIEW_ROWS: usize = 8;
+
+// Сколько символов ячейки показывать в предпросмотре
+const PREVIEW_CELL_CHARS: usize = 32;
+
+// Откуда импортируем
+#[derive(Clone, Copy, PartialEq)]
+enum Source {
+    Csv,
+    Kdbx,
+    KeePassXml,
+    Bitwarden { protected: bool },
+    OnePassword,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Unlock,
+    Preview,
+    Mapping,
+    Summary,
+}
+
+// Мастер импорта. CSV: файл → предпросмотр → колонки → дубликаты → импорт.
+// KeePass: файл → пароль и ключевой файл → дубликаты → импорт.
+// XML KeePass: файл → дубликаты → импорт.
+// Bitwarden: файл → пароль, если экспорт защищён → дубликаты → импорт.
+// 1Password (1PUX): файл → сводка сопоставления и дубликаты → импорт
+pub struct ImportWizard {
+    pub show: bool,
+    source: Source,
+    step: Step,
+    file_name: String,
+
+    // Исходные байты файла: при смене кодировки или разделителя разбираем заново
+    bytes: Vec<u8>,
+    encoding: Encoding,
+    delimiter: char,
+    has_header: bool,
+    rows: Vec<Vec<String>>,
+
+    // Доступ к базе KeePass или экспорту Bitwarden: пароль и ключевой файл (имя и содержимое)
+    password: String,
+    key_file: Option<(String, Vec<u8>)>,
+
+    mapping: Vec<Field>,
+    preset: Option<usize>,
+
+    // Записи из файла, сводка сопоставления, замечания по строкам и план с учётом дубликатов
+    entries: Vec<Entry>,
+    summary: Vec<String>,
+    warnings: Vec<String>,
+    duplicates: Duplicates,
+    plan: Option<Plan>,
+
+    // Подтверждённый план, который забирает менеджер паролей
+    result: Option<Plan>,
+    error_message: String,
+}
+
+impl ImportWizard {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            source: Source::Csv,
+            step: Step::Preview,
+            file_name: String::new(),
+            bytes: Vec::new(),
+            encoding: Encoding::Utf8,
+            delimiter: ',',
+            has_header: true,
+            rows: Vec::new(),
+            password: String::new(),
+            key_file: None,
+            mapping: Vec::new(),
+            preset: None,
+            entries: Vec::new(),
+            summary: Vec::new(),
+            warnings: Vec::new(),
+            duplicates: Duplicates::Skip,
+            plan: None,
+            result: None,
+            error_message: String::new(),
+        }
+    }
+
+    // Выбираем CSV-файл и открываем предпросмотр
+    pub fn open_csv(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv", "txt", "tsv"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.zeroize();
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                self.file_name = file_name(&path);
+                self.encoding = csv::detect_encoding(&bytes);
+                self.bytes = bytes;
+                self.delimiter = csv::detect_delimiter(&csv::decode(&self.bytes, self.encoding));
+                self.has_header = true;
+                self.source = Source::Csv;
+                self.reparse();
+                self.step = Step::Preview;
+                self.show = true;
+            }
+            Err(e) => {
+                self.error_message = format!("❌ Не удалось прочитать файл: {}", e);
+                self.show = true;
+            }
+        }
+    }
+
+    // Выбираем базу KeePass и спрашиваем пароль
+    pub fn open_kdbx(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("KeePass", &["kdbx"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.zeroize();
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                self.file_name = file_name(&path);
+                self.bytes = bytes;
+                self.source = Source::Kdbx;
+                self.step = Step::Unlock;
+                self.show = true;
+            }
+            Err(e) => {
+                self.error_message = format!("❌ Не удалось прочитать файл: {}", e);
+                self.show = true;
+            }
+        }
+    }
+
+    // Незашифрованный XML KeePass 2.x разбираем сразу
+    pub fn open_keepass_xml(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("KeePass XML", &["xml"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.zeroize();
+        self.show = true;
+        self.source = Source::KeePassXml;
+        self.step = Step::Summary;
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.error_message = format!("❌ Не удалось прочитать файл: {}", e);
+                return;
+            }
+        };
+        self.file_name = file_name(&path);
+        let result = std::str::from_utf8(&bytes)
+            .map_err(|_| "Файл не в кодировке UTF-8".to_string())
+            .and_then(kdbx::read_xml);
+        match result {
+            Ok((entries, warnings)) => {
+                self.bytes = bytes;
+                self.entries = entries;
+                self.warnings = warnings;
+            }
+            Err(e) => {
+                let mut bytes = bytes;
+                bytes.zeroize();
+                self.error_message = format!("❌ {}", e);
+            }
+        }
+    }
+
+    // Экспорт Bitwarden: защищённый паролем открываем после ввода пароля
+    pub fn open_bitwarden(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Bitwarden JSON", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.zeroize();
+        self.show = true;
+        self.source = Source::Bitwarden { protected: false };
+        self.step = Step::Summary;
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.error_message = format!("❌ Не удалось прочитать файл: {}", e);
+                return;
+            }
+        };
+        self.file_name = file_name(&path);
+        let Ok(text) = std::str::from_utf8(&bytes) else {
+            self.error_message = "❌ Файл не в кодировке UTF-8".to_string();
+            return;
+        };
+        let result = bitwarden::is_password_protected(text).and_then(|protected| {
+            if protected {
+                Ok(None)
+            } else {
+                bitwarden::read(text, "").map(Some)
+            }
+        });
+        match result {
+            Ok(Some((entries, warnings))) => {
+                self.bytes = bytes;
+                self.entries = entries;
+                self.warnings = warnings;
+            }
+            Ok(None) => {
+                self.bytes = bytes;
+                self.source = Source::Bitwarden { protected: true };
+                self.step = Step::Unlock;
+            }
+            Err(e) => {
+                let mut bytes = bytes;
+                bytes.zeroize();
+                self.error_message = format!("❌ {}", e);
+            }
+        }
+    }
+
+    // Архив 1PUX разбираем сразу: он не зашифрован
+    pub fn open_1pux(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("1Password", &["1pux"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.zeroize();
+        self.show = true;
+        self.source = Source::OnePassword;
+        self.step = Step::Summary;
+        let mut bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.error_message = format!("❌ Не удалось прочитать файл: {}", e);
+                return;
+            }
+        };
+        self.file_name = file_name(&path);
+        match onepassword::read(&bytes) {
+            Ok(import) => {
+                self.bytes = bytes;
+                self.entries = import.entries;
+                self.summary = import.summary;
+                self.warnings = import.warnings;
+            }
+            Err(e) => {
+                bytes.zeroize();
+                self.error_message = format!("❌ {}", e);
+            }
+        }
+    }
+
+    fn reparse(&mut self) {
+        let mut text = csv::decode(&self.bytes, self.encoding);
+        self.rows.zeroize();
+        self.rows = csv::parse(&text, self.delimiter);
+        text.zeroize();
+        self.remap();
+    }
+
+    // Раскладка колонок по заголовку (или пустая, если заголовка нет)
+    fn remap(&mut self) {
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        match self.rows.first().filter(|_| self.has_header) {
+            Some(header) => {
+                self.preset = csv::detect_preset(header);
+                self.mapping = csv::guess_mapping(header);
+            }
+            None => {
+                self.preset = None;
+                self.mapping.clear();
+            }
+        }
+        self.mapping.resize(columns, Field::Skip);
+    }
+
+    fn data_rows(&self) -> &[Vec<String>] {
+        if self.has_header && !self.rows.is_empty() {
+            &self.rows[1..]
+        } else {
+            &self.rows
+        }
+    }
+
+    fn column_title(&self, column: usize) -> String {
+        self.rows
+            .first()
+            .filter(|_| self.has_header)
+            .and_then(|header| header.get(column))
+            .filter(|name| !name.trim().is_empty())
+            .cloned()
+            .unwrap_or_else(|| format!("Колонка {}", column + 1))
+    }
+
+    // Значение для предпросмотра: пароли скрыты, длинный текст обрезан
+    fn preview_cell(&self, column: usize, value: &str) -> String {
+        if self.mapping.get(column) == Some(&Field::Password) && !value.is_empty() {
+            return "••••••••".to_string();
+        }
+        let first_line = value.lines().next().unwrap_or_default();
+        let mut text: String = first_line.chars().take(PREVIEW_CELL_CHARS).collect();
+        if text.len() < value.len() {
+            text.push('…');
+        }
+        text
+    }
+
+    // Записи из файла и план импорта
+    fn build_plan(&mut self, existing: &[Entry]) {
+        let first_line = if self.has_header { 2 } else { 1 };
+        self.entries.zeroize();
+        let (entries, warnings) = csv::to_entries(self.data_rows(), &self.mapping, first_line);
+        self.entries = entries;
+        self.warnings = warnings;
+        self.update_plan(existing);
+    }
+
+    fn update_plan(&mut self, existing: &[Entry]) {
+        if let Some(plan) = self.plan.as_mut() {
+            plan.zeroize();
+        }
+        self.plan = Some(import::plan(existing, &self.entries, self.duplicates));
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, existing: &[Entry]) {
+        let mut open = self.show;
+        let (title, steps): (&str, &[(Step, &str)]) = match self.source {
+            Source::Csv => (
+                "📥 Импорт из CSV",
+                &[
+                    (Step::Preview, "1. Файл"),
+                    (Step::Mapping, "2. Колонки"),
+                    (Step::Summary, "3. Итог"),
+                ],
+            ),
+            Source::Kdbx => (
+                "📥 Импорт из KeePass",
+                &[(Step::Unlock, "1. Доступ"), (Step::Summary, "2. Итог")],
+            ),
+            Source::KeePassXml => ("📥 Импорт из KeePass XML", &[(Step::Summary, "Итог")]),
+            Source::Bitwarden { protected: false } => {
+                ("📥 Импорт из Bitwarden", &[(Step::Summary, "Итог")])
+            }
+            Source::Bitwarden { protected: true } => (
+                "📥 Импорт из Bitwarden",
+                &[(Step::Unlock, "1. Пароль"), (Step::Summary, "2. Итог")],
+            ),
+            Source::OnePassword => ("📥 Импорт из 1Password", &[(Step::Summary, "Итог")]),
+        };
+        egui::Window::new(title)
+            .id(egui::Id::new("import_wizard"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                if !self.error_message.is_empty() {
+                    ui.colored_label(ui.visuals().error_fg_color, &self.error_message);
+                    ui.separator();
+                }
+                if self.bytes.is_empty() {
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    for &(step, title) in steps {
+                        if self.step == step {
+                            ui.strong(title);
+                        } else {
+                            ui.weak(title);
+                        }
+                    }
+                });
+                ui.separator();
+
+                match self.step {
+                    Step::Unlock => self.unlock_ui(ui, existing),
+                    Step::Preview => self.preview_ui(ui),
+                    Step::Mapping => self.mapping_ui(ui, existing),
+                    Step::Summary => self.summary_ui(ui, existing),
+                }
+            });
+        self.show = open && self.show;
+        if !self.show {
+            self.zeroize();
+        }
+    }
+
+    fn preview_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("📄 {}", self.file_name));
+        let mut reparse = false;
+        ui.horizontal(|ui| {
+            ui.label("Кодировка:");
+            egui::ComboBox::from_id_salt("import_encoding")
+                .selected_text(self.encoding.title())
+                .show_ui(ui, |ui| {
+                    for encoding in Encoding::ALL {
+                        reparse |= ui
+                            .selectable_value(&mut self.encoding, encoding, encoding.title())
+                            .changed();
+                    }
+                });
+            ui.label("Разделитель:");
+            egui::ComboBox::from_id_salt("import_delimiter")
+                .selected_text(csv::delimiter_title(self.delimiter))
+                .show_ui(ui, |ui| {
+                    for delimiter in csv::DELIMITERS {
+                        reparse |= ui
+                            .selectable_value(
+                                &mut self.delimiter,
+                                delimiter,
+                                csv::delimiter_title(delimiter),
+                            )
+                            .changed();
+                    }
+                });
+        });
+        if ui
+            .checkbox(&mut self.has_header, "Первая строка — заголовок")
+            .changed()
+        {
+            self.remap();
+        }
+        if reparse {
+            self.reparse();
+        }
+
+        ui.label(format!("Записей в файле: {}", self.data_rows().len()));
+        egui::ScrollArea::both()
+            .max_height(260.0)
+            .id_salt("import_preview")
+            .show(ui, |ui| {
+                egui::Grid::new("import_preview_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for column in 0..self.mapping.len() {
+                            ui.strong(self.column_title(column));
+                        }
+                        ui.end_row();
+                        for row in self.data_rows().iter().take(PREVIEW_ROWS) {
+                            for (column, value) in row.iter().enumerate() {
+                                ui.label(self.preview_cell(column, value));
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        ui.separator();
+        if ui
+            .add_enabled(!self.data_rows().is_empty(), egui::Button::new("➡ Далее"))
+            .clicked()
+        {
+            self.step = Step::Mapping;
+        }
+    }
+
+    fn unlock_ui(&mut self, ui: &mut egui::Ui, existing: &[Entry]) {
+        ui.label(format!("🗄️ {}", self.file_name));
+        ui.label(match self.source {
+            Source::Bitwarden { .. } => "Пароль, заданный при экспорте:",
+            _ => "Пароль базы:",
+        });
+        let response = ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+        // Ключевой файл бывает только у KeePass
+        if self.source == Source::Kdbx {
+            ui.horizontal(|ui| {
+                ui.label("Ключевой файл:");
+                match &self.key_file {
+                    Some((name, _)) => {
+                        ui.label(name);
+                        if ui.small_button("✖").on_hover_text("Убрать").clicked() {
+                            self.key_file.zeroize();
+                        }
+                    }
+                    None => {
+                        ui.weak("нет");
+                    }
+                }
+                if ui.button("📂 Выбрать…").clicked()
+                    && let Some(path) = rfd::FileDialog::new().pick_file()
+                {
+                    match std::fs::read(&path) {
+                        Ok(data) => self.key_file = Some((file_name(&path), data)),
+                        Err(e) => {
+                            self.error_message =
+                                format!("❌ Не удалось прочитать ключевой файл: {}", e)
+                        }
+                    }
+                }
+            });
+        }
+
+        ui.separator();
+        let ready = !self.password.is_empty() || self.key_file.is_some();
+        let enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui
+            .add_enabled(ready, egui::Button::new("🔓 Открыть"))
+            .clicked()
+            || (ready && enter)
+        {
+            self.unlock(existing);
+        }
+    }
+
+    fn unlock(&mut self, existing: &[Entry]) {
+        let result = match self.source {
+            Source::Bitwarden { .. } => std::str::from_utf8(&self.bytes)
+                .map_err(|_| "Файл не в кодировке UTF-8".to_string())
+                .and_then(|text| bitwarden::read(text, &self.password)),
+            _ => {
+                let key_file = self.key_file.as_ref().map(|(_, data)| data.as_slice());
+                kdbx::read(&self.bytes, &self.password, key_file)
+            }
+        };
+        match result {
+            Ok((entries, warnings)) => {
+                self.password.zeroize();
+                self.entries.zeroize();
+                self.entries = entries;
+                self.warnings = warnings;
+                self.error_message.clear();
+                self.update_plan(existing);
+                self.step = Step::Summary;
+            }
+            Err(e) => self.error_message = format!("❌ {}", e),
+        }
+    }
+
+    fn mapping_ui(&mut self, ui: &mut egui::Ui, existing: &[Entry]) {
+        ui.horizontal(|ui| {
+            ui.label("Формат:");
+            let selected = self
+                .preset
+                .map_or("Свой", |preset| csv::PRESETS[preset].name);
+            let mut preset = self.preset;
+            egui::ComboBox::from_id_salt("import_preset")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (i, known) in csv::PRESETS.iter().enumerate() {
+                        ui.selectable_value(&mut preset, Some(i), known.name);
+                    }
+                });
+            if preset != self.preset
+                && let Some(preset) = preset
+                && let Some(header) = self.rows.first().filter(|_| self.has_header)
+            {
+                let columns = self.mapping.len();
+                self.mapping = csv::preset_mapping(preset, header);
+                self.mapping.resize(columns, Field::Skip);
+                self.preset = Some(preset);
+            }
+        });
+
+        let sample = self.data_rows().first().cloned().unwrap_or_default();
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .id_salt("import_mapping")
+            .show(ui, |ui| {
+                egui::Grid::new("import_mapping_grid")
+                    .striped(true)
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        ui.strong("Колонка");
+                        ui.strong("Пример");
+                        ui.strong("Поле записи");
+                        ui.end_row();
+                        for column in 0..self.mapping.len() {
+                            ui.label(self.column_title(column));
+                            let value = sample.get(column).map(String::as_str).unwrap_or("");
+                            ui.weak(self.preview_cell(column, value));
+                            let field = &mut self.mapping[column];
+                            egui::ComboBox::from_id_salt(("import_field", column))
+                                .selected_text(field.title())
+                                .show_ui(ui, |ui| {
+                                    for option in Field::ALL {
+                                        ui.selectable_value(field, option, option.title());
+                                    }
+                                });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        let mapped = self.mapping.iter().any(|field| *field != Field::Skip);
+        if !mapped {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "Сопоставьте хотя бы одну колонку с полем записи",
+            );
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("⬅ Назад").clicked() {
+                self.step = Step::Preview;
+            }
+            if ui
+                .add_enabled(mapped, egui::Button::new("➡ Далее"))
+                .clicked()
+            {
+                self.build_plan(existing);
+                self.step = Step::Summary;
+            }
+        });
+    }
+
+    fn summary_ui(&mut self, ui: &mut egui::Ui, existing: &[Entry]) {
+        if self.plan.is_none() {
+            self.update_plan(existing);
+        }
+        ui.label("Если такая учётная запись (сервис и логин) уже есть:");
+        let mut changed = false;
+        for option in Duplicates::ALL {
+            changed |= ui
+                .radio_value(&mut self.duplicates, option, option.title())
+                .changed();
+        }
+        if changed {
+            self.update_plan(existing);
+        }
+        ui.separator();
+
+        let Some(plan) = &self.plan else {
+            return;
+        };
+        ui.label(format!("➕ Будет добавлено: {}", plan.added.len()));
+        ui.label(format!("🔀 Будет объединено: {}", plan.merged.len()));
+        match self.source {
+            Source::Csv => ui.label(format!(
+                "⏭ Будет пропущено: {} (дубликаты), {} (пустые строки)",
+                plan.skipped,
+                self.data_rows().len() - self.entries.len()
+            )),
+            Source::Kdbx | Source::KeePassXml | Source::Bitwarden { .. } | Source::OnePassword => {
+                ui.label(format!("⏭ Будет пропущено: {} (дубликаты)", plan.skipped))
+            }
+        };
+        if !self.summary.is_empty() {
+            egui::CollapsingHeader::new("🗺 Сопоставление")
+                .id_salt("import_summary")
+                .default_open(true)
+                .show(ui, |ui| {
+                    for line in &self.summary {
+                        ui.label(line);
+                    }
+                });
+        }
+        if !self.warnings.is_empty() {
+            egui::CollapsingHeader::new(format!("⚠ Замечания ({})", self.warnings.len()))
+                .id_salt("import_warnings")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            for warning in &self.warnings {
+                                ui.weak(warning);
+                            }
+                        });
+                });
+        }
+        let nothing_to_do = plan.added.is_empty() && plan.merged.is_empty();
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            let back = match self.source {
+                Source::Csv => Some(Step::Mapping),
+                Source::Kdbx | Source::Bitwarden { protected: true } => Some(Step::Unlock),
+                Source::KeePassXml
+                | Source::Bitwarden { protected: false }
+                | Source::OnePassword => None,
+            };
+            if let Some(back) = back
+                && ui.button("⬅ Назад").clicked()
+            {
+                self.step = back;
+            }
+            if ui
+                .add_enabled(!nothing_to_do, egui::Button::new("✅ Импортировать"))
+                .clicked()
+            {
+                // Записи могли измениться, пока окно было открыто
+                self.update_plan(existing);
+                self.result = self.plan.take();
+                self.show = false;
+            }
+        });
+    }
+
+    // Забираем подтверждённый план импорта
+    pub fn take_result(&mut self) -> Option<Plan> {
+        self.result.take()
+    }
+}
+
+impl Zeroize for ImportWizard {
+    fn zeroize(&mut self) {
+        self.bytes.zeroize();
+        self.rows.zeroize();
+        self.password.zeroize();
+        self.key_file.zeroize();
+        self.mapping.clear();
+        self.entries.zeroize();
+        self.summary.clear();
+        self.warnings.clear();
+        if let Some(plan) = self.plan.as_mut() {
+            plan.zeroize();
+        }
+        self.plan = None;
+        self.error_message.clear();
+    }
+}
+
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,274 @@
+use crate::passphrase::{self, PassphraseOptions};
+use crate::strength::{self, Estimate, MasterPasswordPolicy};
+use crate::ui::{generator_ui, widgets};
+use eframe::egui;
+use zeroize::Zeroize;
+
+// Ввод нового мастер-пароля: подтверждение, оценка надёжности, требования
+// из настроек и подбор парольной фразы. Используется при создании хранилища
+// и при смене мастер-пароля
+pub struct MasterPasswordInput {
+    pub password: String,
+    pub confirm: String,
+    pub policy: MasterPasswordPolicy,
+
+    // Слабый, но допустимый пароль нужно подтвердить отдельно
+    weak_warning: bool,
+    weak_confirmed: bool,
+
+    passphrase_options: PassphraseOptions,
+    suggested_passphrase: String,
+}
+
+impl MasterPasswordInput {
+    pub fn new() -> Self {
+        Self {
+            password: String::new(),
+            confirm: String::new(),
+            policy: MasterPasswordPolicy::default(),
+            weak_warning: false,
+            weak_confirmed: false,
+            passphrase_options: PassphraseOptions::default(),
+            suggested_passphrase: String::new(),
+        }
+    }
+
+    fn estimate(&self) -> Estimate {
+        strength::estimate(&self.password, &[])
+    }
+
+    // Поля ввода; возвращает true, если пользователь подтвердил слабый пароль
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+
+        ui.label("Мастер-пароль:");
+        ui.horizontal(|ui| {
+            ui.label("🔑");
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut self.password).password(true))
+                .changed();
+        });
+
+        if !self.password.is_empty() {
+            let estimate = self.estimate();
+            widgets::strength_meter(ui, &estimate);
+            if let Err(e) = self.policy.check(&estimate) {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+        }
+
+        ui.label("Подтвердите пароль:");
+        ui.horizontal(|ui| {
+            ui.label("🔑");
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut self.confirm).password(true))
+                .changed();
+        });
+
+        egui::CollapsingHeader::new("🎲 Подобрать парольную фразу")
+            .id_salt("master_password_passphrase")
+            .show(ui, |ui| changed |= self.passphrase_ui(ui));
+
+        // Любое изменение пароля отменяет подтверждение
+        if changed {
+            self.weak_warning = false;
+            self.weak_confirmed = false;
+        }
+
+        let mut confirmed = false;
+        if self.weak_warning {
+            ui.separator();
+            let estimate = self.estimate();
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!(
+                    "⚠ Пароль допустим, но слабый: его можно подобрать за {}. \
+                     От мастер-пароля зависит безопасность всех записей.",
+                    estimate.crack_time()
+                ),
+            );
+            ui.horizontal(|ui| {
+                if ui.button("⚠ Всё равно использовать").clicked() {
+                    self.weak_confirmed = true;
+                    self.weak_warning = false;
+                    confirmed = true;
+                }
+                if ui.button("✏️ Изменить пароль").clicked() {
+                    self.weak_warning = false;
+                }
+            });
+        }
+        confirmed
+    }
+
+    // Генератор парольной фразы; возвращает true, если фраза подставлена в поля
+    fn passphrase_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        generator_ui::passphrase_options_ui(ui, &mut self.passphrase_options);
+
+        match self.passphrase_options.validate() {
+            Ok(()) => {
+                ui.label(format!(
+                    "Энтропия: ≈{:.0} бит",
+                    self.passphrase_options.entropy_bits()
+                ));
+            }
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+        }
+
+        let mut used = false;
+        ui.horizontal(|ui| {
+            if ui.button("🔄 Сгенерировать").clicked() {
+                self.suggested_passphrase.zeroize();
+                if let Ok(phrase) = passphrase::generate(&self.passphrase_options) {
+                    self.suggested_passphrase = phrase;
+                }
+            }
+            if ui
+                .add_enabled(
+                    !self.suggested_passphrase.is_empty(),
+                    egui::Button::new("✅ Использовать"),
+                )
+                .clicked()
+            {
+                self.password.zeroize();
+                self.confirm.zeroize();
+                self.password = self.suggested_passphrase.clone();
+                self.confirm = std::mem::take(&mut self.suggested_passphrase);
+                used = true;
+            }
+        });
+
+        // Фразу показываем открыто, чтобы её можно было запомнить или записать
+        if !self.suggested_passphrase.is_empty() {
+            ui.monospace(&self.suggested_passphrase);
+        }
+        used
+    }
+
+    // Проверяем пароль: Ok(true) — можно использовать, Ok(false) — слабый пароль
+    // ждёт подтверждения (предупреждение показывается в ui)
+    pub fn validate(&mut self) -> Result<bool, String> {
+        if self.password.is_empty() {
+            return Err("Введите мастер-пароль".to_string());
+        }
+        if self.password != self.confirm {
+            return Err("Пароли не совпадают".to_string());
+        }
+
+        let estimate = self.estimate();
+        self.policy.check(&estimate)?;
+        if estimate.is_weak() && !self.weak_confirmed {
+            self.weak_warning = true;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+impl Zeroize for MasterPasswordInput {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+        self.confirm.zeroize();
+        self.suggested_passphrase.zeroize();
+        self.weak_warning = false;
+        self.weak_confirmed = false;
+    }
+}
+
+// Окно смены мастер-пароля
+pub struct ChangeMasterPassword {
+    pub show: bool,
+    current: String,
+    pub input: MasterPasswordInput,
+    error_message: String,
+
+    // Новый пароль, прошедший все проверки
+    result: Option<String>,
+}
+
+impl ChangeMasterPassword {
+    pub fn new() -> Self {
+        Self {
+            show: false,
+            current: String::new(),
+            input: MasterPasswordInput::new(),
+            error_message: String::new(),
+            result: None,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, master_password: &str) {
+        let mut show = self.show;
+        egui::Window::new("🔐 Смена мастер-пароля")
+            .open(&mut show)
+            .resizable(false)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                ui.label("Текущий мастер-пароль:");
+                ui.horizontal(|ui| {
+                    ui.label("🔑");
+                    ui.add(egui::TextEdit::singleline(&mut self.current).password(true));
+                });
+                ui.separator();
+
+                let mut submit = self.input.ui(ui);
+
+                if !self.error_message.is_empty() {
+                    ui.colored_label(ui.visuals().error_fg_color, &self.error_message);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("✅ Сменить").clicked() {
+                        submit = true;
+                    }
+                    if ui.button("❌ Отмена").clicked() {
+                        self.show = false;
+                    }
+                });
+
+                if submit {
+                    self.submit(master_password);
+                }
+            });
+        self.show = show && self.show;
+        if !self.show {
+            self.zeroize();
+        }
+    }
+
+    fn submit(&mut self, master_password: &str) {
+        self.error_message.clear();
+        if self.current != master_password {
+            self.error_message = "Текущий мастер-пароль введён неверно".to_string();
+            return;
+        }
+        if self.input.password == master_password {
+            self.error_message = "Новый пароль совпадает с текущим".to_string();
+            return;
+        }
+        match self.input.validate() {
+            Ok(true) => {
+                self.result = Some(std::mem::take(&mut self.input.password));
+                self.show = false;
+            }
+            Ok(false) => {}
+            Err(e) => self.error_message = e,
+        }
+    }
+
+    // Забираем новый мастер-пароль
+    pub fn take_result(&mut self) -> Option<String> {
+        self.result.take()
+    }
+}
+
+impl Zeroize for ChangeMasterPassword {
+    fn zeroize(&mut self) {
+        self.current.zeroize();
+        self.input.zeroize();
+        self.error_message.clear();
+    }
+}
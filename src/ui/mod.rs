@@ -1,4 +1,11 @@
+pub mod entry_details_ui;
+pub mod export_ui;
+pub mod generator_ui;
+pub mod health_ui;
+pub mod import_ui;
+pub mod master_password_ui;
 pub mod password_manager_ui;
 pub mod settings_ui;
 pub mod theme_creator_ui;
 pub mod vault_creator_ui;
+pub mod widgets;
@@ -1,29 +1,302 @@
+use crate::breach::BreachFilter;
+use crate::health;
+use crate::history::{History, Transaction};
+use crate::import::Plan;
+use crate::policy::{PasswordPolicy, SitePolicy};
+use crate::qr;
+use crate::search::{self, EntryFlags, EntryMatch, SearchQuery};
 use crate::storage;
-use crate::vault::{Entry, Vault};
+use crate::strength::{self, Estimate};
+use crate::totp::{self, OtpConfig, OtpKind};
+use crate::twofactor::Directory;
+use crate::ui::entry_details_ui::{DetailAction, EntryDetails};
+use crate::ui::export_ui::{KdbxExport, PlainExport, PlainFormat};
+use crate::ui::generator_ui::PasswordGenerator;
+use crate::ui::health_ui::VaultHealth;
+use crate::ui::import_ui::ImportWizard;
+use crate::ui::master_password_ui::ChangeMasterPassword;
+use crate::ui::widgets;
+use crate::vault::{
+    self, Attachment, Column, CustomField, Entry, Expiry, ExpiryStatus, TableView, TrashedEntry,
+    Vault, VaultData, normalize_folder, parse_tags,
+};
 use eframe::egui;
+use egui_extras::{Column as TableColumn, TableBuilder};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{BuildHasher, RandomState};
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 use zeroize::Zeroize;
 
 // Структура для редактирования записи
 #[derive(Clone)]
 pub struct EditEntry {
     pub index: usize,
+    pub revision: u64, // состояние истории, для которого проверен индекс
+    origin: RecordKey, // редактируемая запись до правки
     pub service: String,
     pub login: String,
     pub password: String,
+    pub otp: String,
+    pub url: String,
+    pub notes: String,
+    pub tags: String, // через запятую
+    pub folder: String,
+    pub policy: String, // правила в синтаксисе passwordrules
+    pub expiry_kind: ExpiryKind,
+    pub expiry_date: String, // ГГГГ-ММ-ДД
+    pub expiry_days: u32,
+    pub fields: Vec<CustomField>,
 }
 
+// Вид срока действия в диалоге редактирования
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExpiryKind {
+    None,
+    Date,
+    Interval,
+}
+
+// Признаки, по которым редактируемая запись узнаётся после того, как отмена
+// или корзина сдвинули индексы. Правка самой записи меняет modified_at
+#[derive(Clone, PartialEq)]
+struct RecordKey {
+    service: String,
+    login: String,
+    created_at: Option<SystemTime>,
+    modified_at: Option<SystemTime>,
+}
+
+impl RecordKey {
+    fn of(entry: &Entry) -> Self {
+        Self {
+            service: entry.service.clone(),
+            login: entry.login.clone(),
+            created_at: entry.created_at,
+            modified_at: entry.modified_at,
+        }
+    }
+}
+
+// Интервал смены пароля по умолчанию
+const DEFAULT_ROTATION_DAYS: u32 = 90;
+
 impl zeroize::Zeroize for EditEntry {
     fn zeroize(&mut self) {
         self.service.zeroize();
         self.login.zeroize();
         self.password.zeroize();
+        self.otp.zeroize();
+        self.url.zeroize();
+        self.notes.zeroize();
+        self.tags.zeroize();
+        self.folder.zeroize();
+        self.fields.zeroize();
+        self.origin.service.zeroize();
+        self.origin.login.zeroize();
+    }
+}
+
+impl EditEntry {
+    fn from_entry(index: usize, revision: u64, entry: &Entry) -> Self {
+        Self {
+            index,
+            revision,
+            origin: RecordKey::of(entry),
+            service: entry.service.clone(),
+            login: entry.login.clone(),
+            password: entry.password.clone(),
+            otp: entry
+                .otp
+                .as_ref()
+                .map(|config| config.to_uri())
+                .unwrap_or_default(),
+            url: entry.url.clone(),
+            notes: entry.notes.clone(),
+            tags: entry.tags.join(", "),
+            folder: entry.folder.clone(),
+            policy: entry
+                .policy
+                .as_ref()
+                .map(PasswordPolicy::to_rules)
+                .unwrap_or_default(),
+            expiry_kind: match entry.expiry {
+                None => ExpiryKind::None,
+                Some(Expiry::Date(_)) => ExpiryKind::Date,
+                Some(Expiry::Interval(_)) => ExpiryKind::Interval,
+            },
+            expiry_date: match entry.expiry {
+                Some(Expiry::Date(date)) => widgets::format_date(date),
+                _ => String::new(),
+            },
+            expiry_days: match entry.expiry {
+                Some(Expiry::Interval(days)) => days,
+                _ => DEFAULT_ROTATION_DAYS,
+            },
+            fields: entry.fields.clone(),
+        }
+    }
+
+    fn expiry(&self) -> Result<Option<Expiry>, String> {
+        match self.expiry_kind {
+            ExpiryKind::None => Ok(None),
+            ExpiryKind::Date => widgets::parse_date(&self.expiry_date)
+                .map(|date| Some(Expiry::Date(date)))
+                .ok_or_else(|| "Срок действия: дата в формате ГГГГ-ММ-ДД".to_string()),
+            ExpiryKind::Interval => Ok(Some(Expiry::Interval(self.expiry_days.max(1)))),
+        }
+    }
+
+    // Переносим отредактированные поля в запись
+    fn apply_to(&self, entry: &mut Entry) -> Result<(), String> {
+        entry.otp = parse_otp_field(&self.otp).map_err(|e| format!("2FA: {}", e))?;
+        entry.policy = parse_policy_field(&self.policy).map_err(|e| format!("Политика: {}", e))?;
+        // Срок задаём до смены пароля: новый пароль может закрыть наступивший срок
+        entry.expiry = self.expiry()?;
+        entry.service = self.service.clone();
+        entry.login = self.login.clone();
+        entry.set_password(self.password.clone());
+        entry.url = self.url.trim().to_string();
+        entry.notes = self.notes.clone();
+        entry.tags = parse_tags(&self.tags);
+        entry.folder = normalize_folder(&self.folder);
+        entry.fields = self
+            .fields
+            .iter()
+            .filter(|field| !field.name.trim().is_empty())
+            .cloned()
+            .collect();
+        entry.modified_at = Some(SystemTime::now());
+        Ok(())
+    }
+}
+
+// Пустое поле политики — политика не задана
+fn parse_policy_field(input: &str) -> Result<Option<PasswordPolicy>, String> {
+    if input.trim().is_empty() {
+        Ok(None)
+    } else {
+        PasswordPolicy::parse(input).map(Some)
+    }
+}
+
+// Оценка надёжности пароля записи; сервис и логин в пароле считаются легко угадываемыми
+fn estimate_strength(entry: &Entry) -> Estimate {
+    strength::estimate(&entry.password, &[&entry.service, &entry.login])
+}
+
+// Значок перед названием сервиса, если пароль пора сменить
+fn expiry_badge(ui: &mut egui::Ui, entry: &Entry) {
+    let Some(expires_at) = entry.expires_at() else {
+        return;
+    };
+    match entry.expiry_status(SystemTime::now()) {
+        Some(ExpiryStatus::Expired) => {
+            ui.colored_label(ui.visuals().error_fg_color, "⌛")
+                .on_hover_text(format!(
+                    "Срок пароля истёк {} — смените его",
+                    widgets::format_date(expires_at)
+                ));
+        }
+        Some(ExpiryStatus::Soon) => {
+            ui.colored_label(ui.visuals().warn_fg_color, "⏰")
+                .on_hover_text(format!(
+                    "Срок пароля истекает {}",
+                    widgets::format_date(expires_at)
+                ));
+        }
+        None => {}
+    }
+}
+
+// Подсвечиваем совпавшие символы (позиции в символах, не в байтах)
+fn highlighted_text(ui: &egui::Ui, text: &str, positions: &[usize]) -> egui::text::LayoutJob {
+    let mut job = egui::text::LayoutJob::default();
+    let normal = egui::TextFormat {
+        font_id: egui::TextStyle::Body.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let highlight = egui::TextFormat {
+        background: ui.visuals().selection.bg_fill,
+        color: ui.visuals().strong_text_color(),
+        ..normal.clone()
+    };
+
+    let mut buffer = [0u8; 4];
+    for (i, c) in text.chars().enumerate() {
+        let format = if positions.binary_search(&i).is_ok() {
+            highlight.clone()
+        } else {
+            normal.clone()
+        };
+        job.append(c.encode_utf8(&mut buffer), 0.0, format);
     }
+    job
+}
+
+// Разбираем поле 2FA: пустая строка означает отсутствие 2FA
+fn parse_otp_field(input: &str) -> Result<Option<OtpConfig>, String> {
+    if input.trim().is_empty() {
+        Ok(None)
+    } else {
+        OtpConfig::parse(input).map(Some)
+    }
+}
+
+// Максимальный размер вложения
+const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+// Ширина окна, начиная с которой панель записи показывается справа, а не снизу
+const DETAILS_SIDE_MIN_WIDTH: f32 = 900.0;
+
+// Высота строки таблицы (одинаковая для всех строк, нужна для виртуализации)
+const ROW_HEIGHT: f32 = 26.0;
+
+// Действие над строкой таблицы, выполняемое после отрисовки
+enum RowAction {
+    Edit(usize),
+    Trash(usize),
+    AdvanceHotp(usize),
+    ShowQr(usize),
+    Used(usize),
+    ToggleFavorite(usize),
+    TogglePin(usize),
+    Sort(Column),
+    Select(usize), // позиция строки среди отображаемых
+}
+
+// Сколько записей показывать в представлении «Недавние»
+const RECENT_LIMIT: usize = 20;
+
+// Представление списка: все записи, избранное или недавно использованные
+#[derive(Clone, Copy, PartialEq)]
+enum EntryView {
+    All,
+    Favorites,
+    Recent,
+}
+
+// Куда подставить пароль из окна генератора
+#[derive(Clone, Copy, PartialEq)]
+enum GeneratorTarget {
+    NewEntry,
+    EditEntry,
+}
+
+// Массовое действие над выбранными записями
+enum BulkAction {
+    Trash,
+    MoveToFolder(String),
+    AddTags(Vec<String>),
+    RemoveTags(Vec<String>),
+    RegeneratePasswords,
 }
 
 pub struct PasswordManager {
     pub current_vault: Option<Vault>,
     pub decrypted_entries: Vec<Entry>,
+    pub trash: Vec<TrashedEntry>,
     pub master_password: String,
     pub vault_path: Option<PathBuf>,
 
@@ -31,6 +304,68 @@ pub struct PasswordManager {
     pub new_service: String,
     pub new_login: String,
     pub new_password: String,
+    pub new_otp: String,
+    pub new_url: String,
+    pub new_notes: String,
+    pub new_tags: String,
+    pub new_folder: String,
+
+    // Поиск: строка запроса и кэш результатов (запрос, ревизия записей, совпадения)
+    pub search_query: String,
+    search_cache: Option<(String, u64, Vec<EntryMatch>)>,
+    entries_revision: u64,
+    entry_view: EntryView,
+
+    // Колонки и сортировка таблицы (сохраняются в хранилище)
+    pub table_view: TableView,
+    // Изменения, которые не стоят отдельного сохранения: записываются вместе
+    // со следующим сохранением хранилища или при блокировке
+    pending_save: bool,
+
+    // Выбранные записи (индексы в decrypted_entries) для массовых действий
+    selected: BTreeSet<usize>,
+    selection_anchor: Option<usize>,
+    bulk_folder: String,
+    bulk_tags: String,
+
+    // Панель с полями выбранной записи
+    entry_details: EntryDetails,
+
+    // Кэш оценок надёжности: индекс записи → (отпечаток полей, оценка).
+    // Отпечаток считается со случайным ключом, чтобы по нему нельзя было перебрать пароль
+    strength_cache: HashMap<usize, (u64, Estimate)>,
+    fingerprint_hasher: RandomState,
+
+    // Фильтр утечек HIBP и кэш проверок: индекс записи → (отпечаток пароля, найден ли)
+    breach_filter: Option<BreachFilter>,
+    breach_cache: HashMap<usize, (u64, bool)>,
+
+    // Справочник сервисов с 2FA для проверки здоровья
+    two_factor: Directory,
+
+    // Политики паролей для сайтов по шаблону адреса (сохраняются в хранилище)
+    site_policies: Vec<SitePolicy>,
+    show_policies: bool,
+    new_policy_pattern: String,
+    new_policy_rules: String,
+
+    // Генератор паролей (параметры и профили хранятся в настройках)
+    pub generator: PasswordGenerator,
+    generator_target: GeneratorTarget,
+
+    // Смена мастер-пароля (требования задаются в настройках)
+    pub change_master: ChangeMasterPassword,
+
+    // Отчёт о здоровье хранилища
+    health: VaultHealth,
+
+    // Сводка просроченных паролей после открытия хранилища
+    show_expiry_summary: bool,
+
+    // Мастер импорта записей из других форматов и экспорт в CSV
+    import: ImportWizard,
+    plain_export: PlainExport,
+    kdbx_export: KdbxExport,
 
     // Для редактирования
     pub edit_entry: Option<EditEntry>,
@@ -40,12 +375,22 @@ pub struct PasswordManager {
     pub hovered_password_index: Option<usize>,
     pub error_message: String,
 
+    pub show_trash: bool,
+
+    // Стек отмены/повтора изменений за сессию
+    history: History,
+
+    // QR-код 2FA для переноса на телефон: заголовок и текстура
+    qr_preview: Option<(String, egui::TextureHandle)>,
+
     app_state: bool,
 }
 
 impl zeroize::Zeroize for PasswordManager {
     fn zeroize(&mut self) {
         self.decrypted_entries.zeroize();
+        self.trash.zeroize();
+        self.history.zeroize();
         self.master_password.zeroize();
         self.edit_entry.zeroize();
     }
@@ -56,20 +401,71 @@ impl PasswordManager {
         Self {
             current_vault: None,
             decrypted_entries: Vec::new(),
+            trash: Vec::new(),
             master_password: String::new(),
             vault_path: None,
             new_service: String::new(),
             new_login: String::new(),
             new_password: String::new(),
+            new_otp: String::new(),
+            new_url: String::new(),
+            new_notes: String::new(),
+            new_tags: String::new(),
+            new_folder: String::new(),
+            search_query: String::new(),
+            search_cache: None,
+            entries_revision: 0,
+            entry_view: EntryView::All,
+            table_view: TableView::default(),
+            pending_save: false,
+            selected: BTreeSet::new(),
+            selection_anchor: None,
+            bulk_folder: String::new(),
+            bulk_tags: String::new(),
+            entry_details: EntryDetails::default(),
+            strength_cache: HashMap::new(),
+            fingerprint_hasher: RandomState::new(),
+            breach_filter: None,
+            breach_cache: HashMap::new(),
+            two_factor: Directory::bundled(),
+            site_policies: Vec::new(),
+            show_policies: false,
+            new_policy_pattern: String::new(),
+            new_policy_rules: String::new(),
+            generator: PasswordGenerator::new(Default::default(), Vec::new()),
+            generator_target: GeneratorTarget::NewEntry,
+            change_master: ChangeMasterPassword::new(),
+            health: VaultHealth::new(),
+            show_expiry_summary: false,
+            import: ImportWizard::new(),
+            plain_export: PlainExport::new(),
+            kdbx_export: KdbxExport::new(),
             edit_entry: None,
             show_edit_dialog: false,
             hovered_password_index: None,
             error_message: String::new(),
+            show_trash: false,
+            history: History::default(),
+            qr_preview: None,
             app_state: true,
         }
     }
 
     pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        // Горячие клавиши отмены/повтора (если не редактируется текстовое поле)
+        if !ui.ctx().wants_keyboard_input() {
+            let redo_shortcut = egui::KeyboardShortcut::new(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::Z,
+            );
+            let undo_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+            if ui.input_mut(|i| i.consume_shortcut(&redo_shortcut)) {
+                self.redo();
+            } else if ui.input_mut(|i| i.consume_shortcut(&undo_shortcut)) {
+                self.undo();
+            }
+        }
+
         // Заголовок с информацией о хранилище
         self.show_header(ui);
         ui.separator();
@@ -84,7 +480,8 @@ impl PasswordManager {
         self.show_add_form(ui);
         ui.separator();
 
-        // Таблица с паролями
+        // Панель выбранной записи и таблица с паролями
+        self.show_entry_details(ui);
         self.show_password_table(ui);
 
         // Показываем диалог редактирования если нужно
@@ -92,8 +489,109 @@ impl PasswordManager {
             self.show_edit_dialog_ui(ui.ctx());
         }
 
+        // Показываем корзину если нужно
+        if self.show_trash {
+            self.show_trash_window(ui.ctx());
+        }
+
+        // Показываем QR-код 2FA если нужно
+        if self.qr_preview.is_some() {
+            self.show_qr_window(ui.ctx());
+        }
+
+        // Окно политик паролей
+        if self.show_policies {
+            self.show_policies_window(ui.ctx());
+        }
+
+        // Окно генератора паролей
+        if self.generator.show {
+            self.generator.show(ui.ctx());
+        }
+        if let Some(password) = self.generator.take_result() {
+            match self.generator_target {
+                GeneratorTarget::NewEntry => {
+                    self.new_password.zeroize();
+                    self.new_password = password;
+                }
+                GeneratorTarget::EditEntry => {
+                    if let Some(edit) = self.edit_entry.as_mut() {
+                        edit.password.zeroize();
+                        edit.password = password;
+                    }
+                }
+            }
+        }
+
+        // Мастер импорта; подтверждённый план применяется одной операцией
+        if self.import.show {
+            self.import.show(ui.ctx(), &self.decrypted_entries);
+        }
+        if let Some(plan) = self.import.take_result() {
+            self.apply_import(plan);
+        }
+        let vault_name = self
+            .current_vault
+            .as_ref()
+            .map(|vault| vault.name.clone())
+            .unwrap_or_default();
+        if self.plain_export.show {
+            self.plain_export.show(
+                ui.ctx(),
+                &self.decrypted_entries,
+                &vault_name,
+                &self.master_password,
+            );
+        }
+        if self.kdbx_export.show {
+            self.kdbx_export.show(
+                ui.ctx(),
+                &self.decrypted_entries,
+                &vault_name,
+                &self.master_password,
+            );
+        }
+
+        // Сводка паролей, которые пора сменить
+        if self.show_expiry_summary {
+            self.show_expiry_window(ui.ctx());
+        }
+
+        // Окно здоровья хранилища; отчёт пересчитывается после изменения записей
+        if self.health.show {
+            self.show_health_window(ui.ctx());
+        }
+        // Фильтр утечек перестраивается в фоне и подменяется по готовности
+        match self.health.take_built_filter(ui.ctx()) {
+            Some(Ok(filter)) => {
+                self.error_message = format!(
+                    "✅ Фильтр утечек построен: {} хешей {}",
+                    filter.count(),
+                    filter.kind().title()
+                );
+                self.breach_filter = Some(filter);
+                self.breach_cache.clear();
+                self.health.invalidate();
+            }
+            Some(Err(e)) => self.error_message = format!("❌ Фильтр утечек: {}", e),
+            None => {}
+        }
+
+        // Окно смены мастер-пароля; хранилище сразу перешифровывается новым паролем
+        if self.change_master.show {
+            self.change_master.show(ui.ctx(), &self.master_password);
+        }
+        if let Some(password) = self.change_master.take_result() {
+            self.master_password.zeroize();
+            self.master_password = password;
+            self.save_vault();
+            if self.error_message.starts_with('✅') {
+                self.error_message = "✅ Мастер-пароль изменён".to_string();
+            }
+        }
+
         if !self.app_state {
-            self.zeroize();
+            self.lock();
         }
 
         self.app_state
@@ -114,6 +612,101 @@ impl PasswordManager {
                 if ui.button("🚪 Выйти").clicked() {
                     self.app_state = false;
                 }
+
+                ui.menu_button("📤 Экспорт", |ui| {
+                    if ui
+                        .button("📄 CSV…")
+                        .on_hover_text("Открытым текстом")
+                        .clicked()
+                    {
+                        self.plain_export.open(PlainFormat::Csv);
+                        ui.close();
+                    }
+                    if ui
+                        .button("📄 KeePass XML…")
+                        .on_hover_text("Открытым текстом, для KeePass 2.x")
+                        .clicked()
+                    {
+                        self.plain_export.open(PlainFormat::KeePassXml);
+                        ui.close();
+                    }
+                    if ui
+                        .button("🗄️ KeePass (.kdbx)…")
+                        .on_hover_text("Зашифрованная база под отдельным паролем")
+                        .clicked()
+                    {
+                        self.kdbx_export.show = true;
+                        ui.close();
+                    }
+                });
+
+                ui.menu_button("📥 Импорт", |ui| {
+                    if ui.button("📄 CSV…").clicked() {
+                        self.import.open_csv();
+                        ui.close();
+                    }
+                    if ui.button("🗄️ KeePass (.kdbx)…").clicked() {
+                        self.import.open_kdbx();
+                        ui.close();
+                    }
+                    if ui.button("📄 KeePass XML…").clicked() {
+                        self.import.open_keepass_xml();
+                        ui.close();
+                    }
+                    if ui.button("📄 Bitwarden (.json)…").clicked() {
+                        self.import.open_bitwarden();
+                        ui.close();
+                    }
+                    if ui.button("🗄️ 1Password (.1pux)…").clicked() {
+                        self.import.open_1pux();
+                        ui.close();
+                    }
+                });
+
+                if ui.button("🩺 Здоровье").clicked() {
+                    self.health.show = !self.health.show;
+                }
+
+                if ui.button("🔐 Мастер-пароль").clicked() {
+                    self.change_master.show = true;
+                }
+
+                if ui.button("📏 Политики").clicked() {
+                    self.show_policies = !self.show_policies;
+                }
+
+                // Кнопка корзины
+                if ui
+                    .button(format!("🗑️ Корзина ({})", self.trash.len()))
+                    .clicked()
+                {
+                    self.show_trash = !self.show_trash;
+                }
+
+                // Кнопки отмены/повтора
+                let redo_label = self.history.redo_label().map(str::to_string);
+                let redo_button = ui.add_enabled(redo_label.is_some(), egui::Button::new("↷"));
+                if redo_button
+                    .on_hover_text(format!(
+                        "Повторить: {} (Ctrl+Shift+Z)",
+                        redo_label.unwrap_or_default()
+                    ))
+                    .clicked()
+                {
+                    self.redo();
+                }
+
+                let undo_label = self.history.undo_label().map(str::to_string);
+                let undo_button = ui.add_enabled(undo_label.is_some(), egui::Button::new("↶"));
+                if undo_button
+                    .on_hover_text(format!(
+                        "Отменить: {} (Ctrl+Z)",
+                        undo_label.unwrap_or_default()
+                    ))
+                    .clicked()
+                {
+                    self.undo();
+                }
             });
         });
     }
@@ -135,6 +728,47 @@ impl PasswordManager {
             ui.label("🔑 Пароль:");
             ui.text_edit_singleline(&mut self.new_password);
         });
+        if !self.new_password.is_empty() {
+            let estimate =
+                strength::estimate(&self.new_password, &[&self.new_service, &self.new_login]);
+            widgets::strength_meter(ui, &estimate);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("🔢 2FA:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_otp)
+                    .hint_text("otpauth://... или секрет (необязательно)"),
+            );
+            if ui
+                .button("📷 Из QR")
+                .on_hover_text("Загрузить QR-код (в т.ч. экспорт Google Authenticator)")
+                .clicked()
+            {
+                self.import_otp_from_qr();
+            }
+        });
+
+        egui::CollapsingHeader::new("Дополнительно")
+            .id_salt("add_form_extra")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("🔗 URL:");
+                    ui.text_edit_singleline(&mut self.new_url);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("📁 Папка:");
+                    ui.add(egui::TextEdit::singleline(&mut self.new_folder).hint_text("work/ops"));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("🏷️ Теги:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_tags).hint_text("через запятую"),
+                    );
+                });
+                ui.label("📝 Заметки:");
+                ui.add(egui::TextEdit::multiline(&mut self.new_notes).desired_rows(2));
+            });
 
         let all_filled = !self.new_service.is_empty()
             && !self.new_login.is_empty()
@@ -149,7 +783,22 @@ impl PasswordManager {
             }
 
             if ui.button("🔄 Сгенерировать").clicked() {
-                self.new_password = self.generate_password(16);
+                let policy = self.site_policy_for(&self.new_url).cloned();
+                match self.generate_password(policy.as_ref()) {
+                    Ok(password) => {
+                        self.new_password.zeroize();
+                        self.new_password = password;
+                    }
+                    Err(e) => self.error_message = format!("❌ Генератор: {}", e),
+                }
+            }
+            if ui
+                .button("🎲")
+                .on_hover_text("Настроить генератор")
+                .clicked()
+            {
+                self.generator_target = GeneratorTarget::NewEntry;
+                self.generator.show = true;
             }
 
             if ui.button("💾 Сохранить").clicked() {
@@ -166,151 +815,1407 @@ impl PasswordManager {
             return;
         }
 
-        // Заголовок таблицы
-        egui::Grid::new("passwords_header")
-            .spacing([20.0, 8.0])
-            .min_col_width(150.0)
-            .show(ui, |ui| {
-                ui.heading("🌐 Сервис");
-                ui.heading("👤 Логин");
-                ui.heading("🔑 Пароль");
-                ui.heading(""); // Для кнопок действий
-                ui.end_row();
+        self.show_search_bar(ui);
+        let visible = self.visible_entries();
+        if visible.is_empty() {
+            ui.label("🔍 Ничего не найдено");
+            return;
+        }
+
+        // Ctrl+A выбирает все найденные записи, Esc снимает выбор
+        if !ui.ctx().wants_keyboard_input() {
+            let select_all = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::A);
+            if ui.input_mut(|i| i.consume_shortcut(&select_all)) {
+                self.selected = visible.iter().map(|m| m.index).collect();
+            } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.selected.clear();
+            }
+        }
+        self.show_bulk_actions(ui, &visible);
+
+        // Живые TOTP-коды требуют перерисовки хотя бы раз в секунду
+        let columns = self.table_view.columns.clone();
+        if columns.contains(&Column::Totp) && self.decrypted_entries.iter().any(|e| e.otp.is_some())
+        {
+            ui.ctx()
+                .request_repaint_after(std::time::Duration::from_secs(1));
+        }
+        let now = totp::unix_now();
+        let mut action = None;
+
+        // Текст в ячейках не выделяется, чтобы клик по строке выбирал запись
+        ui.style_mut().interaction.selectable_labels = false;
+        let mut table = TableBuilder::new(ui)
+            .id_salt("passwords_table")
+            .striped(true)
+            .resizable(true)
+            .sense(egui::Sense::click())
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center));
+        for column in &columns {
+            let width = match column {
+                Column::Service | Column::Login | Column::Url => 160.0,
+                Column::Tags | Column::Folder | Column::Modified => 130.0,
+                _ => 110.0,
+            };
+            table = table.column(TableColumn::initial(width).at_least(60.0).clip(true));
+        }
+        // Колонка с кнопками действий занимает остаток ширины
+        table = table.column(TableColumn::remainder().at_least(170.0));
+
+        table
+            .header(28.0, |mut header| {
+                for column in &columns {
+                    header.col(|ui| {
+                        // Сортировка по паролю раскрывала бы секреты, поэтому её нет
+                        if *column == Column::Password {
+                            ui.strong(column.title());
+                            return;
+                        }
+                        let arrow = match self.table_view.sort_column {
+                            Some(sorted) if sorted == *column => {
+                                if self.table_view.sort_ascending {
+                                    " ⏶"
+                                } else {
+                                    " ⏷"
+                                }
+                            }
+                            _ => "",
+                        };
+                        let title = egui::RichText::new(format!("{}{}", column.title(), arrow));
+                        if ui
+                            .add(egui::Label::new(title.strong()).sense(egui::Sense::click()))
+                            .on_hover_text("Сортировать")
+                            .clicked()
+                        {
+                            action = Some(RowAction::Sort(*column));
+                        }
+                    });
+                }
+                header.col(|_| {});
+            })
+            .body(|body| {
+                // Отрисовываются только видимые строки, поэтому таблица не тормозит на тысячах записей
+                body.rows(ROW_HEIGHT, visible.len(), |mut row| {
+                    let position = row.index();
+                    let matched = &visible[position];
+                    if matched.index >= self.decrypted_entries.len() {
+                        return;
+                    }
+                    row.set_selected(self.selected.contains(&matched.index));
+                    for column in &columns {
+                        row.col(|ui| {
+                            self.table_cell_ui(ui, *column, matched, now, &mut action);
+                        });
+                    }
+                    row.col(|ui| {
+                        let index = matched.index;
+                        let entry = &self.decrypted_entries[index];
+                        let star = if entry.favorite { "★" } else { "☆" };
+                        if ui.button(star).on_hover_text("Избранное").clicked() {
+                            action = Some(RowAction::ToggleFavorite(index));
+                        }
+                        if ui
+                            .add(egui::Button::new("📌").selected(entry.pinned))
+                            .on_hover_text("Закрепить вверху")
+                            .clicked()
+                        {
+                            action = Some(RowAction::TogglePin(index));
+                        }
+                        if ui.button("📋").on_hover_text("Копировать").clicked() {
+                            ui.ctx()
+                                .copy_text(self.decrypted_entries[index].password.clone());
+                            action = Some(RowAction::Used(index));
+                        }
+                        if ui.button("✏️").on_hover_text("Редактировать").clicked()
+                        {
+                            action = Some(RowAction::Edit(index));
+                        }
+                        if ui
+                            .button("🗑️")
+                            .on_hover_text("Переместить в корзину")
+                            .clicked()
+                        {
+                            action = Some(RowAction::Trash(index));
+                        }
+                    });
+                    if row.response().clicked() && action.is_none() {
+                        action = Some(RowAction::Select(position));
+                    }
+                });
             });
 
-        ui.separator();
+        match action {
+            Some(RowAction::Edit(index)) => {
+                // Клонируем запись для редактирования
+                if let Some(entry) = self.decrypted_entries.get(index).cloned() {
+                    self.edit_entry = Some(EditEntry::from_entry(
+                        index,
+                        self.history.revision(),
+                        &entry,
+                    ));
+                    self.show_edit_dialog = true;
+                }
+            }
+            Some(RowAction::Trash(index)) => {
+                self.move_to_trash(index);
+                // Корректируем индексы при наведении
+                if let Some(hovered_index) = self.hovered_password_index {
+                    if hovered_index == index {
+                        self.hovered_password_index = None;
+                    } else if hovered_index > index {
+                        self.hovered_password_index = Some(hovered_index - 1);
+                    }
+                }
+                // Автоматически сохраняем изменения
+                self.save_vault();
+            }
+            Some(RowAction::AdvanceHotp(index)) => self.advance_hotp_counter(index),
+            Some(RowAction::ShowQr(index)) => {
+                if let Some(entry) = self.decrypted_entries.get(index)
+                    && let Some(config) = entry.otp.clone()
+                {
+                    let service = entry.service.clone();
+                    self.open_qr_preview(ui.ctx(), &service, &config);
+                }
+            }
+            Some(RowAction::Used(index)) => self.mark_used(index),
+            Some(RowAction::ToggleFavorite(index)) => self.toggle_favorite(index),
+            Some(RowAction::TogglePin(index)) => self.toggle_pin(index),
+            Some(RowAction::Select(position)) => {
+                let modifiers = ui.input(|i| i.modifiers);
+                self.select_row(&visible, position, modifiers);
+            }
+            Some(RowAction::Sort(column)) => {
+                // По кругу: по возрастанию → по убыванию → без сортировки
+                let view = &mut self.table_view;
+                match view.sort_column {
+                    Some(sorted) if sorted == column && view.sort_ascending => {
+                        view.sort_ascending = false
+                    }
+                    Some(sorted) if sorted == column => view.sort_column = None,
+                    _ => {
+                        view.sort_column = Some(column);
+                        view.sort_ascending = true;
+                    }
+                }
+                self.search_cache = None;
+                self.pending_save = true;
+            }
+            None => {}
+        }
+    }
+
+    // Содержимое одной ячейки таблицы
+    fn table_cell_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        column: Column,
+        matched: &EntryMatch,
+        now: u64,
+        action: &mut Option<RowAction>,
+    ) {
+        let index = matched.index;
+        let breached = column == Column::Service && self.entry_breached(index);
+        let entry = &self.decrypted_entries[index];
+
+        match column {
+            Column::Service => {
+                if breached {
+                    ui.colored_label(ui.visuals().error_fg_color, "🚨")
+                        .on_hover_text("Пароль найден в базе утечек HIBP — смените его");
+                }
+                expiry_badge(ui, entry);
+                ui.label(highlighted_text(ui, &entry.service, &matched.service));
+            }
+            Column::Login => {
+                ui.label(highlighted_text(ui, &entry.login, &matched.login));
+            }
+            Column::Password => {
+                // Скрытие/показ пароля при наведении
+                let password_text = if self.hovered_password_index == Some(index) {
+                    entry.password.clone()
+                } else {
+                    "••••••••".to_string()
+                };
 
-        // Скроллируемая область с паролями
-        egui::ScrollArea::vertical().show(ui, |ui| {
-            egui::Grid::new("passwords_grid")
-                .striped(true)
-                .spacing([20.0, 8.0])
-                .min_col_width(150.0)
-                .show(ui, |ui| {
-                    // Используем индексы для избежания конфликтов заимствования
-                    let entries_count = self.decrypted_entries.len();
-                    for index in 0..entries_count {
-                        if let Some(entry) = self.decrypted_entries.get(index) {
-                            let service = entry.service.clone();
-                            let login = entry.login.clone();
-                            let password = entry.password.clone();
-
-                            ui.label(&service);
-                            ui.label(&login);
-
-                            // Скрытие/показ пароля при наведении
-                            let password_text = if self.hovered_password_index == Some(index) {
-                                password.clone()
-                            } else {
-                                "••••••••".to_string()
-                            };
-
-                            let response = ui.add(egui::Label::new(password_text));
-                            if response.hovered() {
-                                self.hovered_password_index = Some(index);
-                            } else if self.hovered_password_index == Some(index) {
-                                self.hovered_password_index = None;
+                let response = ui.add(egui::Label::new(password_text));
+                if response.hovered() {
+                    self.hovered_password_index = Some(index);
+                } else if self.hovered_password_index == Some(index) {
+                    self.hovered_password_index = None;
+                }
+            }
+            Column::Url => {
+                ui.label(&entry.url);
+            }
+            Column::Folder => {
+                ui.label(&entry.folder);
+            }
+            Column::Tags => {
+                ui.label(entry.tags.join(", "));
+            }
+            Column::Modified => {
+                ui.label(
+                    entry
+                        .modified_at
+                        .map(widgets::format_time)
+                        .unwrap_or_else(|| "—".to_string()),
+                );
+            }
+            Column::LastUsed => {
+                ui.label(
+                    entry
+                        .last_used_at
+                        .map(widgets::format_time)
+                        .unwrap_or_else(|| "—".to_string()),
+                );
+            }
+            Column::UseCount => {
+                ui.label(entry.use_count.to_string());
+            }
+            Column::Expiry => match entry.expires_at() {
+                Some(expires_at) => {
+                    let text = widgets::format_date(expires_at);
+                    match entry.expiry_status(SystemTime::now()) {
+                        Some(ExpiryStatus::Expired) => {
+                            ui.colored_label(ui.visuals().error_fg_color, text);
+                        }
+                        Some(ExpiryStatus::Soon) => {
+                            ui.colored_label(ui.visuals().warn_fg_color, text);
+                        }
+                        None => {
+                            ui.label(text);
+                        }
+                    }
+                }
+                None => {
+                    ui.weak("—");
+                }
+            },
+            Column::Strength => {
+                if entry.password.is_empty() {
+                    ui.weak("—");
+                } else {
+                    let estimate = self.entry_strength(index);
+                    let color = widgets::strength_color(ui, estimate.score);
+                    let response = ui.colored_label(color, estimate.label());
+                    if !estimate.warning.is_empty() {
+                        response.on_hover_text(&estimate.warning);
+                    }
+                }
+            }
+            Column::Totp => {
+                // Текущий одноразовый код (копируется одним кликом)
+                match &entry.otp {
+                    Some(config) => match config.code_at(now) {
+                        Ok(code) => {
+                            let code_label =
+                                egui::Label::new(egui::RichText::new(&code).monospace().strong())
+                                    .sense(egui::Sense::click());
+                            if ui
+                                .add(code_label)
+                                .on_hover_text("Нажмите, чтобы скопировать код")
+                                .clicked()
+                            {
+                                ui.ctx().copy_text(code);
+                                *action = Some(RowAction::Used(index));
                             }
 
-                            // Кнопки действий (создаем копии для замыканий)
-                            let index_copy = index;
-                            let password_copy = password.clone();
+                            if ui
+                                .small_button("📱")
+                                .on_hover_text("Показать QR-код для телефона")
+                                .clicked()
+                            {
+                                *action = Some(RowAction::ShowQr(index));
+                            }
 
-                            ui.horizontal(|ui| {
-                                if ui.button("📋").on_hover_text("Копировать").clicked()
-                                {
-                                    ui.ctx().copy_text(password_copy);
+                            match config.kind {
+                                OtpKind::Totp => {
+                                    let remaining = config.seconds_remaining(now);
+                                    widgets::countdown_ring(
+                                        ui,
+                                        remaining as f32 / config.period as f32,
+                                    )
+                                    .on_hover_text(format!("{} с", remaining));
                                 }
-
-                                if ui.button("✏️").on_hover_text("Редактировать").clicked()
-                                {
-                                    // Клонируем запись для редактирования
-                                    if let Some(entry) =
-                                        self.decrypted_entries.get(index_copy).cloned()
+                                OtpKind::Hotp => {
+                                    if ui
+                                        .small_button("⏭")
+                                        .on_hover_text("Следующий код")
+                                        .clicked()
                                     {
-                                        self.edit_entry = Some(EditEntry {
-                                            index: index_copy,
-                                            service: entry.service,
-                                            login: entry.login,
-                                            password: entry.password,
-                                        });
-                                        self.show_edit_dialog = true;
+                                        *action = Some(RowAction::AdvanceHotp(index));
                                     }
                                 }
+                            }
+                        }
+                        Err(e) => {
+                            ui.colored_label(ui.visuals().error_fg_color, "⚠")
+                                .on_hover_text(e);
+                        }
+                    },
+                    None => {
+                        ui.label("—");
+                    }
+                }
+            }
+        }
+    }
 
-                                if ui.button("🗑️").on_hover_text("Удалить").clicked() {
-                                    self.decrypted_entries.remove(index_copy);
-                                    // Корректируем индексы при наведении
-                                    if let Some(hovered_index) = self.hovered_password_index {
-                                        if hovered_index == index_copy {
-                                            self.hovered_password_index = None;
-                                        } else if hovered_index > index_copy {
-                                            self.hovered_password_index = Some(hovered_index - 1);
-                                        }
-                                    }
-                                    // Автоматически сохраняем изменения
-                                    self.save_vault();
-                                }
-                            });
+    // Строка поиска с фильтрами; Ctrl+F переводит на неё фокус
+    fn show_search_bar(&mut self, ui: &mut egui::Ui) {
+        let search_id = egui::Id::new("entry_search");
+        let focus_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::F);
+        if ui.input_mut(|i| i.consume_shortcut(&focus_shortcut)) {
+            ui.memory_mut(|m| m.request_focus(search_id));
+        }
 
-                            ui.end_row();
+        ui.horizontal(|ui| {
+            for (view, title) in [
+                (EntryView::All, "📋 Все"),
+                (EntryView::Favorites, "⭐ Избранное"),
+                (EntryView::Recent, "🕘 Недавние"),
+            ] {
+                if ui
+                    .selectable_label(self.entry_view == view, title)
+                    .clicked()
+                {
+                    self.entry_view = view;
+                    self.search_cache = None;
+                }
+            }
+            ui.separator();
+
+            ui.label("🔍");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.search_query)
+                    .id(search_id)
+                    .hint_text("Поиск (Ctrl+F): tag:work folder:ops weak: expired: has:totp")
+                    .desired_width(400.0),
+            );
+            if response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.search_query.clear();
+            }
+            if !self.search_query.is_empty() && ui.small_button("✖").clicked() {
+                self.search_query.clear();
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let mut changed = false;
+                ui.menu_button("🧩 Колонки", |ui| {
+                    for column in Column::ALL {
+                        let mut shown = self.table_view.columns.contains(&column);
+                        if ui.checkbox(&mut shown, column.title()).changed() {
+                            changed = true;
+                            if shown {
+                                self.table_view.columns.push(column);
+                                // Сохраняем порядок колонок как в Column::ALL
+                                self.table_view
+                                    .columns
+                                    .sort_by_key(|c| Column::ALL.iter().position(|a| a == c));
+                            } else if self.table_view.columns.len() > 1 {
+                                self.table_view.columns.retain(|c| *c != column);
+                            }
                         }
                     }
                 });
+                if changed {
+                    self.pending_save = true;
+                }
+            });
         });
+        ui.add_space(4.0);
     }
 
-    // Диалог редактирования записи
-    fn show_edit_dialog_ui(&mut self, ctx: &egui::Context) {
-        // Создаем временную переменную вместо прямого заимствования
-        let mut show_dialog = self.show_edit_dialog;
-        if !show_dialog {
-            return;
+    // Записи, подходящие под запрос (результат кэшируется до изменения запроса или записей)
+    fn visible_entries(&mut self) -> Vec<EntryMatch> {
+        if let Some((query, revision, matches)) = &self.search_cache
+            && *query == self.search_query
+            && *revision == self.entries_revision
+        {
+            return matches.clone();
         }
 
-        // Создаем копии данных для диалога
-        let (mut service, mut login, mut password, index) = if let Some(ref entry) = self.edit_entry
-        {
-            (
-                entry.service.clone(),
-                entry.login.clone(),
-                entry.password.clone(),
-                entry.index,
-            )
+        let query = SearchQuery::parse(&self.search_query);
+        let mut matches = if query.is_empty() {
+            (0..self.decrypted_entries.len())
+                .map(|index| EntryMatch {
+                    index,
+                    ..EntryMatch::default()
+                })
+                .collect()
         } else {
-            (String::new(), String::new(), String::new(), 0)
+            // Пароли читаются только если запрос явно содержит weak:
+            let needs_secrets = query.needs_secrets();
+            let now = SystemTime::now();
+            search::filter_entries(&self.decrypted_entries, &query, |entry| EntryFlags {
+                weak: needs_secrets && estimate_strength(entry).is_weak(),
+                expired: entry.expiry_status(now) == Some(ExpiryStatus::Expired),
+            })
         };
 
-        // Используем временную переменную
-        egui::Window::new("✏️ Редактировать запись")
-            .open(&mut show_dialog)
-            .resizable(false)
-            .default_width(400.0)
-            .show(ctx, |ui| {
-                ui.label("🌐 Сервис:");
-                ui.text_edit_singleline(&mut service);
-
-                ui.label("👤 Логин:");
-                ui.text_edit_singleline(&mut login);
+        if let Some(column) = self.table_view.sort_column {
+            self.sort_matches(&mut matches, column);
+        }
+
+        let entries = &self.decrypted_entries;
+        match self.entry_view {
+            EntryView::All => {}
+            EntryView::Favorites => matches.retain(|m| entries[m.index].favorite),
+            EntryView::Recent => {
+                matches.retain(|m| entries[m.index].last_used_at.is_some());
+                matches.sort_by_key(|m| std::cmp::Reverse(entries[m.index].last_used_at));
+                matches.truncate(RECENT_LIMIT);
+            }
+        }
+        // Закреплённые записи поднимаем наверх, сохраняя порядок внутри групп
+        if self.entry_view != EntryView::Recent {
+            matches.sort_by_key(|m| !entries[m.index].pinned);
+        }
+
+        self.search_cache = Some((
+            self.search_query.clone(),
+            self.entries_revision,
+            matches.clone(),
+        ));
+        matches
+    }
+
+    // Запись для панели подробностей — последняя выбранная кликом
+    fn detail_index(&self) -> Option<usize> {
+        self.selection_anchor
+            .filter(|index| self.selected.contains(index))
+            .filter(|&index| index < self.decrypted_entries.len())
+    }
+
+    // Панель записи: справа на широком окне, снизу на узком
+    fn show_entry_details(&mut self, ui: &mut egui::Ui) {
+        let Some(index) = self.detail_index() else {
+            return;
+        };
+        let entry = &self.decrypted_entries[index];
+        let details = &mut self.entry_details;
+
+        let action = if ui.available_width() >= DETAILS_SIDE_MIN_WIDTH {
+            egui::SidePanel::right("entry_details_side")
+                .resizable(true)
+                .default_width(320.0)
+                .min_width(240.0)
+                .show_inside(ui, |ui| details.ui(ui, index, entry))
+                .inner
+        } else {
+            egui::TopBottomPanel::bottom("entry_details_bottom")
+                .resizable(true)
+                .default_height(220.0)
+                .show_inside(ui, |ui| details.ui(ui, index, entry))
+                .inner
+        };
+
+        match action {
+            Some(DetailAction::Used) => self.mark_used(index),
+            Some(DetailAction::ToggleFavorite) => self.toggle_favorite(index),
+            Some(DetailAction::TogglePin) => self.toggle_pin(index),
+            Some(DetailAction::Edit) => {
+                let entry = self.decrypted_entries[index].clone();
+                self.edit_entry = Some(EditEntry::from_entry(
+                    index,
+                    self.history.revision(),
+                    &entry,
+                ));
+                self.show_edit_dialog = true;
+            }
+            Some(DetailAction::ShowQr) => {
+                if let Some(config) = self.decrypted_entries[index].otp.clone() {
+                    let service = self.decrypted_entries[index].service.clone();
+                    self.open_qr_preview(ui.ctx(), &service, &config);
+                }
+            }
+            Some(DetailAction::AdvanceHotp) => self.advance_hotp_counter(index),
+            Some(DetailAction::RotatePassword) => self.rotate_password(index),
+            Some(DetailAction::AddAttachment) => self.add_attachment(index),
+            Some(DetailAction::SaveAttachment(i)) => self.save_attachment(index, i),
+            Some(DetailAction::RemoveAttachment(i)) => {
+                let mut entry = self.decrypted_entries[index].clone();
+                if i < entry.attachments.len() {
+                    entry.attachments.remove(i).zeroize();
+                    entry.modified_at = Some(SystemTime::now());
+                    let mut tx = Transaction::new(
+                        "Удаление вложения",
+                        &mut self.decrypted_entries,
+                        &mut self.trash,
+                    );
+                    tx.replace_entry(index, entry);
+                    self.history.record(tx.finish());
+                    self.save_vault();
+                }
+            }
+            Some(DetailAction::Close) => self.selected.clear(),
+            None => {}
+        }
+    }
+
+    // Прикрепляем файл к записи
+    fn add_attachment(&mut self, index: usize) {
+        let Some(path) = rfd::FileDialog::new().pick_file() else {
+            return;
+        };
+        let data = match std::fs::read(&path) {
+            Ok(data) if data.len() > MAX_ATTACHMENT_SIZE => {
+                self.error_message = "❌ Файл больше 10 МБ".to_string();
+                return;
+            }
+            Ok(data) => data,
+            Err(_) => {
+                self.error_message = "❌ Не удалось прочитать файл".to_string();
+                return;
+            }
+        };
+
+        let mut entry = self.decrypted_entries[index].clone();
+        entry.attachments.push(Attachment {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "файл".to_string()),
+            data,
+        });
+        entry.modified_at = Some(SystemTime::now());
+
+        let mut tx = Transaction::new(
+            "Добавление вложения",
+            &mut self.decrypted_entries,
+            &mut self.trash,
+        );
+        tx.replace_entry(index, entry);
+        self.history.record(tx.finish());
+        self.save_vault();
+    }
+
+    // Сохраняем вложение в расшифрованном виде на диск
+    fn save_attachment(&mut self, index: usize, attachment_index: usize) {
+        let Some(attachment) = self.decrypted_entries[index]
+            .attachments
+            .get(attachment_index)
+        else {
+            return;
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(&attachment.name)
+            .save_file()
+        else {
+            return;
+        };
+
+        self.error_message = match std::fs::write(&path, &attachment.data) {
+            Ok(()) => format!("✅ Вложение сохранено: {}", path.display()),
+            Err(_) => "❌ Не удалось сохранить вложение".to_string(),
+        };
+    }
+
+    // Политика сайта, под шаблон которого подходит адрес
+    fn site_policy_for(&self, url: &str) -> Option<&PasswordPolicy> {
+        self.site_policies
+            .iter()
+            .find(|site| site.matches(url))
+            .map(|site| &site.policy)
+    }
+
+    fn generate_password(&self, policy: Option<&PasswordPolicy>) -> Result<String, String> {
+        Self::generate_with(&self.generator, policy)
+    }
+
+    // С политикой сайта длина берётся из генератора, а символы — из политики
+    fn generate_with(
+        generator: &PasswordGenerator,
+        policy: Option<&PasswordPolicy>,
+    ) -> Result<String, String> {
+        match policy {
+            Some(policy) => policy.generate(generator.options.length),
+            None => generator.generate(),
+        }
+    }
+
+    // Меняем пароль записи на сгенерированный: прежний уходит в историю, срок отсчитывается заново
+    fn rotate_password(&mut self, index: usize) {
+        let Some(mut entry) = self.decrypted_entries.get(index).cloned() else {
+            return;
+        };
+        let policy = entry
+            .policy
+            .as_ref()
+            .or_else(|| self.site_policy_for(&entry.url));
+        let password = match self.generate_password(policy) {
+            Ok(password) => password,
+            Err(e) => {
+                self.error_message = format!("❌ Генератор: {}", e);
+                return;
+            }
+        };
+
+        entry.set_password(password);
+        entry.modified_at = Some(SystemTime::now());
+        let service = entry.service.clone();
+        let mut tx = Transaction::new("Смена пароля", &mut self.decrypted_entries, &mut self.trash);
+        tx.replace_entry(index, entry);
+        self.history.record(tx.finish());
+        self.save_vault();
+        if self.error_message.starts_with('✅') {
+            self.error_message = format!("✅ Пароль для «{}» сменён", service);
+        }
+    }
+
+    // Окно с паролями, срок которых истёк или скоро истечёт
+    fn show_expiry_window(&mut self, ctx: &egui::Context) {
+        let now = SystemTime::now();
+        let mut due: Vec<(usize, ExpiryStatus)> = self
+            .decrypted_entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| entry.expiry_status(now).map(|status| (index, status)))
+            .collect();
+        if due.is_empty() {
+            self.show_expiry_summary = false;
+            return;
+        }
+        due.sort_by_key(|&(index, _)| self.decrypted_entries[index].expires_at());
+        let expired = due
+            .iter()
+            .filter(|(_, status)| *status == ExpiryStatus::Expired)
+            .count();
+
+        let mut open = self.show_expiry_summary;
+        let mut rotate_index = None;
+        let mut open_index = None;
+        egui::Window::new("⏰ Пора сменить пароли")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Срок истёк: {}, истекает в ближайшие {} дн.: {}",
+                    expired,
+                    vault::EXPIRY_WARNING.as_secs() / 86_400,
+                    due.len() - expired
+                ));
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("expiry_summary_grid")
+                            .striped(true)
+                            .num_columns(4)
+                            .show(ui, |ui| {
+                                for &(index, status) in &due {
+                                    let entry = &self.decrypted_entries[index];
+                                    let date = entry
+                                        .expires_at()
+                                        .map(widgets::format_date)
+                                        .unwrap_or_default();
+                                    ui.label(&entry.service);
+                                    ui.label(&entry.login);
+                                    match status {
+                                        ExpiryStatus::Expired => ui.colored_label(
+                                            ui.visuals().error_fg_color,
+                                            format!("⌛ истёк {}", date),
+                                        ),
+                                        ExpiryStatus::Soon => ui.colored_label(
+                                            ui.visuals().warn_fg_color,
+                                            format!("⏰ до {}", date),
+                                        ),
+                                    };
+                                    ui.horizontal(|ui| {
+                                        if ui
+                                            .small_button("🔄")
+                                            .on_hover_text(
+                                                "Сменить пароль (прежний сохранится в истории)",
+                                            )
+                                            .clicked()
+                                        {
+                                            rotate_index = Some(index);
+                                        }
+                                        if ui
+                                            .small_button("➡")
+                                            .on_hover_text("Открыть запись")
+                                            .clicked()
+                                        {
+                                            open_index = Some(index);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        self.show_expiry_summary = open;
+
+        if let Some(index) = rotate_index {
+            self.rotate_password(index);
+        }
+        if let Some(index) = open_index {
+            self.selected.clear();
+            self.selected.insert(index);
+            self.selection_anchor = Some(index);
+        }
+    }
+
+    // Окно политик паролей для сайтов
+    fn show_policies_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_policies;
+        let mut remove_index = None;
+        let mut add_clicked = false;
+
+        egui::Window::new("📏 Политики паролей")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.label("Шаблон адреса: example.com (домен и поддомены) или *.example.com");
+                ui.separator();
+
+                if self.site_policies.is_empty() {
+                    ui.label("Политики не заданы");
+                }
+                egui::Grid::new("site_policies_grid")
+                    .striped(true)
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for (i, site) in self.site_policies.iter().enumerate() {
+                            ui.label(&site.pattern);
+                            ui.monospace(site.policy.to_rules());
+                            if ui.small_button("🗑️").on_hover_text("Удалить").clicked()
+                            {
+                                remove_index = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_policy_pattern)
+                            .hint_text("example.com")
+                            .desired_width(140.0),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_policy_rules)
+                            .hint_text("minlength: 8; maxlength: 16; allowed: upper, lower, digit")
+                            .desired_width(260.0),
+                    );
+                    if ui.button("➕ Добавить").clicked() {
+                        add_clicked = true;
+                    }
+                });
+            });
+
+        if let Some(i) = remove_index {
+            self.site_policies.remove(i);
+            self.save_vault();
+        }
+
+        if add_clicked {
+            let pattern = self.new_policy_pattern.trim().to_lowercase();
+            if pattern.is_empty() {
+                self.error_message = "❌ Укажите шаблон адреса".to_string();
+            } else {
+                match PasswordPolicy::parse(&self.new_policy_rules) {
+                    Ok(policy) => {
+                        self.site_policies.push(SitePolicy { pattern, policy });
+                        self.new_policy_pattern.clear();
+                        self.new_policy_rules.clear();
+                        self.save_vault();
+                    }
+                    Err(e) => self.error_message = format!("❌ Политика: {}", e),
+                }
+            }
+        }
+
+        self.show_policies = open;
+    }
+
+    // Запоминаем время последнего копирования пароля или кода.
+    // Файл не перезаписываем: счётчики уйдут со следующим сохранением
+    fn mark_used(&mut self, index: usize) {
+        if let Some(entry) = self.decrypted_entries.get_mut(index) {
+            entry.last_used_at = Some(SystemTime::now());
+            entry.use_count = entry.use_count.saturating_add(1);
+            // Раздел «Недавние» и сортировка по использованию зависят от счётчиков
+            self.search_cache = None;
+            self.pending_save = true;
+        }
+    }
+
+    fn toggle_favorite(&mut self, index: usize) {
+        if let Some(mut entry) = self.decrypted_entries.get(index).cloned() {
+            entry.favorite = !entry.favorite;
+            let mut tx =
+                Transaction::new("Избранное", &mut self.decrypted_entries, &mut self.trash);
+            tx.replace_entry(index, entry);
+            self.history.record(tx.finish());
+            self.save_vault();
+        }
+    }
+
+    fn toggle_pin(&mut self, index: usize) {
+        if let Some(mut entry) = self.decrypted_entries.get(index).cloned() {
+            entry.pinned = !entry.pinned;
+            let mut tx = Transaction::new(
+                "Закрепление записи",
+                &mut self.decrypted_entries,
+                &mut self.trash,
+            );
+            tx.replace_entry(index, entry);
+            self.history.record(tx.finish());
+            self.save_vault();
+        }
+    }
+
+    // Выбор строки: клик — одна запись, Ctrl — переключение, Shift — диапазон
+    fn select_row(&mut self, visible: &[EntryMatch], position: usize, modifiers: egui::Modifiers) {
+        let index = visible[position].index;
+        let anchor = self
+            .selection_anchor
+            .and_then(|anchor| visible.iter().position(|m| m.index == anchor));
+
+        if modifiers.shift
+            && let Some(anchor) = anchor
+        {
+            if !modifiers.command {
+                self.selected.clear();
+            }
+            let (from, to) = (anchor.min(position), anchor.max(position));
+            self.selected
+                .extend(visible[from..=to].iter().map(|m| m.index));
+            return;
+        }
+
+        if modifiers.command {
+            if !self.selected.remove(&index) {
+                self.selected.insert(index);
+            }
+        } else if self.selected.len() == 1 && self.selected.contains(&index) {
+            self.selected.clear();
+        } else {
+            self.selected.clear();
+            self.selected.insert(index);
+        }
+        self.selection_anchor = Some(index);
+    }
+
+    // Панель массовых действий над выбранными записями
+    fn show_bulk_actions(&mut self, ui: &mut egui::Ui, visible: &[EntryMatch]) {
+        self.selected
+            .retain(|&index| index < self.decrypted_entries.len());
+
+        let mut action = None;
+        ui.horizontal(|ui| {
+            if self.selected.is_empty() {
+                if ui
+                    .small_button("☑ Выбрать все")
+                    .on_hover_text("Выбрать все найденные записи (Ctrl+A)")
+                    .clicked()
+                {
+                    self.selected = visible.iter().map(|m| m.index).collect();
+                }
+                return;
+            }
+
+            ui.label(format!("Выбрано: {}", self.selected.len()));
+            if ui
+                .small_button("✖")
+                .on_hover_text("Снять выбор (Esc)")
+                .clicked()
+            {
+                self.selected.clear();
+                return;
+            }
+            ui.separator();
+
+            if ui.button("🗑️ В корзину").clicked() {
+                action = Some(BulkAction::Trash);
+            }
+
+            ui.menu_button("📁 В папку", |ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.bulk_folder).hint_text("work/ops"));
+                if ui.button("Переместить").clicked() {
+                    action = Some(BulkAction::MoveToFolder(normalize_folder(
+                        &self.bulk_folder,
+                    )));
+                    ui.close();
+                }
+            });
+
+            ui.menu_button("🏷️ Теги", |ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.bulk_tags).hint_text("через запятую"));
+                ui.horizontal(|ui| {
+                    let tags = parse_tags(&self.bulk_tags);
+                    if ui
+                        .add_enabled(!tags.is_empty(), egui::Button::new("➕ Добавить"))
+                        .clicked()
+                    {
+                        action = Some(BulkAction::AddTags(tags.clone()));
+                        ui.close();
+                    }
+                    if ui
+                        .add_enabled(!tags.is_empty(), egui::Button::new("➖ Убрать"))
+                        .clicked()
+                    {
+                        action = Some(BulkAction::RemoveTags(tags));
+                        ui.close();
+                    }
+                });
+            });
+
+            ui.menu_button("🔄 Новые пароли", |ui| {
+                ui.label("Заменить пароли выбранных записей на случайные?");
+                if ui.button("Заменить").clicked() {
+                    action = Some(BulkAction::RegeneratePasswords);
+                    ui.close();
+                }
+            });
+
+            if ui
+                .button("📤 Экспорт")
+                .on_hover_text("Сохранить выбранные записи в отдельное хранилище")
+                .clicked()
+            {
+                self.export_selection();
+            }
+        });
+
+        if let Some(action) = action {
+            self.apply_bulk_action(action);
+        }
+    }
+
+    // Массовое изменение — одна операция в истории и одно сохранение
+    fn apply_bulk_action(&mut self, action: BulkAction) {
+        let count = self.selected.len();
+        let label = match &action {
+            BulkAction::Trash => format!("Удаление записей ({})", count),
+            BulkAction::MoveToFolder(_) => format!("Перемещение записей ({})", count),
+            BulkAction::AddTags(_) | BulkAction::RemoveTags(_) => {
+                format!("Изменение тегов ({})", count)
+            }
+            BulkAction::RegeneratePasswords => format!("Смена паролей ({})", count),
+        };
+
+        // Пароли генерируем заранее: если хоть один не получился, не меняем ни одной записи
+        let mut passwords = Vec::new();
+        if let BulkAction::RegeneratePasswords = action {
+            for &index in &self.selected {
+                let Some(entry) = self.decrypted_entries.get(index) else {
+                    continue;
+                };
+                let policy = entry
+                    .policy
+                    .as_ref()
+                    .or_else(|| self.site_policy_for(&entry.url));
+                match self.generate_password(policy) {
+                    Ok(password) => passwords.push(password),
+                    Err(e) => {
+                        passwords.zeroize();
+                        self.error_message = format!("❌ Генератор: {}", e);
+                        return;
+                    }
+                }
+            }
+        }
+        let mut passwords = passwords.into_iter();
+
+        let mut tx = Transaction::new(label, &mut self.decrypted_entries, &mut self.trash);
+        if let BulkAction::Trash = action {
+            // С конца, чтобы индексы оставшихся записей не сдвигались
+            for &index in self.selected.iter().rev() {
+                tx.trash_entry(index);
+            }
+            self.selected.clear();
+            self.hovered_password_index = None;
+        } else {
+            for &index in &self.selected {
+                let Some(mut entry) = tx.entries().get(index).cloned() else {
+                    continue;
+                };
+                match &action {
+                    BulkAction::MoveToFolder(folder) => entry.folder = folder.clone(),
+                    BulkAction::AddTags(tags) => {
+                        for tag in tags {
+                            if !entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                                entry.tags.push(tag.clone());
+                            }
+                        }
+                    }
+                    BulkAction::RemoveTags(tags) => entry.tags.retain(|t| {
+                        !tags
+                            .iter()
+                            .any(|tag| t.to_lowercase() == tag.to_lowercase())
+                    }),
+                    BulkAction::RegeneratePasswords => {
+                        if let Some(password) = passwords.next() {
+                            entry.set_password(password);
+                        }
+                    }
+                    BulkAction::Trash => {}
+                }
+                entry.modified_at = Some(SystemTime::now());
+                tx.replace_entry(index, entry);
+            }
+        }
+        self.history.record(tx.finish());
+        self.save_vault();
+    }
+
+    // Импорт — одна операция в истории и одно сохранение
+    fn apply_import(&mut self, mut plan: Plan) {
+        let (added, merged) = (plan.added.len(), plan.merged.len());
+        let mut tx = Transaction::new(
+            format!("Импорт записей ({})", added + merged),
+            &mut self.decrypted_entries,
+            &mut self.trash,
+        );
+        for (index, entry) in plan.merged.drain(..) {
+            tx.replace_entry(index, entry);
+        }
+        for entry in plan.added.drain(..) {
+            tx.push_entry(entry);
+        }
+        self.history.record(tx.finish());
+        self.save_vault();
+        if self.error_message.starts_with('✅') {
+            self.error_message = format!(
+                "✅ Импорт: добавлено {}, объединено {}, пропущено {}",
+                added, merged, plan.skipped
+            );
+        }
+    }
+
+    // Экспортируем выбранные записи в новое хранилище с тем же мастер-паролем
+    fn export_selection(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Хранилище", &["vault"])
+            .set_file_name("export.vault")
+            .save_file()
+        else {
+            return;
+        };
+
+        let data = VaultData {
+            entries: self
+                .selected
+                .iter()
+                .filter_map(|&index| self.decrypted_entries.get(index).cloned())
+                .collect(),
+            ..VaultData::default()
+        };
+        let result = storage::create_encrypted_vault(&data, &self.master_password, &path);
+        let count = data.entries.len();
+        let mut data = data;
+        data.entries.zeroize();
+
+        self.error_message = match result {
+            Ok(_) => format!("✅ Экспортировано записей: {}", count),
+            Err(e) => format!("❌ Ошибка экспорта: {}", e),
+        };
+    }
+
+    fn show_health_window(&mut self, ctx: &egui::Context) {
+        if self.health.needs_update(self.entries_revision) {
+            let scores: Vec<u8> = (0..self.decrypted_entries.len())
+                .map(|index| self.entry_strength(index).score)
+                .collect();
+            let breached: Vec<bool> = (0..self.decrypted_entries.len())
+                .map(|index| self.entry_breached(index))
+                .collect();
+            let report = health::analyze(
+                &self.decrypted_entries,
+                &scores,
+                &breached,
+                &self.two_factor,
+                self.health.max_age_months,
+            );
+            self.health.set_report(report, self.entries_revision);
+        }
+
+        // Переход к записи: выделяем её, чтобы открылась панель подробностей
+        if let Some(index) = self.health.show(
+            ctx,
+            &self.decrypted_entries,
+            self.breach_filter.as_ref(),
+            &self.two_factor,
+        ) {
+            self.selected.clear();
+            self.selected.insert(index);
+            self.selection_anchor = Some(index);
+        }
+        if let Some(directory) = self.health.take_updated_directory() {
+            self.two_factor = directory;
+            self.health.invalidate();
+        }
+    }
+
+    // Оценка надёжности записи; пересчитывается, только если изменились пароль, сервис или логин
+    fn entry_strength(&mut self, index: usize) -> &Estimate {
+        let entry = &self.decrypted_entries[index];
+        let fingerprint =
+            self.fingerprint_hasher
+                .hash_one((&entry.password, &entry.service, &entry.login));
+        let cached = self
+            .strength_cache
+            .entry(index)
+            .or_insert_with(|| (fingerprint, estimate_strength(entry)));
+        if cached.0 != fingerprint {
+            *cached = (fingerprint, estimate_strength(entry));
+        }
+        &cached.1
+    }
+
+    // Найден ли пароль записи в базе утечек (если фильтр построен)
+    fn entry_breached(&mut self, index: usize) -> bool {
+        let Some(filter) = &self.breach_filter else {
+            return false;
+        };
+        let entry = &self.decrypted_entries[index];
+        let fingerprint = self.fingerprint_hasher.hash_one(&entry.password);
+        if let Some(&(cached, breached)) = self.breach_cache.get(&index)
+            && cached == fingerprint
+        {
+            return breached;
+        }
+        // Ошибку чтения фильтра не считаем утечкой
+        let breached = filter.contains(&entry.password).unwrap_or(false);
+        self.breach_cache.insert(index, (fingerprint, breached));
+        breached
+    }
+
+    // Сортируем найденные записи по колонке таблицы
+    fn sort_matches(&mut self, matches: &mut [EntryMatch], column: Column) {
+        let strength: Vec<f64> = if column == Column::Strength {
+            (0..self.decrypted_entries.len())
+                .map(|index| self.entry_strength(index).guesses_log10)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let entries = &self.decrypted_entries;
+        let text_key = |index: usize| -> String {
+            let entry = &entries[index];
+            match column {
+                Column::Service => entry.service.to_lowercase(),
+                Column::Login => entry.login.to_lowercase(),
+                Column::Url => entry.url.to_lowercase(),
+                Column::Folder => entry.folder.to_lowercase(),
+                Column::Tags => entry.tags.join(",").to_lowercase(),
+                _ => String::new(),
+            }
+        };
+
+        match column {
+            Column::Modified => matches.sort_by_key(|m| entries[m.index].modified_at),
+            Column::LastUsed => matches.sort_by_key(|m| entries[m.index].last_used_at),
+            Column::UseCount => matches.sort_by_key(|m| entries[m.index].use_count),
+            // Записи без срока — в конце
+            Column::Expiry => matches.sort_by_key(|m| {
+                let expires_at = entries[m.index].expires_at();
+                (expires_at.is_none(), expires_at)
+            }),
+            Column::Strength => {
+                matches.sort_by(|a, b| strength[a.index].total_cmp(&strength[b.index]))
+            }
+            Column::Totp => matches.sort_by_key(|m| entries[m.index].otp.is_none()),
+            Column::Password => {}
+            _ => matches.sort_by_cached_key(|m| text_key(m.index)),
+        }
+
+        if !self.table_view.sort_ascending {
+            matches.reverse();
+        }
+    }
+
+    // Диалог редактирования записи
+    // Отмена, повтор или корзина могли сдвинуть записи — находим редактируемую заново.
+    // false, только если изменилась или исчезла сама эта запись
+    fn relocate_edit(&self, edit: &mut EditEntry) -> bool {
+        if edit.revision == self.history.revision() {
+            return true;
+        }
+        let is_origin = |entry: &Entry| RecordKey::of(entry) == edit.origin;
+        let index = match self.decrypted_entries.get(edit.index) {
+            Some(entry) if is_origin(entry) => Some(edit.index),
+            _ => self.decrypted_entries.iter().position(is_origin),
+        };
+        let Some(index) = index else {
+            return false;
+        };
+        edit.index = index;
+        edit.revision = self.history.revision();
+        true
+    }
+
+    fn show_edit_dialog_ui(&mut self, ctx: &egui::Context) {
+        // Создаем временную переменную вместо прямого заимствования
+        let mut show_dialog = self.show_edit_dialog;
+        let Some(mut edit) = self.edit_entry.take() else {
+            self.show_edit_dialog = false;
+            return;
+        };
+        if !self.relocate_edit(&mut edit) {
+            edit.zeroize();
+            self.show_edit_dialog = false;
+            self.error_message =
+                "⚠️ Запись изменилась или удалена — редактирование отменено".to_string();
+            return;
+        }
+        let mut save_clicked = false;
+
+        // Политика записи важнее политики сайта по шаблону адреса
+        let (policy, policy_error) = match parse_policy_field(&edit.policy) {
+            Ok(Some(policy)) => (Some(policy), None),
+            Ok(None) => (self.site_policy_for(&edit.url).cloned(), None),
+            Err(e) => (None, Some(e)),
+        };
+
+        // Используем временную переменную
+        egui::Window::new("✏️ Редактировать запись")
+            .open(&mut show_dialog)
+            .resizable(false)
+            .default_width(400.0)
+            .show(ctx, |ui| {
+                ui.label("🌐 Сервис:");
+                ui.text_edit_singleline(&mut edit.service);
+
+                ui.label("👤 Логин:");
+                ui.text_edit_singleline(&mut edit.login);
 
                 ui.label("🔑 Пароль:");
-                ui.text_edit_singleline(&mut password);
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut edit.password);
+                    if ui.button("🔄").on_hover_text("Сгенерировать").clicked() {
+                        let generated = match &policy {
+                            Some(policy) => policy.generate(self.generator.options.length),
+                            None => self.generator.generate(),
+                        };
+                        if let Ok(password) = generated {
+                            edit.password.zeroize();
+                            edit.password = password;
+                        }
+                    }
+                    if ui
+                        .button("🎲")
+                        .on_hover_text("Настроить генератор")
+                        .clicked()
+                    {
+                        self.generator_target = GeneratorTarget::EditEntry;
+                        self.generator.show = true;
+                    }
+                });
+
+                if !edit.password.is_empty() {
+                    let estimate =
+                        strength::estimate(&edit.password, &[&edit.service, &edit.login]);
+                    widgets::strength_meter(ui, &estimate);
+                }
+
+                // Предупреждаем, если введённый вручную пароль нарушает политику
+                if let Some(policy) = &policy {
+                    for problem in policy.check(&edit.password) {
+                        ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", problem));
+                    }
+                }
+
+                ui.label("🔢 2FA (otpauth:// или секрет):");
+                ui.text_edit_singleline(&mut edit.otp);
+
+                ui.label("🔗 URL:");
+                ui.text_edit_singleline(&mut edit.url);
+
+                ui.label("📁 Папка:");
+                ui.text_edit_singleline(&mut edit.folder);
+
+                ui.label("🏷️ Теги (через запятую):");
+                ui.text_edit_singleline(&mut edit.tags);
+
+                ui.label("📏 Политика пароля (passwordrules):");
+                ui.add(
+                    egui::TextEdit::singleline(&mut edit.policy)
+                        .hint_text("minlength: 12; maxlength: 32; required: digit; allowed: [-_]"),
+                );
+                if let Some(e) = &policy_error {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
+                }
+
+                ui.label("⏳ Срок действия пароля:");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut edit.expiry_kind, ExpiryKind::None, "Бессрочно");
+                    ui.radio_value(&mut edit.expiry_kind, ExpiryKind::Date, "До даты");
+                    ui.radio_value(&mut edit.expiry_kind, ExpiryKind::Interval, "Менять каждые");
+                });
+                match edit.expiry_kind {
+                    ExpiryKind::None => {}
+                    ExpiryKind::Date => {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut edit.expiry_date)
+                                .hint_text("ГГГГ-ММ-ДД"),
+                        );
+                        if !edit.expiry_date.trim().is_empty()
+                            && widgets::parse_date(&edit.expiry_date).is_none()
+                        {
+                            ui.colored_label(
+                                ui.visuals().error_fg_color,
+                                "Неверная дата, нужен формат ГГГГ-ММ-ДД",
+                            );
+                        }
+                    }
+                    ExpiryKind::Interval => {
+                        ui.add(
+                            egui::DragValue::new(&mut edit.expiry_days)
+                                .range(1..=3650)
+                                .suffix(" дн."),
+                        );
+                    }
+                }
+
+                ui.label("📝 Заметки:");
+                ui.text_edit_multiline(&mut edit.notes);
+
+                ui.horizontal(|ui| {
+                    ui.label("🧾 Доп. поля:");
+                    if ui
+                        .small_button("➕")
+                        .on_hover_text("Добавить поле")
+                        .clicked()
+                    {
+                        edit.fields.push(CustomField::default());
+                    }
+                });
+                let mut remove_field = None;
+                for (i, field) in edit.fields.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut field.name)
+                                .hint_text("Название")
+                                .desired_width(110.0),
+                        );
+                        ui.add(
+                            egui::TextEdit::singleline(&mut field.value)
+                                .password(field.protected)
+                                .desired_width(170.0),
+                        );
+                        ui.checkbox(&mut field.protected, "🔒")
+                            .on_hover_text("Скрывать значение");
+                        if ui.small_button("🗑️").on_hover_text("Удалить").clicked() {
+                            remove_field = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove_field {
+                    edit.fields.remove(i).zeroize();
+                }
 
                 ui.separator();
 
                 ui.horizontal(|ui| {
                     if ui.button("✅ Сохранить").clicked() {
-                        // Сохраняем изменения в основном списке
-                        if index < self.decrypted_entries.len() {
-                            self.decrypted_entries[index] = Entry {
-                                service: service,
-                                login: login,
-                                password: password,
-                            };
-                            self.show_edit_dialog = false; // Закрываем диалог
-                            self.error_message.clear();
-                            self.save_vault();
-                        }
+                        save_clicked = true;
                     }
 
                     if ui.button("❌ Отмена").clicked() {
@@ -319,10 +2224,198 @@ impl PasswordManager {
                 });
             });
 
+        // Сохраняем изменения в основном списке
+        if save_clicked && let Some(mut entry) = self.decrypted_entries.get(edit.index).cloned() {
+            match edit.apply_to(&mut entry) {
+                Ok(()) => {
+                    let mut tx = Transaction::new(
+                        "Редактирование записи",
+                        &mut self.decrypted_entries,
+                        &mut self.trash,
+                    );
+                    tx.replace_entry(edit.index, entry);
+                    self.history.record(tx.finish());
+
+                    self.show_edit_dialog = false; // Закрываем диалог
+                    self.error_message.clear();
+                    self.save_vault();
+                }
+                Err(e) => {
+                    self.error_message = format!("❌ {}", e);
+                }
+            }
+        }
+
         // Обновляем состояние после закрытия диалога
-        self.show_edit_dialog = show_dialog;
-        if !show_dialog {
-            self.edit_entry = None;
+        self.show_edit_dialog = show_dialog && self.show_edit_dialog;
+        if self.show_edit_dialog {
+            // Сохраняем введённые данные между кадрами
+            self.edit_entry = Some(edit);
+        } else {
+            edit.zeroize();
+        }
+    }
+
+    // Окно корзины: восстановление и окончательное удаление
+    fn show_trash_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_trash;
+        let mut restore_index = None;
+        let mut purge_index = None;
+        let mut empty_trash = false;
+
+        egui::Window::new("🗑️ Корзина")
+            .open(&mut open)
+            .resizable(true)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                if self.trash.is_empty() {
+                    ui.label("Корзина пуста");
+                    return;
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("trash_grid")
+                            .striped(true)
+                            .spacing([20.0, 8.0])
+                            .show(ui, |ui| {
+                                ui.strong("🌐 Сервис");
+                                ui.strong("👤 Логин");
+                                ui.strong("🕒 Удалено");
+                                ui.label("");
+                                ui.end_row();
+
+                                for (index, trashed) in self.trash.iter().enumerate() {
+                                    ui.label(&trashed.entry.service);
+                                    ui.label(&trashed.entry.login);
+                                    ui.label(widgets::format_time(trashed.deleted_at));
+                                    ui.horizontal(|ui| {
+                                        if ui.button("♻️").on_hover_text("Восстановить").clicked()
+                                        {
+                                            restore_index = Some(index);
+                                        }
+                                        if ui
+                                            .button("❌")
+                                            .on_hover_text("Удалить навсегда")
+                                            .clicked()
+                                        {
+                                            purge_index = Some(index);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.separator();
+                if ui.button("🧹 Очистить корзину").clicked() {
+                    empty_trash = true;
+                }
+            });
+
+        if let Some(index) = restore_index {
+            self.restore_from_trash(index);
+        }
+        if let Some(index) = purge_index {
+            let mut tx = Transaction::new(
+                "Удаление из корзины",
+                &mut self.decrypted_entries,
+                &mut self.trash,
+            );
+            tx.remove_trash(index);
+            self.history.record(tx.finish());
+            self.save_vault();
+        }
+        if empty_trash {
+            let mut tx = Transaction::new(
+                "Очистка корзины",
+                &mut self.decrypted_entries,
+                &mut self.trash,
+            );
+            while tx.remove_trash(0).is_some() {}
+            self.history.record(tx.finish());
+            self.save_vault();
+        }
+
+        self.show_trash = open;
+    }
+
+    // Перемещаем запись в корзину
+    fn move_to_trash(&mut self, index: usize) {
+        self.selected.clear();
+        let mut tx = Transaction::new(
+            "Удаление записи",
+            &mut self.decrypted_entries,
+            &mut self.trash,
+        );
+        tx.trash_entry(index);
+        self.history.record(tx.finish());
+    }
+
+    // Возвращаем запись из корзины в список
+    fn restore_from_trash(&mut self, index: usize) {
+        let mut tx = Transaction::new(
+            "Восстановление записи",
+            &mut self.decrypted_entries,
+            &mut self.trash,
+        );
+        tx.restore_entry(index);
+        self.history.record(tx.finish());
+        self.save_vault();
+    }
+
+    // Отменяем последнюю операцию и сохраняем хранилище
+    pub fn undo(&mut self) {
+        if let Some(label) = self
+            .history
+            .undo(&mut self.decrypted_entries, &mut self.trash)
+        {
+            self.hovered_password_index = None;
+            self.selected.clear();
+            self.save_vault();
+            if self.error_message.starts_with('✅') {
+                self.error_message = format!("↶ Отменено: {}", label);
+            }
+        }
+    }
+
+    // Повторяем отменённую операцию и сохраняем хранилище
+    pub fn redo(&mut self) {
+        if let Some(label) = self
+            .history
+            .redo(&mut self.decrypted_entries, &mut self.trash)
+        {
+            self.hovered_password_index = None;
+            self.selected.clear();
+            self.save_vault();
+            if self.error_message.starts_with('✅') {
+                self.error_message = format!("↷ Повторено: {}", label);
+            }
+        }
+    }
+
+    // Удаляем из корзины записи старше заданного числа дней
+    pub fn purge_trash(&mut self, retention_days: u32) {
+        if retention_days == 0 {
+            return;
+        }
+        let retention = Duration::from_secs(retention_days as u64 * 86_400);
+        let now = SystemTime::now();
+        let before = self.trash.len();
+
+        self.trash.retain_mut(|trashed| {
+            let expired = now
+                .duration_since(trashed.deleted_at)
+                .is_ok_and(|age| age > retention);
+            if expired {
+                trashed.zeroize();
+            }
+            !expired
+        });
+
+        if self.trash.len() != before {
+            self.save_vault();
         }
     }
 
@@ -334,18 +2427,45 @@ impl PasswordManager {
             return;
         }
 
+        let otp = match parse_otp_field(&self.new_otp) {
+            Ok(otp) => otp,
+            Err(e) => {
+                self.error_message = format!("❌ 2FA: {}", e);
+                return;
+            }
+        };
+
         let new_entry = Entry {
             service: self.new_service.clone(),
             login: self.new_login.clone(),
             password: self.new_password.clone(),
+            otp,
+            url: self.new_url.trim().to_string(),
+            notes: self.new_notes.clone(),
+            tags: parse_tags(&self.new_tags),
+            folder: normalize_folder(&self.new_folder),
+            created_at: Some(SystemTime::now()),
+            modified_at: Some(SystemTime::now()),
+            ..Entry::default()
         };
 
-        self.decrypted_entries.push(new_entry);
+        let mut tx = Transaction::new(
+            "Добавление записи",
+            &mut self.decrypted_entries,
+            &mut self.trash,
+        );
+        tx.push_entry(new_entry);
+        self.history.record(tx.finish());
 
         // Очищаем поля
         self.new_service.clear();
         self.new_login.clear();
         self.new_password.clear();
+        self.new_otp.zeroize();
+        self.new_url.clear();
+        self.new_notes.zeroize();
+        self.new_tags.clear();
+        self.new_folder.clear();
         self.error_message.clear();
 
         // Автоматически сохраняем изменения
@@ -354,10 +2474,31 @@ impl PasswordManager {
 
     // Сохраняем хранилище
     fn save_vault(&mut self) {
+        // Без мастер-пароля записи уже стёрты: сохранение затёрло бы файл пустым хранилищем
+        if self.master_password.is_empty() {
+            self.error_message = "❌ Хранилище заблокировано — сохранение невозможно".to_string();
+            return;
+        }
+        // Любое сохранение следует за изменением записей — сбрасываем кэш поиска
+        self.entries_revision += 1;
         if let (Some(path), ref master_password) = (&self.vault_path, &self.master_password) {
-            match storage::create_encrypted_vault(&self.decrypted_entries, master_password, path) {
+            // Временно переносим данные в VaultData, чтобы не создавать лишних копий секретов
+            let data = VaultData {
+                entries: std::mem::take(&mut self.decrypted_entries),
+                trash: std::mem::take(&mut self.trash),
+                table_view: std::mem::take(&mut self.table_view),
+                site_policies: std::mem::take(&mut self.site_policies),
+            };
+            let result = storage::create_encrypted_vault(&data, master_password, path);
+            self.decrypted_entries = data.entries;
+            self.trash = data.trash;
+            self.table_view = data.table_view;
+            self.site_policies = data.site_policies;
+
+            match result {
                 Ok(new_vault) => {
                     self.current_vault = Some(new_vault);
+                    self.pending_save = false;
                     self.error_message = "✅ Сохранено!".to_string();
                 }
                 Err(e) => {
@@ -369,32 +2510,213 @@ impl PasswordManager {
         }
     }
 
-    // Генерируем случайный пароль
-    fn generate_password(&self, length: usize) -> String {
-        use rand::{Rng, distr::Alphanumeric};
-        rand::rng()
-            .sample_iter(&Alphanumeric)
-            .take(length)
-            .map(char::from)
-            .collect()
+    // Импортируем секреты 2FA из изображения с QR-кодом
+    fn import_otp_from_qr(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Изображения", &["png", "jpg", "jpeg", "bmp", "gif", "webp"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let mut configs = Vec::new();
+        match qr::decode_qr_image(&path) {
+            Ok(texts) => {
+                for text in texts {
+                    match qr::parse_otp_payload(&text) {
+                        Ok(parsed) => configs.extend(parsed),
+                        Err(e) => {
+                            self.error_message = format!("❌ QR: {}", e);
+                            return;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                self.error_message = format!("❌ QR: {}", e);
+                return;
+            }
+        }
+
+        // Один код — заполняем форму добавления
+        if configs.len() == 1 {
+            let config = configs.remove(0);
+            if self.new_service.is_empty() {
+                self.new_service = config.issuer.clone();
+            }
+            if self.new_login.is_empty() {
+                self.new_login = config.account.clone();
+            }
+            self.new_otp = config.to_uri();
+            self.error_message = "✅ Код 2FA загружен из QR".to_string();
+            return;
+        }
+
+        // Пакет миграции — привязываем коды к существующим записям или создаём новые
+        let mut attached = 0;
+        let mut created = 0;
+        let mut tx = Transaction::new(
+            "Импорт кодов 2FA",
+            &mut self.decrypted_entries,
+            &mut self.trash,
+        );
+        for config in configs {
+            let existing = tx.entries().iter().position(|entry| {
+                entry.otp.is_none()
+                    && entry.service.eq_ignore_ascii_case(&config.issuer)
+                    && entry.login == config.account
+            });
+            match existing {
+                Some(index) => {
+                    let mut entry = tx.entries()[index].clone();
+                    entry.otp = Some(config);
+                    tx.replace_entry(index, entry);
+                    attached += 1;
+                }
+                None => {
+                    let service = if config.issuer.is_empty() {
+                        config.account.clone()
+                    } else {
+                        config.issuer.clone()
+                    };
+                    tx.push_entry(Entry {
+                        service,
+                        login: config.account.clone(),
+                        otp: Some(config),
+                        created_at: Some(SystemTime::now()),
+                        modified_at: Some(SystemTime::now()),
+                        ..Entry::default()
+                    });
+                    created += 1;
+                }
+            }
+        }
+        self.history.record(tx.finish());
+
+        self.save_vault();
+        if self.error_message.starts_with('✅') {
+            self.error_message = format!(
+                "✅ Импортировано кодов 2FA: {} (привязано к записям: {}, новых записей: {})",
+                attached + created,
+                attached,
+                created
+            );
+        }
+    }
+
+    // Рисуем QR-код с otpauth URI записи
+    fn open_qr_preview(&mut self, ctx: &egui::Context, service: &str, config: &OtpConfig) {
+        const SCALE: usize = 6;
+        const QUIET_ZONE: usize = 4;
+
+        let mut uri = config.to_uri();
+        let result = qr::encode_qr(&uri);
+        uri.zeroize();
+
+        match result {
+            Ok((width, modules)) => {
+                let size = (width + QUIET_ZONE * 2) * SCALE;
+                let mut image =
+                    egui::ColorImage::new([size, size], vec![egui::Color32::WHITE; size * size]);
+                for (i, _) in modules.iter().enumerate().filter(|(_, dark)| **dark) {
+                    let (mx, my) = (i % width + QUIET_ZONE, i / width + QUIET_ZONE);
+                    for y in my * SCALE..(my + 1) * SCALE {
+                        for x in mx * SCALE..(mx + 1) * SCALE {
+                            image.pixels[y * size + x] = egui::Color32::BLACK;
+                        }
+                    }
+                }
+                let texture = ctx.load_texture("otp_qr", image, egui::TextureOptions::NEAREST);
+                self.qr_preview = Some((service.to_string(), texture));
+            }
+            Err(e) => {
+                self.error_message = format!("❌ QR: {}", e);
+            }
+        }
+    }
+
+    fn show_qr_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        if let Some((service, texture)) = &self.qr_preview {
+            egui::Window::new(format!("📱 QR-код 2FA: {}", service))
+                .open(&mut open)
+                .resizable(false)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "⚠️ QR-код содержит секрет 2FA. Не показывайте его посторонним.",
+                    );
+                    ui.image((texture.id(), texture.size_vec2()));
+                });
+        }
+        if !open {
+            self.qr_preview = None;
+        }
+    }
+
+    // Переходим к следующему HOTP-коду и сохраняем счётчик
+    fn advance_hotp_counter(&mut self, index: usize) {
+        if let Some(otp) = self
+            .decrypted_entries
+            .get_mut(index)
+            .and_then(|entry| entry.otp.as_mut())
+        {
+            otp.counter += 1;
+            self.save_vault();
+        }
     }
 
     // Устанавливаем текущее хранилище
     pub fn set_vault(
         &mut self,
         vault: Vault,
-        entries: Vec<Entry>,
+        data: VaultData,
         master_password: String,
         path: PathBuf,
     ) {
         self.current_vault = Some(vault);
-        self.decrypted_entries = entries;
+        self.decrypted_entries = data.entries;
+        self.trash = data.trash;
+        self.table_view = data.table_view;
+        self.site_policies = data.site_policies;
+        self.selected.clear();
+        self.entries_revision += 1;
         self.master_password = master_password;
         self.vault_path = Some(path);
+
+        // Фильтр утечек загружается, если он уже построен
+        self.breach_filter = BreachFilter::default_path()
+            .filter(|path| path.exists())
+            .and_then(|path| BreachFilter::open(&path).ok());
+        self.breach_cache.clear();
+        self.two_factor = Directory::load_default();
+
+        // Сразу напоминаем о паролях, которые пора сменить
+        let now = SystemTime::now();
+        self.show_expiry_summary = self
+            .decrypted_entries
+            .iter()
+            .any(|entry| entry.expiry_status(now).is_some());
+    }
+
+    // Записываем отложенные изменения вида, если они есть
+    pub fn flush_pending(&mut self) {
+        if self.pending_save {
+            self.save_vault();
+        }
+    }
+
+    // Выход из хранилища: сначала дописываем отложенное, затем стираем секреты
+    fn lock(&mut self) {
+        self.flush_pending();
+        self.pending_save = false;
+        self.zeroize();
     }
 
     // Очищаем данные при выходе (с zeroize)
     pub fn clear(&mut self) {
+        self.flush_pending();
         self.clear_sensitive_data();
         self.current_vault = None;
         self.vault_path = None;
@@ -404,6 +2726,31 @@ impl PasswordManager {
         self.show_edit_dialog = false;
         self.hovered_password_index = None;
         self.error_message.clear();
+        self.show_trash = false;
+        self.history.zeroize();
+        self.search_query.clear();
+        self.search_cache = None;
+        self.strength_cache.clear();
+        self.breach_cache.clear();
+        self.table_view = TableView::default();
+        self.pending_save = false;
+        self.selected.clear();
+        self.selection_anchor = None;
+        self.qr_preview = None;
+        self.generator.zeroize();
+        self.generator.show = false;
+        self.site_policies.clear();
+        self.show_policies = false;
+        self.change_master.zeroize();
+        self.change_master.show = false;
+        self.health.clear();
+        self.show_expiry_summary = false;
+        self.import.zeroize();
+        self.import.show = false;
+        self.plain_export.zeroize();
+        self.plain_export.show = false;
+        self.kdbx_export.zeroize();
+        self.kdbx_export.show = false;
     }
 
     // Безопасная очистка конфиденциальных данных
@@ -411,8 +2758,10 @@ impl PasswordManager {
         // Очищаем мастер-пароль
         self.master_password.zeroize();
 
-        // Очищаем новый пароль
+        // Очищаем новый пароль и секрет 2FA
         self.new_password.zeroize();
+        self.new_otp.zeroize();
+        self.new_notes.zeroize();
 
         // Очищаем все записи
         for entry in &mut self.decrypted_entries {
@@ -420,9 +2769,152 @@ impl PasswordManager {
         }
         self.decrypted_entries.clear();
 
+        // Очищаем корзину
+        self.trash.zeroize();
+        self.trash.clear();
+
         // Очищаем данные редактирования
         if let Some(ref mut edit_entry) = self.edit_entry {
             edit_entry.zeroize();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::CharRule;
+
+    #[test]
+    fn logout_keeps_deferred_changes() {
+        let path =
+            std::env::temp_dir().join(format!("shroombrella-logout-{}.vault", std::process::id()));
+        let mut data = VaultData::default();
+        data.entries.push(Entry {
+            service: "mail".to_string(),
+            password: "correct horse".to_string(),
+            ..Entry::default()
+        });
+        storage::create_encrypted_vault(&data, "master", &path).unwrap();
+        let vault = storage::load_vault_from_path(&path).unwrap();
+        let data = vault.decrypt("master").unwrap();
+
+        let mut manager = PasswordManager::new();
+        manager.set_vault(vault, data, "master".to_string(), path.clone());
+        manager.mark_used(0);
+        assert!(manager.pending_save);
+
+        // Так выходит пользователь: кнопка «Выйти» в ui(), затем clear() из app.rs
+        manager.lock();
+        manager.clear();
+
+        let loaded = storage::load_vault_from_path(&path).and_then(|vault| vault.decrypt("master"));
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.entries.len(), 1);
+        assert_eq!(loaded.entries[0].password, "correct horse");
+        assert_eq!(loaded.entries[0].use_count, 1);
+    }
+
+    #[test]
+    fn usage_counters_are_saved_in_batches() {
+        let path =
+            std::env::temp_dir().join(format!("shroombrella-usage-{}.vault", std::process::id()));
+        let mut data = VaultData::default();
+        data.entries.push(Entry {
+            service: "mail".to_string(),
+            ..Entry::default()
+        });
+        storage::create_encrypted_vault(&data, "master", &path).unwrap();
+        let vault = storage::load_vault_from_path(&path).unwrap();
+        let data = vault.decrypt("master").unwrap();
+        let mut manager = PasswordManager::new();
+        manager.set_vault(vault, data, "master".to_string(), path.clone());
+
+        let use_count = || {
+            storage::load_vault_from_path(&path)
+                .and_then(|vault| vault.decrypt("master"))
+                .map(|data| data.entries[0].use_count)
+        };
+        // Копирования не перезаписывают файл…
+        manager.mark_used(0);
+        manager.mark_used(0);
+        let before = use_count();
+        // …а уходят в него одним сохранением, например при закрытии окна
+        manager.flush_pending();
+        let after = use_count();
+        assert!(!manager.pending_save);
+        manager.clear();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(before.unwrap(), 0);
+        assert_eq!(after.unwrap(), 2);
+    }
+
+    #[test]
+    fn edit_follows_its_entry() {
+        let mut manager = PasswordManager::new();
+        for (service, second) in [("a", 1), ("b", 2), ("c", 3)] {
+            manager.decrypted_entries.push(Entry {
+                service: service.to_string(),
+                created_at: Some(std::time::UNIX_EPOCH + Duration::from_secs(second)),
+                ..Entry::default()
+            });
+        }
+        let mut edit =
+            EditEntry::from_entry(2, manager.history.revision(), &manager.decrypted_entries[2]);
+
+        // Удаление другой записи сдвигает индекс, но правку не отменяет
+        manager.move_to_trash(0);
+        assert!(manager.relocate_edit(&mut edit));
+        assert_eq!(edit.index, 1);
+        // Отмена возвращает запись на место
+        manager.undo();
+        assert!(manager.relocate_edit(&mut edit));
+        assert_eq!(edit.index, 2);
+
+        // Изменение самой записи отменяет правку
+        let mut changed = manager.decrypted_entries[2].clone();
+        changed.modified_at = Some(SystemTime::now());
+        let mut tx = Transaction::new("Правка", &mut manager.decrypted_entries, &mut manager.trash);
+        tx.replace_entry(2, changed);
+        manager.history.record(tx.finish());
+        assert!(!manager.relocate_edit(&mut edit));
+    }
+
+    #[test]
+    fn locked_manager_does_not_save() {
+        let path =
+            std::env::temp_dir().join(format!("shroombrella-locked-{}.vault", std::process::id()));
+        let mut manager = PasswordManager::new();
+        manager.vault_path = Some(path.clone());
+        manager.save_vault();
+        assert!(!path.exists());
+        assert!(manager.error_message.starts_with('❌'));
+    }
+
+    #[test]
+    fn bulk_regeneration_is_all_or_nothing() {
+        let mut manager = PasswordManager::new();
+        for service in ["mail", "bank"] {
+            manager.decrypted_entries.push(Entry {
+                service: service.to_string(),
+                password: format!("{}-old", service),
+                ..Entry::default()
+            });
+        }
+        // Политика, которой не удовлетворить: два обязательных класса при длине 1
+        manager.decrypted_entries[1].policy = Some(PasswordPolicy {
+            max_length: Some(1),
+            required: vec![vec![CharRule::Upper], vec![CharRule::Digit]],
+            ..PasswordPolicy::default()
+        });
+        manager.selected.extend([0, 1]);
+
+        manager.apply_bulk_action(BulkAction::RegeneratePasswords);
+        assert!(manager.error_message.starts_with("❌ Генератор:"));
+        assert_eq!(manager.decrypted_entries[0].password, "mail-old");
+        assert_eq!(manager.decrypted_entries[1].password, "bank-old");
+        assert!(manager.history.undo_label().is_none());
+        assert!(!manager.pending_save);
+    }
+}
@@ -1,4 +1,6 @@
+use crate::generator::{GeneratorOptions, GeneratorProfile};
 use crate::settings::Settings;
+use crate::strength::{self, MasterPasswordPolicy};
 use dirs;
 use eframe::egui;
 
@@ -125,6 +127,46 @@ impl SettingsWindow {
             self.buffer_settings.ui_scale
         ));
 
+        ui.separator();
+
+        // Автоочистка корзины
+        ui.label("🗑️ Автоочистка корзины:");
+        ui.add(
+            egui::DragValue::new(&mut self.buffer_settings.trash_retention_days)
+                .range(0..=3650)
+                .suffix(" дн."),
+        );
+        ui.label("Записи старше указанного срока удаляются при входе (0 — никогда)");
+
+        ui.separator();
+
+        // Требования к мастер-паролю
+        ui.label("🔐 Требования к мастер-паролю:");
+        let policy = &mut self.buffer_settings.master_password_policy;
+        ui.horizontal(|ui| {
+            let by_score = matches!(policy, MasterPasswordPolicy::MinScore(_));
+            if ui.radio(by_score, "По оценке").clicked() && !by_score {
+                *policy = MasterPasswordPolicy::MinScore(2);
+            }
+            if ui.radio(!by_score, "По энтропии").clicked() && by_score {
+                *policy = MasterPasswordPolicy::MinEntropy(50);
+            }
+        });
+        match policy {
+            MasterPasswordPolicy::MinScore(score) => {
+                let label = strength::score_label(*score);
+                ui.add(
+                    egui::Slider::new(score, 0..=4)
+                        .text(label)
+                        .show_value(false),
+                );
+            }
+            MasterPasswordPolicy::MinEntropy(bits) => {
+                ui.add(egui::DragValue::new(bits).range(0..=256).suffix(" бит"));
+            }
+        }
+        ui.label("Более слабый пароль нельзя задать при создании хранилища и смене пароля");
+
         ui.separator();
         ui.separator();
 
@@ -185,6 +227,19 @@ impl SettingsWindow {
         &self.settings
     }
 
+    // Сохраняем параметры генератора паролей сразу, минуя кнопку "Применить"
+    pub fn update_generator(
+        &mut self,
+        options: GeneratorOptions,
+        profiles: Vec<GeneratorProfile>,
+    ) -> Result<(), String> {
+        self.settings.generator_options = options.clone();
+        self.settings.generator_profiles = profiles.clone();
+        self.buffer_settings.generator_options = options;
+        self.buffer_settings.generator_profiles = profiles;
+        self.settings.save()
+    }
+
     // Обновляем список доступных тем
     pub fn refresh_themes(&mut self) {
         self.available_themes = self.settings.get_available_themes();
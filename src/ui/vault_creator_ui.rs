@@ -1,4 +1,5 @@
 use crate::storage;
+use crate::ui::master_password_ui::MasterPasswordInput;
 use eframe::egui;
 use std::path::PathBuf;
 use zeroize::Zeroize;
@@ -6,8 +7,7 @@ use zeroize::Zeroize;
 pub struct VaultCreator {
     pub show: bool,
     pub vault_name: String,
-    pub master_password: String,
-    pub confirm_password: String,
+    pub master: MasterPasswordInput,
     pub error_message: String,
     pub success_message: String,
     pub created_vault_path: Option<PathBuf>,
@@ -18,8 +18,7 @@ impl VaultCreator {
         Self {
             show: false,
             vault_name: String::new(),
-            master_password: String::new(),
-            confirm_password: String::new(),
+            master: MasterPasswordInput::new(),
             error_message: String::new(),
             success_message: String::new(),
             created_vault_path: None,
@@ -64,18 +63,10 @@ impl VaultCreator {
 
         ui.separator();
 
-        // Мастер-пароль
-        ui.label("Мастер-пароль:");
-        ui.horizontal(|ui| {
-            ui.label("🔑");
-            ui.add(egui::TextEdit::singleline(&mut self.master_password).password(true));
-        });
-
-        ui.label("Подтвердите пароль:");
-        ui.horizontal(|ui| {
-            ui.label("🔑");
-            ui.add(egui::TextEdit::singleline(&mut self.confirm_password).password(true));
-        });
+        // Мастер-пароль с оценкой надёжности
+        if self.master.ui(ui) {
+            self.create_vault();
+        }
 
         ui.separator();
 
@@ -88,6 +79,7 @@ impl VaultCreator {
             if ui.button("❌ Отмена").clicked() {
                 self.show = false;
                 self.clear_messages();
+                self.master.zeroize();
             }
         });
     }
@@ -102,14 +94,15 @@ impl VaultCreator {
             return;
         }
 
-        if self.master_password.is_empty() {
-            self.error_message = "Введите мастер-пароль".to_string();
-            return;
-        }
-
-        if self.master_password != self.confirm_password {
-            self.error_message = "Пароли не совпадают".to_string();
-            return;
+        // Пустой, несовпадающий или не отвечающий требованиям пароль не принимаем,
+        // слабый — только после подтверждения
+        match self.master.validate() {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                self.error_message = e;
+                return;
+            }
         }
 
         // Создаем хранилище
@@ -126,15 +119,14 @@ impl VaultCreator {
         if self.save_new_vault().is_ok() {
             self.success_message = format!("Хранилище '{}' успешно создано!", self.vault_name);
             // Очищаем пароли!
-            self.master_password.zeroize();
-            self.confirm_password.zeroize();
+            self.master.zeroize();
         }
     }
 
     // Сохраняем новое хранилище
     fn save_new_vault(&self) -> Result<PathBuf, String> {
         // Создаем новое хранилище
-        storage::create_new_vault(self.vault_name.clone(), &self.master_password)?;
+        storage::create_new_vault(self.vault_name.clone(), &self.master.password)?;
 
         // Получаем путь до созданного хранилища
         storage::get_vault_path(&self.vault_name)
@@ -0,0 +1,75 @@
+use crate::strength::Estimate;
+use eframe::egui;
+
+// Даты показываются в интерфейсе, но разбираются и импортёрами
+pub use crate::time::{format_date, format_time, parse_date};
+
+// Кольцо обратного отсчёта до смены TOTP-кода
+pub fn countdown_ring(ui: &mut egui::Ui, fraction: f32) -> egui::Response {
+    let size = ui.spacing().interact_size.y;
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+    let painter = ui.painter();
+    let center = rect.center();
+    let radius = size * 0.4;
+    let visuals = ui.visuals();
+
+    painter.circle_stroke(
+        center,
+        radius,
+        egui::Stroke::new(2.0, visuals.widgets.noninteractive.bg_stroke.color),
+    );
+
+    // Цвет кольца меняется, когда до смены кода остаётся мало времени
+    let color = if fraction < 0.2 {
+        visuals.error_fg_color
+    } else {
+        visuals.hyperlink_color
+    };
+    let segments = 32;
+    let points: Vec<egui::Pos2> = (0..=segments)
+        .map(|i| {
+            let angle = -std::f32::consts::FRAC_PI_2
+                + std::f32::consts::TAU * fraction * i as f32 / segments as f32;
+            center + radius * egui::vec2(angle.cos(), angle.sin())
+        })
+        .collect();
+    painter.add(egui::Shape::line(points, egui::Stroke::new(2.5, color)));
+
+    response
+}
+
+// Цвет оценки надёжности пароля (0…4)
+pub fn strength_color(ui: &egui::Ui, score: u8) -> egui::Color32 {
+    match score {
+        0 | 1 => ui.visuals().error_fg_color,
+        2 => ui.visuals().warn_fg_color,
+        3 => egui::Color32::from_rgb(140, 190, 70),
+        _ => egui::Color32::from_rgb(80, 180, 80),
+    }
+}
+
+// Полоска надёжности пароля с предупреждением и советами
+pub fn strength_meter(ui: &mut egui::Ui, estimate: &Estimate) {
+    let color = strength_color(ui, estimate.score);
+    ui.add(
+        egui::ProgressBar::new((estimate.score as f32 + 1.0) / 5.0)
+            .desired_width(260.0)
+            .fill(color)
+            .text(estimate.label()),
+    )
+    .on_hover_text(format!(
+        "≈10^{:.0} попыток, подбор займёт: {}",
+        estimate.guesses_log10,
+        estimate.crack_time()
+    ));
+    if !estimate.warning.is_empty() {
+        ui.colored_label(color, format!("⚠ {}", estimate.warning));
+    }
+    for suggestion in &estimate.suggestions {
+        ui.label(
+            egui::RichText::new(format!("• {}", suggestion))
+                .small()
+                .weak(),
+        );
+    }
+}
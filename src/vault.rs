@@ -1,14 +1,185 @@
+use crate::policy::{PasswordPolicy, SitePolicy};
+use crate::totp::OtpConfig;
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 #[allow(unused_imports)]
 use zeroize::Zeroize;
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Entry {
     pub service: String,
     pub login: String,
-    #[serde(skip)]
+    #[serde(default)]
     pub password: String,
+    #[serde(default)]
+    pub otp: Option<OtpConfig>,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub folder: String, // путь вида "work/ops"
+    #[serde(default)]
+    pub created_at: Option<SystemTime>,
+    #[serde(default)]
+    pub modified_at: Option<SystemTime>,
+    #[serde(default)]
+    pub password_changed_at: Option<SystemTime>,
+    #[serde(default)]
+    pub last_used_at: Option<SystemTime>,
+    #[serde(default)]
+    pub use_count: u32, // сколько раз копировался пароль или код
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub pinned: bool, // закреплённые записи всегда вверху списка
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub policy: Option<PasswordPolicy>, // требования сайта к паролю
+    #[serde(default)]
+    pub expiry: Option<Expiry>,
+    #[serde(default)]
+    pub password_history: Vec<OldPassword>, // прежние пароли, новые в конце
+    #[serde(default)]
+    pub fields: Vec<CustomField>, // дополнительные поля: секретный вопрос, PIN…
+}
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+// За сколько до срока запись помечается как «скоро истекает»
+pub const EXPIRY_WARNING: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+// Сколько прежних паролей хранить в истории записи
+pub const PASSWORD_HISTORY_LIMIT: usize = 10;
+
+// Метки для видов записей других менеджеров паролей, которых нет в модели хранилища
+pub const TAG_NOTE: &str = "заметка";
+pub const TAG_CARD: &str = "карта";
+pub const TAG_IDENTITY: &str = "личность";
+pub const TAG_SSH_KEY: &str = "ssh-ключ";
+
+// Срок действия пароля: конкретная дата или регулярная смена
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Expiry {
+    Date(SystemTime),
+    Interval(u32), // менять каждые N дней
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExpiryStatus {
+    Soon,
+    Expired,
+}
+
+// Пароль, который использовался раньше
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct OldPassword {
+    pub password: String,
+    pub replaced_at: Option<SystemTime>,
+}
+
+impl zeroize::Zeroize for OldPassword {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+    }
+}
+
+// Дополнительное поле записи
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub protected: bool, // скрывать значение, как пароль
+}
+
+impl zeroize::Zeroize for CustomField {
+    fn zeroize(&mut self) {
+        self.name.zeroize();
+        self.value.zeroize();
+    }
+}
+
+impl Entry {
+    // Когда пароль менялся в последний раз (у старых записей — время изменения или создания)
+    pub fn password_changed(&self) -> Option<SystemTime> {
+        self.password_changed_at
+            .or(self.modified_at)
+            .or(self.created_at)
+    }
+
+    // Когда пароль нужно сменить
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        match self.expiry? {
+            Expiry::Date(date) => Some(date),
+            Expiry::Interval(days) => self.password_changed().map(|changed| changed + DAY * days),
+        }
+    }
+
+    // Истёк ли срок пароля или истечёт ли он в ближайшие дни
+    pub fn expiry_status(&self, now: SystemTime) -> Option<ExpiryStatus> {
+        let expires_at = self.expires_at()?;
+        if expires_at <= now {
+            Some(ExpiryStatus::Expired)
+        } else if expires_at <= now + EXPIRY_WARNING {
+            Some(ExpiryStatus::Soon)
+        } else {
+            None
+        }
+    }
+
+    // Новый пароль: прежний уходит в историю, срок действия отсчитывается заново.
+    // Наступивший или близкий срок-дата считается выполненным и снимается
+    pub fn set_password(&mut self, password: String) {
+        if password == self.password {
+            return;
+        }
+        let now = SystemTime::now();
+        if !self.password.is_empty() {
+            self.password_history.push(OldPassword {
+                password: std::mem::take(&mut self.password),
+                replaced_at: Some(now),
+            });
+            if self.password_history.len() > PASSWORD_HISTORY_LIMIT {
+                let excess = self.password_history.len() - PASSWORD_HISTORY_LIMIT;
+                self.password_history
+                    .drain(..excess)
+                    .for_each(|mut old| old.zeroize());
+            }
+        }
+        self.password.zeroize();
+        self.password = password;
+        self.password_changed_at = Some(now);
+        if let Some(Expiry::Date(date)) = self.expiry
+            && date <= now + EXPIRY_WARNING
+        {
+            self.expiry = None;
+        }
+    }
+}
+
+// Теги вводятся через запятую
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = input
+        .split(',')
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.dedup();
+    tags
+}
+
+// Папка хранится как путь без лишних разделителей: "work/ops"
+pub fn normalize_folder(input: &str) -> String {
+    input
+        .split('/')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 // Реализуем Zeroize для Entry
@@ -17,9 +188,156 @@ impl zeroize::Zeroize for Entry {
         self.service.zeroize();
         self.login.zeroize();
         self.password.zeroize();
+        self.otp.zeroize();
+        self.url.zeroize();
+        self.notes.zeroize();
+        self.tags.zeroize();
+        self.folder.zeroize();
+        self.attachments.zeroize();
+        self.password_history.zeroize();
+        self.fields.zeroize();
+    }
+}
+
+// Вложенный в запись файл (хранится зашифрованным вместе с хранилищем)
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Attachment {
+    pub name: String,
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl zeroize::Zeroize for Attachment {
+    fn zeroize(&mut self) {
+        self.name.zeroize();
+        self.data.zeroize();
+    }
+}
+
+// Двоичные данные в JSON храним строкой base64, а не массивом чисел
+mod base64_bytes {
+    use base64::{Engine as _, engine::general_purpose};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&general_purpose::STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+// Запись в корзине с моментом удаления
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashedEntry {
+    pub entry: Entry,
+    pub deleted_at: SystemTime,
+}
+
+impl zeroize::Zeroize for TrashedEntry {
+    fn zeroize(&mut self) {
+        self.entry.zeroize();
+    }
+}
+
+// Колонки таблицы записей
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Column {
+    Service,
+    Login,
+    Password,
+    Url,
+    Folder,
+    Tags,
+    Modified,
+    Strength,
+    Totp,
+    LastUsed,
+    UseCount,
+    Expiry,
+}
+
+impl Column {
+    pub const ALL: [Column; 12] = [
+        Column::Service,
+        Column::Login,
+        Column::Password,
+        Column::Url,
+        Column::Folder,
+        Column::Tags,
+        Column::Modified,
+        Column::Strength,
+        Column::Totp,
+        Column::LastUsed,
+        Column::UseCount,
+        Column::Expiry,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Service => "🌐 Сервис",
+            Column::Login => "👤 Логин",
+            Column::Password => "🔑 Пароль",
+            Column::Url => "🔗 URL",
+            Column::Folder => "📁 Папка",
+            Column::Tags => "🏷️ Теги",
+            Column::Modified => "🕒 Изменено",
+            Column::Strength => "💪 Надёжность",
+            Column::Totp => "🔢 2FA",
+            Column::LastUsed => "🕘 Использовано",
+            Column::UseCount => "📈 Частота",
+            Column::Expiry => "⏳ Срок",
+        }
+    }
+}
+
+// Настройки таблицы, сохраняемые внутри хранилища
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TableView {
+    pub columns: Vec<Column>,
+    pub sort_column: Option<Column>,
+    pub sort_ascending: bool,
+}
+
+impl Default for TableView {
+    fn default() -> Self {
+        Self {
+            columns: vec![
+                Column::Service,
+                Column::Login,
+                Column::Password,
+                Column::Totp,
+            ],
+            sort_column: None,
+            sort_ascending: true,
+        }
     }
 }
 
+// Расшифрованное содержимое хранилища
+#[derive(Serialize, Deserialize, Default)]
+pub struct VaultData {
+    pub entries: Vec<Entry>,
+    #[serde(default)]
+    pub trash: Vec<TrashedEntry>,
+    #[serde(default)]
+    pub table_view: TableView,
+    #[serde(default)]
+    pub site_policies: Vec<SitePolicy>,
+}
+
+// Хранилища версии 1 содержали только массив записей
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VaultPayload {
+    Data(VaultData),
+    Legacy(Vec<Entry>),
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct VaultHeader {
     pub version: u32,
@@ -52,7 +370,7 @@ impl Vault {
         }
     }
 
-    pub fn decrypt_entries(&self, master_password: &str) -> Result<Vec<Entry>, String> {
+    pub fn decrypt(&self, master_password: &str) -> Result<VaultData, String> {
         use crate::crypto::*;
         use base64::{Engine as _, engine::general_purpose};
 
@@ -91,7 +409,11 @@ impl Vault {
 
         // Десериализуем записи
         match serde_json::from_slice(&plaintext) {
-            Ok(entries) => Ok(entries),
+            Ok(VaultPayload::Data(data)) => Ok(data),
+            Ok(VaultPayload::Legacy(entries)) => Ok(VaultData {
+                entries,
+                ..VaultData::default()
+            }),
             Err(_) => Err("Неверный формат записей в хранилище".to_string()),
         }
     }